@@ -0,0 +1,236 @@
+## Hauptmenü
+
+menu-continue = Fortsetzen
+menu-new-run = Neuer Durchlauf
+menu-settings = Einstellungen
+menu-compendium = Kompendium
+menu-quit = Beenden
+menu-seed = Seed: { $seed }_
+menu-seed-random = Seed: zufällig
+menu-seed-hint = Gib einen Seed ein, um einen Durchlauf zu wiederholen
+menu-random-seed = Zufälliger Seed
+menu-difficulty = Schwierigkeit: { $difficulty }
+menu-character = Charakter: { $character }
+menu-start = Start
+menu-back = Zurück
+
+compendium-enemies = Gegner
+compendium-power-ups = Verbesserungen
+compendium-abilities = Fähigkeiten: { $abilities }
+
+difficulty-easy = Leicht
+difficulty-normal = Normal
+difficulty-hard = Schwer
+
+starting-character-brawler = Raufbold
+starting-character-archer = Bogenschütze
+starting-character-guardian = Wächter
+
+## Einstellungen
+
+settings-title = Einstellungen
+settings-resolution = Auflösung
+settings-fullscreen = Vollbild
+settings-msaa = Kantenglättung
+settings-music-volume = Musiklautstärke
+settings-sfx-volume = Effektlautstärke
+settings-animation-speed = Animationsgeschwindigkeit
+settings-palette = Farbpalette
+settings-language = Sprache
+settings-on = An
+settings-off = Aus
+settings-reset-bindings = Tastenbelegung zurücksetzen
+settings-close = Schließen
+settings-press-key = Taste drücken...
+settings-key-taken = Belegt mit { $action }, andere Taste drücken...
+
+palette-default = Standard
+palette-deuteranopia = Deuteranopie
+palette-protanopia = Protanopie
+palette-tritanopia = Tritanopie
+palette-high-contrast = Hoher Kontrast
+
+animation-speed-normal = normal
+animation-speed-fast = schnell
+animation-speed-instant = sofort
+
+action-hotkey = Taste { $number }
+action-cursor-up = Cursor hoch
+action-cursor-down = Cursor runter
+action-cursor-left = Cursor links
+action-cursor-right = Cursor rechts
+action-next-target = Nächstes Ziel
+action-previous-target = Vorheriges Ziel
+action-confirm = Bestätigen
+action-cancel = Abbrechen
+action-undo = Rückgängig
+action-damage-breakdown = Schadensaufschlüsselung
+action-animation-speed = Animationsgeschwindigkeit
+action-pan-up = Ansicht hoch
+action-pan-down = Ansicht runter
+action-pan-left = Ansicht links
+action-pan-right = Ansicht rechts
+
+## Kampf
+
+battle-choosing-ability = Wähle eine Fähigkeit oder beende deinen Zug
+battle-targeting = Wähle ein Ziel ({ $next } oder { $cursor } und { $confirm }, { $cancel } oder RMT zum Abbrechen)
+battle-enemy-turn = Zug des Gegners
+battle-resolving = Fähigkeit wird ausgeführt ({ $speed } ändert die Geschwindigkeit)
+battle-log = Kampfprotokoll
+battle-log-filter-damage = Schaden
+battle-log-filter-my-units = Meine Einheiten
+battle-hotkey = [{ $number }] { $label }
+battle-undo = Rückgängig ({ $keys })
+battle-end-turn = Zug beenden
+battle-ability-on = { $ability } auf { $target }
+battle-miss = Verfehlt
+
+inspect-unit = { $category }, { $group }
+inspect-attributes = Werte
+inspect-abilities = Fähigkeiten
+inspect-ability = { $ability } (Reichweite { $range })
+inspect-statuses = Zustände
+inspect-status = { $attribute } { $modifier }
+
+category-human = Mensch
+category-fungus = Pilz
+group-player = Spieler
+group-enemy = Gegner
+
+## Kampfprotokoll
+
+log-moved = { $unit } ging von ({ $from-x }, { $from-y }) nach ({ $to-x }, { $to-y })
+log-damaged = { $actor } setzte { $ability } auf { $target } ein und verursachte { $damage } { $damage-type } Schaden{ $critical ->
+        [yes] {" "}(kritisch)
+       *[no] {""}
+    }{ $shield ->
+        [0] {""}
+       *[other] , { $shield } vom Schild abgefangen
+    }. TP von { $target }: { $hp }
+log-missed = { $actor } setzte { $ability } auf { $target } ein, verfehlte aber
+log-status-applied = { $actor } setzte { $ability } auf { $target } ein: { $attribute } { $change } für { $duration ->
+        [one] { $duration } Zug
+       *[other] { $duration } Züge
+    }. { $attribute } von { $target } ging von { $before } auf { $after }{ $floored ->
+        [yes] {" "}(niedriger geht es nicht)
+       *[no] {""}
+    }
+log-status-expired = { $attribute } { $change } von { $source } bei { $unit } ist abgeklungen
+log-waited = { $unit } wartet
+log-undone = { $unit } nahm { $ability } zurück
+log-died = { $unit } besiegt!
+log-unseen-unit = Jemand im Nebel
+log-damage-breakdown-on = Schadensaufschlüsselung aktiviert
+log-damage-breakdown-off = Schadensaufschlüsselung deaktiviert
+log-animation-speed = Animationsgeschwindigkeit { $speed }
+
+## Fähigkeiten und Schaden
+
+ability-kind-movement = Bewegung
+ability-kind-melee = Nahkampf, geht neben das Ziel
+ability-kind-ranged = Fernkampf
+ability-deals-damage = Verursacht { $potency } { $damage-type } Schaden
+ability-modifies = { $attribute } { $change } für { $duration ->
+        [one] { $duration } Zug
+       *[other] { $duration } Züge
+    } ({ $stacking })
+ability-stacks = bis zu { $max }-mal stapelbar
+ability-refreshes = wird erneuert
+ability-range = Reichweite: { $range }
+ability-targets = Ziele: { $targets }
+target-empty = leeres Feld
+target-ally = Verbündeter
+target-enemy = Gegner
+
+damage-type-physical = physischen
+damage-type-spore = Sporen-
+
+damage-stage-potency = Grundstärke: { $potency }
+damage-stage-scaled = nach Angriff/Verteidigung: { $damage }
+damage-stage-resisted = nach { $resistance }% { $damage-type } Resistenz: { $damage }
+damage-stage-missed = verfehlt ({ $chance }% Trefferchance)
+damage-stage-hit = Treffer ({ $chance }% Trefferchance): { $damage }
+damage-stage-crit = kritischer Treffer ({ $chance }% Chance): { $damage }
+damage-stage-shield = vom Schild abgefangen: { $damage }
+damage-stage-hp = verlorene Trefferpunkte: { $damage }
+
+forecast-miss = { $chance }% Fehlschlag
+forecast-hit = { $chance }% Treffer: { $damage } Schaden, TP { $hp }
+forecast-crit = { $chance }% kritisch: { $damage } Schaden, TP { $hp }
+forecast-attribute = { $attribute }: { $before } -> { $after }
+
+## Werte
+
+attribute-hit-points = Trefferpunkte
+attribute-attack = Angriff
+attribute-defense = Verteidigung
+attribute-speed = Tempo
+attribute-accuracy = Genauigkeit
+attribute-evasion = Ausweichen
+attribute-crit-chance = Kritische Chance
+attribute-energy = Energie
+attribute-shield = Schild
+attribute-vision = Sicht
+
+attribute-value = { $attribute }: { $value }
+attribute-gauge = { $attribute }: { $value }/{ $max }
+attribute-base = Basis { $base }
+attribute-change = { $attribute } { $change }
+
+modifier = { $change } durch { $source }
+modifier-turns = ({ $turns ->
+        [one] { $turns } Zug
+       *[other] { $turns } Züge
+    })
+source-power-up = Verbesserung „{ $power-up }“
+source-ability = { $ability } von { $caster }
+
+## Verbesserungen
+
+power-up-pick = Wähle eine Verbesserung!
+power-up-main-effect = Haupteffekt: { $effect }
+power-up-side-effect = Nebeneffekt: { $effect }
+power-up-learn = lernt { $ability }
+
+## Kampfende
+
+resolution-victory = Sieg!
+resolution-defeat = Du hast verloren!
+resolution-continue = Weiter
+resolution-main-menu = Zurück zum Hauptmenü
+resolution-run-total = Gesamter Durchlauf
+
+stats-unit = { $unit }: { $dealt } verursacht, { $taken } erlitten, { $kills } besiegt, { $turns } Züge, { $abilities } Fähigkeiten eingesetzt, { $overkill } Überschaden
+stats-battles = Kämpfe: { $count }
+stats-turns = Züge: { $count }
+stats-damage-dealt = Verursachter Schaden: { $count }
+stats-damage-taken = Erlittener Schaden: { $count }
+stats-kills = Besiegte Gegner: { $count }
+stats-abilities-used = Eingesetzte Fähigkeiten: { $count }
+stats-overkill = Überschaden: { $count }
+
+## Namen der Dateneinträge, nach ihren Ids nachgeschlagen
+
+ability-move = Bewegen
+ability-hit = Schlag
+ability-shoot = Schuss
+ability-slam = Hieb
+ability-spores = Sporen
+ability-weaken = Schwächen
+ability-sunder = Spalten
+ability-harden = Verhärten
+
+character-player = Spieler
+character-mushroom = Pilz
+character-wideshroom = Breitpilz
+character-purpleshroom = Lilapilz
+character-manyshroom = Vielpilz
+
+power-up-slam = Hieb
+power-up-shoot = Schuss
+power-up-weaken = Schwächen
+power-up-harden = Verhärten
+power-up-raise-hit-points = Mehr Trefferpunkte
+power-up-raise-attack = Mehr Angriff
+power-up-raise-defense = Mehr Verteidigung
@@ -0,0 +1,236 @@
+## Main menu
+
+menu-continue = Continue
+menu-new-run = New Run
+menu-settings = Settings
+menu-compendium = Compendium
+menu-quit = Quit
+menu-seed = Seed: { $seed }_
+menu-seed-random = Seed: random
+menu-seed-hint = Type a seed to replay a run
+menu-random-seed = Random seed
+menu-difficulty = Difficulty: { $difficulty }
+menu-character = Character: { $character }
+menu-start = Start
+menu-back = Back
+
+compendium-enemies = Enemies
+compendium-power-ups = Power ups
+compendium-abilities = Abilities: { $abilities }
+
+difficulty-easy = Easy
+difficulty-normal = Normal
+difficulty-hard = Hard
+
+starting-character-brawler = Brawler
+starting-character-archer = Archer
+starting-character-guardian = Guardian
+
+## Settings
+
+settings-title = Settings
+settings-resolution = Resolution
+settings-fullscreen = Fullscreen
+settings-msaa = Anti-aliasing
+settings-music-volume = Music volume
+settings-sfx-volume = Effects volume
+settings-animation-speed = Animation speed
+settings-palette = Colour palette
+settings-language = Language
+settings-on = On
+settings-off = Off
+settings-reset-bindings = Reset key bindings
+settings-close = Close
+settings-press-key = Press a key...
+settings-key-taken = Taken by { $action }, press another key...
+
+palette-default = Default
+palette-deuteranopia = Deuteranopia
+palette-protanopia = Protanopia
+palette-tritanopia = Tritanopia
+palette-high-contrast = High contrast
+
+animation-speed-normal = normal
+animation-speed-fast = fast
+animation-speed-instant = instant
+
+action-hotkey = Button { $number }
+action-cursor-up = Cursor up
+action-cursor-down = Cursor down
+action-cursor-left = Cursor left
+action-cursor-right = Cursor right
+action-next-target = Next target
+action-previous-target = Previous target
+action-confirm = Confirm
+action-cancel = Cancel
+action-undo = Undo
+action-damage-breakdown = Damage breakdown
+action-animation-speed = Animation speed
+action-pan-up = Pan up
+action-pan-down = Pan down
+action-pan-left = Pan left
+action-pan-right = Pan right
+
+## Battle
+
+battle-choosing-ability = Select an ability or end your turn
+battle-targeting = Select a target ({ $next } or { $cursor } and { $confirm }, { $cancel } or RMB to cancel)
+battle-enemy-turn = Enemy's turn
+battle-resolving = Resolving an ability ({ $speed } to change the speed)
+battle-log = Battle Log
+battle-log-filter-damage = Damage
+battle-log-filter-my-units = My units
+battle-hotkey = [{ $number }] { $label }
+battle-undo = Undo ({ $keys })
+battle-end-turn = End turn
+battle-ability-on = { $ability } on { $target }
+battle-miss = Miss
+
+inspect-unit = { $category }, { $group }
+inspect-attributes = Attributes
+inspect-abilities = Abilities
+inspect-ability = { $ability } (range { $range })
+inspect-statuses = Statuses
+inspect-status = { $attribute } { $modifier }
+
+category-human = Human
+category-fungus = Fungus
+group-player = Player
+group-enemy = Enemy
+
+## Battle log
+
+log-moved = { $unit } moved from ({ $from-x }, { $from-y }) to ({ $to-x }, { $to-y })
+log-damaged = { $actor } used { $ability } on { $target }, dealing { $damage } { $damage-type } dmg{ $critical ->
+        [yes] {" "}(critical)
+       *[no] {""}
+    }{ $shield ->
+        [0] {""}
+       *[other] , { $shield } absorbed by shield
+    }. { $target } HP changed to: { $hp }
+log-missed = { $actor } used { $ability } on { $target }, but missed
+log-status-applied = { $actor } used { $ability } on { $target }: { $attribute } { $change } for { $duration ->
+        [one] { $duration } turn
+       *[other] { $duration } turns
+    }. { $target } { $attribute } changed from { $before } to { $after }{ $floored ->
+        [yes] {" "}(can't go lower)
+       *[no] {""}
+    }
+log-status-expired = { $unit }'s { $attribute } { $change } from { $source } wore off
+log-waited = { $unit } waits
+log-undone = { $unit } took back { $ability }
+log-died = { $unit } defeated!
+log-unseen-unit = Someone in the fog
+log-damage-breakdown-on = Damage breakdown enabled
+log-damage-breakdown-off = Damage breakdown disabled
+log-animation-speed = Animation speed { $speed }
+
+## Abilities and damage
+
+ability-kind-movement = Movement
+ability-kind-melee = Melee, moves next to the target
+ability-kind-ranged = Ranged
+ability-deals-damage = Deals { $potency } { $damage-type } damage
+ability-modifies = { $attribute } { $change } for { $duration ->
+        [one] { $duration } turn
+       *[other] { $duration } turns
+    } ({ $stacking })
+ability-stacks = stacks up to { $max } times
+ability-refreshes = refreshes
+ability-range = Range: { $range }
+ability-targets = Targets: { $targets }
+target-empty = empty tile
+target-ally = ally
+target-enemy = enemy
+
+damage-type-physical = physical
+damage-type-spore = spore
+
+damage-stage-potency = base potency: { $potency }
+damage-stage-scaled = after attack/defense: { $damage }
+damage-stage-resisted = after { $resistance }% { $damage-type } resistance: { $damage }
+damage-stage-missed = missed ({ $chance }% to hit)
+damage-stage-hit = hit ({ $chance }% to hit): { $damage }
+damage-stage-crit = critical hit ({ $chance }% to crit): { $damage }
+damage-stage-shield = absorbed by shield: { $damage }
+damage-stage-hp = hit points lost: { $damage }
+
+forecast-miss = { $chance }% miss
+forecast-hit = { $chance }% hit: { $damage } dmg, HP { $hp }
+forecast-crit = { $chance }% crit: { $damage } dmg, HP { $hp }
+forecast-attribute = { $attribute }: { $before } -> { $after }
+
+## Attributes
+
+attribute-hit-points = Hit points
+attribute-attack = Attack
+attribute-defense = Defense
+attribute-speed = Speed
+attribute-accuracy = Accuracy
+attribute-evasion = Evasion
+attribute-crit-chance = Critical chance
+attribute-energy = Energy
+attribute-shield = Shield
+attribute-vision = Vision
+
+attribute-value = { $attribute }: { $value }
+attribute-gauge = { $attribute }: { $value }/{ $max }
+attribute-base = base { $base }
+attribute-change = { $attribute } { $change }
+
+modifier = { $change } from { $source }
+modifier-turns = ({ $turns ->
+        [one] { $turns } turn
+       *[other] { $turns } turns
+    })
+source-power-up = power up "{ $power-up }"
+source-ability = { $caster }'s { $ability }
+
+## Power ups
+
+power-up-pick = Pick a power up!
+power-up-main-effect = Main effect: { $effect }
+power-up-side-effect = Side effect: { $effect }
+power-up-learn = learn { $ability }
+
+## Battle end
+
+resolution-victory = Victory!
+resolution-defeat = You lost!
+resolution-continue = Continue
+resolution-main-menu = Back to main menu
+resolution-run-total = Run total
+
+stats-unit = { $unit }: { $dealt } dealt, { $taken } taken, { $kills } kills, { $turns } turns, { $abilities } abilities used, { $overkill } overkill
+stats-battles = Battles fought: { $count }
+stats-turns = Turns: { $count }
+stats-damage-dealt = Damage dealt: { $count }
+stats-damage-taken = Damage taken: { $count }
+stats-kills = Kills: { $count }
+stats-abilities-used = Abilities used: { $count }
+stats-overkill = Overkill: { $count }
+
+## Names of the data entries, looked up by their ids
+
+ability-move = move
+ability-hit = hit
+ability-shoot = shoot
+ability-slam = slam
+ability-spores = spores
+ability-weaken = weaken
+ability-sunder = sunder
+ability-harden = harden
+
+character-player = player
+character-mushroom = mushroom
+character-wideshroom = wideshroom
+character-purpleshroom = purpleshroom
+character-manyshroom = manyshroom
+
+power-up-slam = slam
+power-up-shoot = shoot
+power-up-weaken = weaken
+power-up-harden = harden
+power-up-raise-hit-points = Raise hit points
+power-up-raise-attack = Raise attack
+power-up-raise-defense = Raise defense
@@ -1,10 +1,13 @@
 use bevy::{prelude::*, utils::HashSet};
+use fluent::fluent_args;
 use rand::seq::IteratorRandom;
 
 use crate::{
     available_power_ups::AvailablePowerUps,
-    character::{Attribute, AttributeType},
-    AppState, GameState, HOVERED_BUTTON, NORMAL_BUTTON,
+    character::{AttributeModifier, AttributeType, Character, ModifierSource},
+    locale::Locale,
+    theme::Theme,
+    AppState, GameState,
 };
 
 use super::Ability;
@@ -28,8 +31,55 @@ pub struct AvailablePowerUp {
     pub side_effect: PowerUp,
 }
 
+impl PowerUp {
+    pub fn describe(&self, locale: &Locale) -> String {
+        match self {
+            PowerUp::Ability(ability) => locale.format(
+                "power-up-learn",
+                &fluent_args!["ability" => locale.ability_name(&ability.name)],
+            ),
+            PowerUp::ChangeAttribute { r#type, value } => locale.format(
+                "attribute-change",
+                &fluent_args![
+                    "attribute" => r#type.name(locale),
+                    "change" => format!("{value:+}")
+                ],
+            ),
+        }
+    }
+}
+
+impl AvailablePowerUp {
+    pub fn apply(&self, character: &mut Character) {
+        for power_up in [&self.main_effect, &self.side_effect] {
+            match power_up {
+                PowerUp::Ability(ability) => {
+                    character
+                        .bundle
+                        .abilities
+                        .0
+                        .insert(ability.name.clone(), ability.clone());
+                }
+                PowerUp::ChangeAttribute { r#type, value } => {
+                    character
+                        .bundle
+                        .attributes
+                        .0
+                        .get_mut(r#type)
+                        .expect("Missing attribute to change")
+                        .add_modifier(AttributeModifier::flat(
+                            *value,
+                            ModifierSource::PowerUp(self.name.clone()),
+                        ));
+                }
+            }
+        }
+    }
+}
+
 pub fn interact_pick_power_up(
     available_power_ups: Res<AvailablePowerUps>,
+    theme: Res<Theme>,
     mut game_state: ResMut<GameState>,
     mut interaction_query: Query<
         (&Interaction, &PowerUpToChoose, &mut BackgroundColor),
@@ -40,10 +90,10 @@ pub fn interact_pick_power_up(
     for (interaction, button, mut color) in &mut interaction_query {
         match *interaction {
             Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
+                *color = theme.hovered_button.into();
             }
             Interaction::None => {
-                *color = NORMAL_BUTTON.into();
+                *color = theme.normal_button.into();
             }
             Interaction::Clicked => {
                 let player = game_state
@@ -52,34 +102,8 @@ pub fn interact_pick_power_up(
                     .expect("Missing player character!");
 
                 let chosen = available_power_ups.0.get(&button.0).unwrap();
-                for power_up in [&chosen.main_effect, &chosen.side_effect] {
-                    match power_up {
-                        PowerUp::Ability(ability) => {
-                            player
-                                .bundle
-                                .abilities
-                                .0
-                                .insert(ability.name.clone(), ability.clone());
-                        }
-                        PowerUp::ChangeAttribute { r#type, value } => {
-                            let change_value = value;
-                            let attr = player
-                                .bundle
-                                .attributes
-                                .0
-                                .get_mut(r#type)
-                                .expect("Missing attribute to change");
-
-                            match attr {
-                                Attribute::Value(v) => *v += change_value,
-                                Attribute::Gauge { value, max, .. } => {
-                                    *value += change_value;
-                                    *max += change_value;
-                                }
-                            }
-                        }
-                    }
-                }
+                chosen.apply(player);
+                game_state.power_ups.push(chosen.name.clone());
                 next_state.set(AppState::Battle);
             }
         }
@@ -90,13 +114,15 @@ pub fn setup_ability_screen(
     mut commands: Commands,
     game_state: Res<GameState>,
     available_power_ups: Res<AvailablePowerUps>,
+    locale: Res<Locale>,
+    theme: Res<Theme>,
     asset_server: Res<AssetServer>,
 ) {
     let mut rng = rand::thread_rng();
 
     let player = game_state
         .characters
-        .get(0)
+        .first()
         .expect("Missing player character!");
 
     let player_abilities = player
@@ -153,7 +179,7 @@ pub fn setup_ability_screen(
         .with_children(|parent| {
             parent.spawn(
                 TextBundle::from_section(
-                    "Pick a power up!",
+                    locale.get("power-up-pick"),
                     TextStyle {
                         font: asset_server.load("fonts/FiraSans-Medium.ttf"),
                         font_size: 70.0,
@@ -166,6 +192,15 @@ pub fn setup_ability_screen(
                 }),
             );
 
+            let mut attributes = player.bundle.attributes.0.iter().collect::<Vec<_>>();
+            attributes.sort_by_key(|&(typ, _)| *typ);
+
+            parent.spawn(TextBundle::from_sections(attributes.into_iter().map(
+                |(typ, attribute)| {
+                    TextSection::new(attribute.describe(*typ, &locale) + "\n", text_style.clone())
+                },
+            )));
+
             parent
                 .spawn(NodeBundle {
                     style: Style {
@@ -221,20 +256,25 @@ pub fn setup_ability_screen(
                                                         padding: UiRect::all(Val::Px(5.0)),
                                                         ..default()
                                                     },
-                                                    background_color: NORMAL_BUTTON.into(),
+                                                    background_color: theme.normal_button.into(),
                                                     ..default()
                                                 },
                                                 PowerUpToChoose(power_up_name.clone()),
                                             ))
                                             .with_children(|parent| {
                                                 parent.spawn(TextBundle::from_section(
-                                                    power_up_name.as_str(),
+                                                    locale.power_up_name(power_up_name),
                                                     button_style.clone(),
                                                 ));
                                             });
                                         parent.spawn(
                                             TextBundle::from_section(
-                                                format!("Main effect: {:#?}", power_up.main_effect),
+                                                locale.format(
+                                                    "power-up-main-effect",
+                                                    &fluent_args![
+                                                        "effect" => power_up.main_effect.describe(&locale)
+                                                    ],
+                                                ),
                                                 text_style.clone(),
                                             )
                                             .with_style(Style {
@@ -245,7 +285,12 @@ pub fn setup_ability_screen(
                                         );
                                         parent.spawn(
                                             TextBundle::from_section(
-                                                format!("Side effect: {:#?}", power_up.side_effect),
+                                                locale.format(
+                                                    "power-up-side-effect",
+                                                    &fluent_args![
+                                                        "effect" => power_up.side_effect.describe(&locale)
+                                                    ],
+                                                ),
                                                 text_style.clone(),
                                             )
                                             .with_style(Style {
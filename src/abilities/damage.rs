@@ -0,0 +1,368 @@
+use fluent::fluent_args;
+use rand::Rng;
+use serde::Serialize;
+
+use crate::{
+    character::{AttributeType, Attributes, CharacterCategory, ModifierKind},
+    locale::Locale,
+};
+
+const CRIT_MULTIPLIER: f32 = 1.5;
+/// How much more a unit does for every point of Attack over the Defense of
+/// the target, or over the default Attack for buffs and debuffs
+const ATTACK_SCALING: f32 = 1.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum DamageType {
+    Physical,
+    Spore,
+}
+
+impl DamageType {
+    pub fn name(&self, locale: &Locale) -> String {
+        locale.get(match self {
+            Self::Physical => "damage-type-physical",
+            Self::Spore => "damage-type-spore",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageRoll {
+    Miss,
+    Hit,
+    Crit,
+}
+
+/// Deterministic stages of the damage calculation. Randomness only enters
+/// through `DamageRoll`, so the same pipeline can be used both to resolve
+/// an attack and to list all of its possible outcomes.
+#[derive(Debug, Clone, Copy)]
+pub struct DamagePipeline {
+    pub potency: i32,
+    pub damage_type: DamageType,
+    /// Potency after attack/defense scaling
+    pub scaled: f32,
+    /// Resistance of the target to `damage_type` in percent
+    pub resistance: i32,
+    /// Damage after scaling and resistance
+    pub resisted: f32,
+    /// Chance to hit in percent
+    pub hit_chance: i32,
+    /// Chance of a critical hit in percent
+    pub crit_chance: i32,
+}
+
+/// Buffs and debuffs grow with the Attack of the caster like damage does,
+/// against the default Attack instead of a Defense
+pub fn scale_modifier(change: ModifierKind, caster: &Attributes) -> ModifierKind {
+    let attack = caster.value(AttributeType::Attack);
+    let factor = ATTACK_SCALING.powi(attack - AttributeType::Attack.definition().default);
+    let scale = |value: i32| (value as f32 * factor).round() as i32;
+
+    match change {
+        ModifierKind::Flat(value) => ModifierKind::Flat(scale(value)),
+        ModifierKind::Percent(value) => ModifierKind::Percent(scale(value)),
+    }
+}
+
+impl DamagePipeline {
+    pub fn new(
+        potency: i32,
+        damage_type: DamageType,
+        caster: &Attributes,
+        target: &Attributes,
+        target_category: CharacterCategory,
+    ) -> Self {
+        let attack = caster.value(AttributeType::Attack);
+        let defense = target.value(AttributeType::Defense);
+        let scaled = potency as f32 * ATTACK_SCALING.powi(attack - defense);
+
+        let resistance = target_category.resistance(damage_type);
+        let resisted = scaled * (100 - resistance) as f32 / 100.0;
+
+        let hit_chance = (caster.value(AttributeType::Accuracy)
+            - target.value(AttributeType::Evasion))
+        .clamp(0, 100);
+        let crit_chance = caster.value(AttributeType::Crit);
+
+        Self {
+            potency,
+            damage_type,
+            scaled,
+            resistance,
+            resisted,
+            hit_chance,
+            crit_chance,
+        }
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> DamageRoll {
+        if rng.gen_range(0..100) >= self.hit_chance {
+            DamageRoll::Miss
+        } else if rng.gen_range(0..100) < self.crit_chance {
+            DamageRoll::Crit
+        } else {
+            DamageRoll::Hit
+        }
+    }
+
+    /// Final damage before shields, this is the only place it gets rounded
+    pub fn damage(&self, roll: DamageRoll) -> i32 {
+        match roll {
+            DamageRoll::Miss => 0,
+            DamageRoll::Hit => self.resisted.round().max(0.0) as i32,
+            DamageRoll::Crit => (self.resisted * CRIT_MULTIPLIER).round().max(0.0) as i32,
+        }
+    }
+
+    /// Rolls the pipeline and applies the result to the target
+    pub fn resolve(&self, target: &mut Attributes, rng: &mut impl Rng) -> DamageReport {
+        self.apply(self.roll(rng), target)
+    }
+
+    /// Applies the outcome of a roll to the target, first to its shield and
+    /// then to its hit points
+    pub fn apply(&self, roll: DamageRoll, target: &mut Attributes) -> DamageReport {
+        let damage = self.damage(roll);
+
+        let shield_absorbed = target
+            .0
+            .get_mut(&AttributeType::Shield)
+            .map(|shield| -shield.change_value(-damage))
+            .unwrap_or(0);
+
+        let hit_points = target
+            .0
+            .get_mut(&AttributeType::HitPoints)
+            .expect("Missing hit points attribute");
+        let hp_damage = -hit_points.change_value(-(damage - shield_absorbed));
+
+        DamageReport {
+            pipeline: *self,
+            roll,
+            damage,
+            shield_absorbed,
+            hp_damage,
+            overkill: damage - shield_absorbed - hp_damage,
+            hp_left: hit_points.get_value(),
+        }
+    }
+
+    /// Every possible outcome against the target with its chance in percent,
+    /// without changing the target
+    pub fn forecast(&self, target: &Attributes) -> Vec<(f32, DamageReport)> {
+        let hit = self.hit_chance as f32 / 100.0;
+        let crit = self.crit_chance.clamp(0, 100) as f32 / 100.0;
+
+        [
+            (DamageRoll::Hit, hit * (1.0 - crit)),
+            (DamageRoll::Crit, hit * crit),
+            (DamageRoll::Miss, 1.0 - hit),
+        ]
+        .into_iter()
+        .filter(|(_, chance)| *chance > 0.0)
+        .map(|(roll, chance)| (chance * 100.0, self.apply(roll, &mut target.clone())))
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DamageReport {
+    pub pipeline: DamagePipeline,
+    pub roll: DamageRoll,
+    /// Damage after all stages, before shields
+    pub damage: i32,
+    pub shield_absorbed: i32,
+    pub hp_damage: i32,
+    /// Damage that didn't land because the target ran out of hit points
+    pub overkill: i32,
+    pub hp_left: i32,
+}
+
+impl DamageReport {
+    /// Human readable description of every stage of the calculation
+    pub fn stages(&self, locale: &Locale) -> Vec<String> {
+        let pipeline = &self.pipeline;
+        let mut stages = vec![
+            locale.format(
+                "damage-stage-potency",
+                &fluent_args!["potency" => pipeline.potency],
+            ),
+            locale.format(
+                "damage-stage-scaled",
+                &fluent_args!["damage" => format!("{:.1}", pipeline.scaled)],
+            ),
+            locale.format(
+                "damage-stage-resisted",
+                &fluent_args![
+                    "resistance" => pipeline.resistance,
+                    "damage-type" => pipeline.damage_type.name(locale),
+                    "damage" => format!("{:.1}", pipeline.resisted)
+                ],
+            ),
+        ];
+
+        stages.push(match self.roll {
+            DamageRoll::Miss => locale.format(
+                "damage-stage-missed",
+                &fluent_args!["chance" => pipeline.hit_chance],
+            ),
+            DamageRoll::Hit => locale.format(
+                "damage-stage-hit",
+                &fluent_args!["chance" => pipeline.hit_chance, "damage" => self.damage],
+            ),
+            DamageRoll::Crit => locale.format(
+                "damage-stage-crit",
+                &fluent_args!["chance" => pipeline.crit_chance, "damage" => self.damage],
+            ),
+        });
+
+        if self.shield_absorbed > 0 {
+            stages.push(locale.format(
+                "damage-stage-shield",
+                &fluent_args!["damage" => self.shield_absorbed],
+            ));
+        }
+        stages.push(locale.format("damage-stage-hp", &fluent_args!["damage" => self.hp_damage]));
+
+        stages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::character::{AttributeModifier, ModifierSource};
+
+    fn attributes(overrides: &[(AttributeType, i32)]) -> Attributes {
+        Attributes::with_overrides(overrides)
+    }
+
+    #[test]
+    fn small_attack_differences_matter() {
+        let caster = attributes(&[(AttributeType::Attack, 12)]);
+        let target = attributes(&[(AttributeType::Defense, 10)]);
+
+        let pipeline = DamagePipeline::new(
+            20,
+            DamageType::Physical,
+            &caster,
+            &target,
+            CharacterCategory::Human,
+        );
+
+        assert_eq!(pipeline.damage(DamageRoll::Hit), 22);
+    }
+
+    #[test]
+    fn modifiers_grow_with_attack() {
+        let change = ModifierKind::Flat(-10);
+
+        assert_eq!(scale_modifier(change, &attributes(&[])), change);
+        assert_eq!(
+            scale_modifier(change, &attributes(&[(AttributeType::Attack, 14)])),
+            ModifierKind::Flat(-12)
+        );
+        assert_eq!(
+            scale_modifier(
+                ModifierKind::Percent(10),
+                &attributes(&[(AttributeType::Attack, 6)])
+            ),
+            ModifierKind::Percent(8)
+        );
+    }
+
+    #[test]
+    fn resistance_reduces_damage() {
+        let caster = attributes(&[]);
+        let target = attributes(&[]);
+
+        let pipeline = DamagePipeline::new(
+            20,
+            DamageType::Spore,
+            &caster,
+            &target,
+            CharacterCategory::Fungus,
+        );
+
+        assert_eq!(pipeline.damage(DamageRoll::Hit), 10);
+        assert_eq!(pipeline.damage(DamageRoll::Crit), 15);
+        assert_eq!(pipeline.damage(DamageRoll::Miss), 0);
+    }
+
+    #[test]
+    fn rolls_respect_chances() {
+        let caster = attributes(&[(AttributeType::Accuracy, 100), (AttributeType::Crit, 0)]);
+        let target = attributes(&[(AttributeType::Evasion, 0)]);
+        let pipeline = DamagePipeline::new(
+            10,
+            DamageType::Physical,
+            &caster,
+            &target,
+            CharacterCategory::Human,
+        );
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            assert_eq!(pipeline.roll(&mut rng), DamageRoll::Hit);
+        }
+    }
+
+    #[test]
+    fn shield_absorbs_before_hit_points() {
+        let caster = attributes(&[(AttributeType::Accuracy, 100), (AttributeType::Crit, 0)]);
+        let mut target = attributes(&[(AttributeType::Evasion, 0)]);
+        target
+            .0
+            .get_mut(&AttributeType::Shield)
+            .unwrap()
+            .add_modifier(AttributeModifier::flat(
+                4,
+                ModifierSource::PowerUp("test".to_string()),
+            ));
+
+        let pipeline = DamagePipeline::new(
+            10,
+            DamageType::Physical,
+            &caster,
+            &target,
+            CharacterCategory::Human,
+        );
+        let report = pipeline.resolve(&mut target, &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(report.shield_absorbed, 4);
+        assert_eq!(report.hp_damage, 6);
+        assert_eq!(report.overkill, 0);
+        assert_eq!(target.0[&AttributeType::Shield].get_value(), 0);
+    }
+
+    #[test]
+    fn forecast_matches_resolution() {
+        let caster = attributes(&[(AttributeType::Crit, 20)]);
+        let target = attributes(&[]);
+        let pipeline = DamagePipeline::new(
+            30,
+            DamageType::Physical,
+            &caster,
+            &target,
+            CharacterCategory::Human,
+        );
+
+        let forecast = pipeline.forecast(&target);
+        let total = forecast.iter().map(|(chance, _)| chance).sum::<f32>();
+        assert!((total - 100.0).abs() < 0.01);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let report = pipeline.resolve(&mut target.clone(), &mut rng);
+            assert!(forecast
+                .iter()
+                .any(|(_, outcome)| outcome.roll == report.roll
+                    && outcome.damage == report.damage
+                    && outcome.hp_left == report.hp_left));
+        }
+    }
+}
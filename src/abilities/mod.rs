@@ -1,14 +1,25 @@
 pub mod choose_ability_screen;
+pub mod damage;
 
 use bevy::prelude::*;
 use enumset::{EnumSet, EnumSetType};
+use fluent::fluent_args;
 
+use crate::battle::animation::{Animation, BattleAnimations, DeferredLifecycle};
 use crate::battle::battle_field::{BattleField, Tile};
 use crate::battle::lifecycle::BattleLifecycleEvent;
-use crate::battle::log::BattleLogEvent;
-use crate::character::{Attribute, AttributeType, Attributes, CharacterName};
+use crate::battle::log::{BattleLogEvent, LogUnit};
+use crate::battle::{BattleRng, BattleState};
+use crate::character::{
+    AttributeModifier, AttributeType, Attributes, CharacterCategory, CharacterName, ModifierKind,
+    ModifierSource, Stacking,
+};
+use crate::locale::Locale;
+use crate::theme::Theme;
 use crate::AppState;
 
+use self::damage::{scale_modifier, DamagePipeline, DamageRoll, DamageType};
+
 use self::choose_ability_screen::{
     cleanup_ability_screen, interact_pick_power_up, setup_ability_screen,
 };
@@ -32,7 +43,10 @@ pub enum TurnEvent {
         by: Entity,
         on: Entity,
     },
+    /// Ends the turn without doing anything
     Pass(Entity),
+    /// Ends the turn after its abilities were used
+    EndTurn,
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +57,182 @@ pub struct Ability {
     pub range: i32,
 }
 
+impl Ability {
+    /// Whether using the ability rolls the battle RNG
+    pub fn involves_randomness(&self) -> bool {
+        matches!(
+            self.r#type,
+            AbilityType::Targeted {
+                ab_type: TargetedAbilityType::Damage { .. },
+                ..
+            }
+        )
+    }
+
+    /// Text for the ability's tooltip
+    pub fn description(&self, locale: &Locale) -> String {
+        let mut lines = vec![locale.ability_name(&self.name)];
+
+        match self.r#type {
+            AbilityType::Movement => lines.push(locale.get("ability-kind-movement")),
+            AbilityType::Targeted { ab_type, proximity } => {
+                lines.push(locale.get(match proximity {
+                    AbilityProximity::Melee => "ability-kind-melee",
+                    AbilityProximity::Ranged => "ability-kind-ranged",
+                }));
+                lines.push(match ab_type {
+                    TargetedAbilityType::Damage {
+                        potency,
+                        damage_type,
+                    } => locale.format(
+                        "ability-deals-damage",
+                        &fluent_args![
+                            "potency" => potency,
+                            "damage-type" => damage_type.name(locale)
+                        ],
+                    ),
+                    TargetedAbilityType::ModifyAttribute {
+                        at_type,
+                        change,
+                        duration,
+                        stacking,
+                    } => locale.format(
+                        "ability-modifies",
+                        &fluent_args![
+                            "attribute" => at_type.name(locale),
+                            "change" => change.to_string(),
+                            "duration" => duration,
+                            "stacking" => match stacking {
+                                Stacking::Stack { max } => {
+                                    locale.format("ability-stacks", &fluent_args!["max" => max])
+                                }
+                                Stacking::Refresh => locale.get("ability-refreshes"),
+                            }
+                        ],
+                    ),
+                });
+            }
+        }
+
+        lines.push(locale.format("ability-range", &fluent_args!["range" => self.range]));
+        lines.push(locale.format(
+            "ability-targets",
+            &fluent_args![
+                "targets" => self
+                    .target
+                    .iter()
+                    .map(|target| locale.get(match target {
+                        AbilityTargetType::Empty => "target-empty",
+                        AbilityTargetType::Ally => "target-ally",
+                        AbilityTargetType::Enemy => "target-enemy",
+                    }))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ],
+        ));
+
+        lines.join("\n")
+    }
+}
+
+fn ability_modifier(
+    ability: &Ability,
+    caster: (&str, &Attributes),
+    change: ModifierKind,
+    duration: u32,
+) -> AttributeModifier {
+    let (caster, caster_attributes) = caster;
+    AttributeModifier {
+        kind: scale_modifier(change, caster_attributes),
+        source: ModifierSource::Ability {
+            name: ability.name.clone(),
+            caster: caster.to_string(),
+        },
+        duration: Some(duration),
+    }
+}
+
+/// Outcomes of using a targeted ability on a unit, computed with the same
+/// code that resolves it
+pub fn forecast_ability(
+    ability: &Ability,
+    caster: (&CharacterName, &Attributes),
+    target: (&CharacterCategory, &Attributes),
+    locale: &Locale,
+) -> Vec<String> {
+    let (caster_name, caster_attributes) = caster;
+    let (category, target_attributes) = target;
+
+    let AbilityType::Targeted { ab_type, .. } = ability.r#type else {
+        return vec![];
+    };
+
+    match ab_type {
+        TargetedAbilityType::Damage {
+            potency,
+            damage_type,
+        } => DamagePipeline::new(
+            potency,
+            damage_type,
+            caster_attributes,
+            target_attributes,
+            *category,
+        )
+        .forecast(target_attributes)
+        .into_iter()
+        .map(|(chance, report)| {
+            let chance = format!("{chance:.0}");
+            match report.roll {
+                DamageRoll::Miss => {
+                    locale.format("forecast-miss", &fluent_args!["chance" => chance])
+                }
+                roll => locale.format(
+                    if roll == DamageRoll::Crit {
+                        "forecast-crit"
+                    } else {
+                        "forecast-hit"
+                    },
+                    &fluent_args![
+                        "chance" => chance,
+                        "damage" => report.damage,
+                        "hp" => report.hp_left
+                    ],
+                ),
+            }
+        })
+        .collect(),
+        TargetedAbilityType::ModifyAttribute {
+            at_type,
+            change,
+            duration,
+            stacking,
+        } => {
+            let Some(attribute) = target_attributes.0.get(&at_type) else {
+                return vec![];
+            };
+            let mut attribute = attribute.clone();
+            let before = attribute.get_max();
+            attribute.apply_modifier(
+                ability_modifier(
+                    ability,
+                    (&caster_name.0, caster_attributes),
+                    change,
+                    duration,
+                ),
+                stacking,
+            );
+            vec![locale.format(
+                "forecast-attribute",
+                &fluent_args![
+                    "attribute" => at_type.name(locale),
+                    "before" => before,
+                    "after" => attribute.get_max()
+                ],
+            )]
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AbilityProximity {
     Melee,
@@ -67,64 +257,112 @@ pub enum AbilityType {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TargetedAbilityType {
-    ChangeAttribute {
-        at_type: AttributeType,
+    Damage {
         potency: i32,
+        damage_type: DamageType,
+    },
+    /// Buffs or debuffs an attribute for a number of the target's turns
+    ModifyAttribute {
+        at_type: AttributeType,
+        change: ModifierKind,
+        duration: u32,
+        stacking: Stacking,
     },
 }
 
+fn tile_position(tile: Entity, tile_transforms: &Query<&Transform, With<Tile>>) -> Vec2 {
+    tile_transforms
+        .get(tile)
+        .expect("Missing tile transform")
+        .translation
+        .truncate()
+}
+
 fn move_char(
     who: Entity,
     where_to: Entity,
     battle_field: &BattleField,
-    caster_name: &str,
+    caster: &LogUnit,
     commands: &mut Commands,
     parent_query: &mut Query<&mut Parent, With<CharacterName>>,
+    tile_transforms: &Query<&Transform, With<Tile>>,
+    animations: &mut BattleAnimations,
     ev_battle_log: &mut EventWriter<BattleLogEvent>,
 ) {
     let caster_tile = parent_query.get(who).expect("Missing player tile").get();
 
     let caster_hex = battle_field
         .hex(caster_tile)
-        .expect("Missing hex for caster")
-        .to_oddr();
-    let target_hex = battle_field
-        .hex(where_to)
-        .expect("Missing hex for target")
-        .to_oddr();
-
-    ev_battle_log.send(BattleLogEvent {
-        message: format!(
-            "{caster_name} moved from ({}, {}) to ({}, {})",
-            caster_hex.x, caster_hex.y, target_hex.x, target_hex.y
-        ),
+        .expect("Missing hex for caster");
+    let target_hex = battle_field.hex(where_to).expect("Missing hex for target");
+
+    // the unit is drawn relative to its new tile while it walks there
+    let end = tile_position(where_to, tile_transforms);
+    animations.push(Animation::Walk {
+        unit: who,
+        path: battle_field
+            .walk_path(caster_hex, target_hex)
+            .unwrap_or_else(|| vec![caster_hex, target_hex])
+            .iter()
+            .filter_map(|hex| battle_field.tile(hex))
+            .map(|tile| tile_position(tile, tile_transforms) - end)
+            .collect(),
+    });
+
+    ev_battle_log.send(BattleLogEvent::Moved {
+        unit: caster.clone(),
+        from: caster_hex.to_oddr().into(),
+        to: target_hex.to_oddr().into(),
     });
     commands.entity(where_to).push_children(&[who]);
 }
 
-fn calculate_damage(potency: i32, attack: i32, defense: i32) -> i32 {
-    potency * (1.05f32.powi(attack - defense)).round() as i32
+/// Floating text above a tile
+fn floating_text(
+    text: String,
+    color: Color,
+    large: bool,
+    tile: Entity,
+    battle_field: &BattleField,
+    tile_transforms: &Query<&Transform, With<Tile>>,
+) -> Animation {
+    Animation::FloatingText {
+        text,
+        color,
+        at: (tile_position(tile, tile_transforms) + Vec2::Y * 0.3 * battle_field.tile_size())
+            .extend(30.0),
+        large,
+    }
 }
 
+/// Applies the used abilities right away and queues their animations, the
+/// battle waits for them in `BattleState::AbilityResolution`
 fn resolve_ability(
     mut commands: Commands,
     battle_field: Option<Res<BattleField>>,
     mut ev_ability: EventReader<TurnEvent>,
     mut ev_battle_log: EventWriter<BattleLogEvent>,
-    mut ev_lifecycle: EventWriter<BattleLifecycleEvent>,
+    mut deferred_lifecycle: ResMut<DeferredLifecycle>,
+    mut animations: ResMut<BattleAnimations>,
+    mut next_state: ResMut<NextState<BattleState>>,
+    mut rng: ResMut<BattleRng>,
+    locale: Res<Locale>,
+    theme: Res<Theme>,
     mut set: ParamSet<(
         Query<Option<&Children>, With<Tile>>,
         Query<(&CharacterName, &mut Attributes)>,
-        Query<(&CharacterName, &mut Attributes)>,
+        Query<(&CharacterName, &CharacterCategory, &mut Attributes)>,
     )>,
     mut parent_query: Query<&mut Parent, With<CharacterName>>,
     tile_children_query: Query<&Children, With<Tile>>,
+    tile_transforms: Query<&Transform, With<Tile>>,
 ) {
     for turn in ev_ability.iter() {
         let battle_field = battle_field
             .as_ref()
             .expect("Missing battle field")
             .as_ref();
+        next_state.set(BattleState::AbilityResolution);
 
         match turn {
             TurnEvent::Ability { ability, by, on } => {
@@ -132,17 +370,18 @@ fn resolve_ability(
                 let (caster_name, caster_attributes) =
                     char_query.get(*by).expect("Missing caster entity");
 
-                let (caster_name, caster_attributes) =
-                    (caster_name.0.clone(), caster_attributes.clone());
+                let (caster, caster_attributes) =
+                    (LogUnit::new(*by, caster_name), caster_attributes.clone());
 
                 let tile_query = set.p0();
                 let children = tile_query.get(*on).expect("Missing ability target");
 
                 match ability.r#type {
                     AbilityType::Targeted { ab_type, proximity } => {
+                        let mut caster_tile =
+                            parent_query.get(*by).expect("Missing caster tile").get();
                         if let AbilityProximity::Melee = proximity {
-                            let from_tile =
-                                parent_query.get(*by).expect("Missing caster tile").get();
+                            let from_tile = caster_tile;
                             let from = battle_field.hex(from_tile).expect("Caster hex not found");
                             let hex = battle_field.hex(*on).expect("Target hex not found");
                             if from.dist(hex) > 1 {
@@ -159,61 +398,175 @@ fn resolve_ability(
                                     *by,
                                     where_to,
                                     battle_field,
-                                    caster_name.as_str(),
+                                    &caster,
                                     &mut commands,
                                     &mut parent_query,
+                                    &tile_transforms,
+                                    &mut animations,
                                     &mut ev_battle_log,
                                 );
+                                caster_tile = where_to;
                             }
                         }
+
+                        let from = tile_position(caster_tile, &tile_transforms);
+                        let to = tile_position(*on, &tile_transforms);
+                        animations.push(match proximity {
+                            AbilityProximity::Melee => Animation::Lunge {
+                                unit: *by,
+                                towards: (to - from).normalize_or_zero()
+                                    * 0.4
+                                    * battle_field.tile_size(),
+                            },
+                            AbilityProximity::Ranged => Animation::Projectile {
+                                from: from.extend(20.0),
+                                to: to.extend(20.0),
+                            },
+                        });
+
+                        let entity = *children
+                            .expect("Expected children on a tile")
+                            .iter()
+                            .next()
+                            .expect("Expected an entity on a tile");
+
+                        let mut target_query = set.p2();
+                        let (name, category, mut target_attributes) = target_query
+                            .get_mut(entity)
+                            .expect("Didn't find target entity");
+
                         match ab_type {
-                            TargetedAbilityType::ChangeAttribute { at_type, potency } => {
-                                let entity = *children
-                                    .expect("Expected children on a tile")
-                                    .iter()
-                                    .next()
-                                    .expect("Expected an entity on a tile");
-
-                                let mut target_query = set.p2();
-                                let (name, mut target_attributes) = target_query
-                                    .get_mut(entity)
-                                    .expect("Didn't find target entity");
-                                let target_defense = target_attributes
-                                    .0
-                                    .get(&AttributeType::Defense)
-                                    .expect("Missing attack attribute")
-                                    .get_value();
+                            TargetedAbilityType::Damage {
+                                potency,
+                                damage_type,
+                            } => {
+                                let report = DamagePipeline::new(
+                                    potency,
+                                    damage_type,
+                                    &caster_attributes,
+                                    &target_attributes,
+                                    *category,
+                                )
+                                .resolve(&mut target_attributes, &mut rng.0);
+
+                                let target = LogUnit::new(entity, name);
+                                ev_battle_log.send(match report.roll {
+                                    DamageRoll::Miss => BattleLogEvent::Missed {
+                                        actor: caster.clone(),
+                                        target,
+                                        ability: ability.name.clone(),
+                                        stages: report.stages(&locale),
+                                    },
+                                    roll => BattleLogEvent::Damaged {
+                                        actor: caster.clone(),
+                                        target,
+                                        ability: ability.name.clone(),
+                                        damage_type,
+                                        damage: report.damage,
+                                        critical: roll == DamageRoll::Crit,
+                                        shield_absorbed: report.shield_absorbed,
+                                        overkill: report.overkill,
+                                        hp_left: report.hp_left,
+                                        stages: report.stages(&locale),
+                                    },
+                                });
 
+                                animations.push(match report.roll {
+                                    DamageRoll::Miss => floating_text(
+                                        locale.get("battle-miss"),
+                                        theme.miss,
+                                        false,
+                                        *on,
+                                        battle_field,
+                                        &tile_transforms,
+                                    ),
+                                    DamageRoll::Crit => floating_text(
+                                        format!("-{}!", report.damage),
+                                        theme.crit,
+                                        true,
+                                        *on,
+                                        battle_field,
+                                        &tile_transforms,
+                                    ),
+                                    _ => floating_text(
+                                        format!("-{}", report.damage),
+                                        theme.damage,
+                                        false,
+                                        *on,
+                                        battle_field,
+                                        &tile_transforms,
+                                    ),
+                                });
+
+                                if report.hp_left <= 0 {
+                                    deferred_lifecycle
+                                        .0
+                                        .push(BattleLifecycleEvent::CharacterDied(entity))
+                                }
+                            }
+                            TargetedAbilityType::ModifyAttribute {
+                                at_type,
+                                change,
+                                duration,
+                                stacking,
+                            } => {
                                 if let Some(attribute) = target_attributes.0.get_mut(&at_type) {
-                                    match attribute {
-                                        Attribute::Value(v) => {
-                                            *v -= potency;
-                                        }
-                                        Attribute::Gauge { value, min, max } => {
-                                            let final_value = calculate_damage(
-                                                potency,
-                                                caster_attributes
-                                                    .0
-                                                    .get(&AttributeType::Attack)
-                                                    .expect("Missing attack attribute")
-                                                    .get_value(),
-                                                target_defense,
-                                            );
-                                            *value = (*value - final_value).clamp(*min, *max);
-
-                                            ev_battle_log.send(BattleLogEvent {
-                                                message: format!(
-                                                "{caster_name} used {} on {}, dealing {final_value} dmg. {} HP changed to: {}",
-                                                ability.name, name.0, name.0, *value
-                                            ),
-                                            });
-
-                                            if at_type == AttributeType::HitPoints && *value <= 0 {
-                                                ev_lifecycle.send(
-                                                    BattleLifecycleEvent::CharacterDied(entity),
+                                    let before = attribute.get_max();
+                                    let modifier = ability_modifier(
+                                        ability,
+                                        (&caster.name, &caster_attributes),
+                                        change,
+                                        duration,
+                                    );
+                                    let change = modifier.kind;
+                                    attribute.apply_modifier(modifier, stacking);
+                                    let after = attribute.get_max();
+                                    let floored = after == attribute.get_min() && after < before;
+
+                                    if after != before {
+                                        let change = after - before;
+                                        animations.push(floating_text(
+                                            if at_type == AttributeType::HitPoints {
+                                                format!("{change:+}")
+                                            } else {
+                                                locale.format(
+                                                    "attribute-change",
+                                                    &fluent_args![
+                                                        "attribute" => at_type.name(&locale),
+                                                        "change" => format!("{change:+}")
+                                                    ],
                                                 )
-                                            }
-                                        }
+                                            },
+                                            if change > 0 {
+                                                theme.increase
+                                            } else {
+                                                theme.decrease
+                                            },
+                                            false,
+                                            *on,
+                                            battle_field,
+                                            &tile_transforms,
+                                        ));
+                                    }
+
+                                    ev_battle_log.send(BattleLogEvent::StatusApplied {
+                                        actor: caster.clone(),
+                                        target: LogUnit::new(entity, name),
+                                        ability: ability.name.clone(),
+                                        attribute: at_type,
+                                        change,
+                                        duration,
+                                        before,
+                                        after,
+                                        floored,
+                                    });
+
+                                    if at_type == AttributeType::HitPoints
+                                        && attribute.get_value() <= 0
+                                    {
+                                        deferred_lifecycle
+                                            .0
+                                            .push(BattleLifecycleEvent::CharacterDied(entity))
                                     }
                                 }
                             }
@@ -224,28 +577,26 @@ fn resolve_ability(
                             *by,
                             *on,
                             battle_field,
-                            caster_name.as_str(),
+                            &caster,
                             &mut commands,
                             &mut parent_query,
+                            &tile_transforms,
+                            &mut animations,
                             &mut ev_battle_log,
                         );
                     }
                 }
             }
             TurnEvent::Pass(caster) => {
-                let caster_name = set
-                    .p1()
-                    .get(*caster)
-                    .expect("Missing caster entity")
-                    .0
-                     .0
-                    .clone();
-
-                ev_battle_log.send(BattleLogEvent {
-                    message: format!("{caster_name} waits"),
+                let char_query = set.p1();
+                let (caster_name, _) = char_query.get(*caster).expect("Missing caster entity");
+
+                ev_battle_log.send(BattleLogEvent::Waited {
+                    unit: LogUnit::new(*caster, caster_name),
                 });
+                deferred_lifecycle.0.push(BattleLifecycleEvent::EndTurn)
             }
+            TurnEvent::EndTurn => deferred_lifecycle.0.push(BattleLifecycleEvent::EndTurn),
         }
-        ev_lifecycle.send(BattleLifecycleEvent::EndTurn)
     }
 }
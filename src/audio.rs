@@ -0,0 +1,422 @@
+use std::path::Path;
+
+use bevy::{
+    asset::{AssetIoError, AssetLoader, LoadContext, LoadedAsset},
+    audio::AudioSink,
+    prelude::*,
+    reflect::TypeUuid,
+    utils::{BoxedFuture, HashMap},
+};
+use serde::Deserialize;
+
+use crate::{
+    abilities::{AbilityProximity, AbilityType, TargetedAbilityType, TurnEvent},
+    battle::{lifecycle::BattleLifecycleEvent, resolution::BattleResolution, BattleState},
+    character::Group,
+    AppState,
+};
+
+const SOUNDS_PATH: &str = "audio/sounds.audio.json";
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<AudioConfig>()
+            .init_asset_loader::<AudioConfigLoader>()
+            .init_resource::<AudioVolume>()
+            .init_resource::<MusicPlayer>()
+            .add_startup_system(load_sounds)
+            .add_systems((
+                update_sound_library,
+                crossfade_music.after(update_sound_library),
+                play_event_sounds,
+            ))
+            .add_system(play_battle_end_sound.in_schedule(OnEnter(BattleState::BattleEnd)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MusicTrack {
+    Menu,
+    Battle,
+}
+
+impl MusicTrack {
+    fn for_state(state: &AppState) -> Self {
+        match state {
+            AppState::Battle => MusicTrack::Battle,
+            _ => MusicTrack::Menu,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundEffect {
+    ButtonHover,
+    ButtonClick,
+    Move,
+    MeleeAttack,
+    RangedAttack,
+    Status,
+    Pass,
+    TurnStart,
+    Death,
+    Victory,
+    Defeat,
+}
+
+impl SoundEffect {
+    #[cfg(test)]
+    const ALL: &'static [SoundEffect] = &[
+        Self::ButtonHover,
+        Self::ButtonClick,
+        Self::Move,
+        Self::MeleeAttack,
+        Self::RangedAttack,
+        Self::Status,
+        Self::Pass,
+        Self::TurnStart,
+        Self::Death,
+        Self::Victory,
+        Self::Defeat,
+    ];
+
+    fn for_turn(turn: &TurnEvent) -> Option<Self> {
+        match turn {
+            TurnEvent::Ability { ability, .. } => Some(match ability.r#type {
+                AbilityType::Movement => Self::Move,
+                AbilityType::Targeted {
+                    ab_type: TargetedAbilityType::ModifyAttribute { .. },
+                    ..
+                } => Self::Status,
+                AbilityType::Targeted {
+                    proximity: AbilityProximity::Melee,
+                    ..
+                } => Self::MeleeAttack,
+                AbilityType::Targeted {
+                    proximity: AbilityProximity::Ranged,
+                    ..
+                } => Self::RangedAttack,
+            }),
+            TurnEvent::Pass(_) => Some(Self::Pass),
+            // the next turn starting has its own sound
+            TurnEvent::EndTurn => None,
+        }
+    }
+
+    fn for_lifecycle(event: &BattleLifecycleEvent) -> Self {
+        match event {
+            BattleLifecycleEvent::CharacterDied(_) => Self::Death,
+            BattleLifecycleEvent::EndTurn => Self::TurnStart,
+        }
+    }
+}
+
+/// Sound effects and music tracks, loaded from a `.audio.json` data file
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "5d3c9f6e-8a1b-4c27-b0e4-7f2a61d9c835"]
+pub struct AudioConfig {
+    /// Seconds it takes for one music track to fade into another
+    crossfade: f32,
+    music: HashMap<MusicTrack, String>,
+    sfx: HashMap<SoundEffect, String>,
+}
+
+#[derive(Default)]
+pub struct AudioConfigLoader;
+
+impl AssetLoader for AudioConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let config: AudioConfig = serde_json::from_slice(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["audio.json"]
+    }
+}
+
+/// Volume of the music and sound effect channels, from 0 to 1
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct AudioVolume {
+    pub music: f32,
+    pub sfx: f32,
+}
+
+impl Default for AudioVolume {
+    fn default() -> Self {
+        Self {
+            music: 0.5,
+            sfx: 0.7,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct SoundLibrary {
+    config: Handle<AudioConfig>,
+    crossfade: f32,
+    music: HashMap<MusicTrack, Handle<AudioSource>>,
+    sfx: HashMap<SoundEffect, Handle<AudioSource>>,
+}
+
+impl SoundLibrary {
+    fn play(&self, audio: &Audio, volume: &AudioVolume, effect: SoundEffect) {
+        if let Some(source) = self.sfx.get(&effect) {
+            audio.play_with_settings(
+                source.clone(),
+                PlaybackSettings::ONCE.with_volume(volume.sfx),
+            );
+        }
+    }
+}
+
+struct PlayingTrack {
+    track: MusicTrack,
+    sink: Handle<AudioSink>,
+    fade: f32,
+}
+
+/// Music tracks currently playing, only the last one is fading in
+#[derive(Resource, Default)]
+pub struct MusicPlayer {
+    playing: Vec<PlayingTrack>,
+}
+
+impl MusicPlayer {
+    /// Drops the tracks which faded out, whatever they play, and returns
+    /// their sinks to be stopped
+    fn drop_faded(&mut self, wanted: MusicTrack) -> Vec<Handle<AudioSink>> {
+        let last = self.playing.len().saturating_sub(1);
+        let mut faded = vec![];
+        let mut i = 0;
+        self.playing.retain(|playing| {
+            let keep = playing.fade > 0.0 || i == last && playing.track == wanted;
+            if !keep {
+                faded.push(playing.sink.clone());
+            }
+            i += 1;
+            keep
+        });
+        faded
+    }
+}
+
+fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SoundLibrary {
+        config: asset_server.load(SOUNDS_PATH),
+        crossfade: 0.0,
+        music: HashMap::new(),
+        sfx: HashMap::new(),
+    });
+}
+
+/// Sounds listed without a file are left out, so that the game runs
+/// without them
+fn load_if_present(asset_server: &AssetServer, path: &str) -> Option<Handle<AudioSource>> {
+    match asset_server.asset_io().get_metadata(Path::new(path)) {
+        Err(AssetIoError::NotFound(_)) => {
+            debug!("No sound file at {path}, skipping it");
+            None
+        }
+        _ => Some(asset_server.load(path)),
+    }
+}
+
+/// Loads the sounds listed in the data file, again whenever it changes
+fn update_sound_library(
+    asset_server: Res<AssetServer>,
+    configs: Res<Assets<AudioConfig>>,
+    mut library: ResMut<SoundLibrary>,
+    mut ev_config: EventReader<AssetEvent<AudioConfig>>,
+) {
+    for ev in ev_config.iter() {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = ev else {
+            continue;
+        };
+        if *handle != library.config {
+            continue;
+        }
+        let Some(config) = configs.get(handle) else {
+            continue;
+        };
+
+        library.crossfade = config.crossfade;
+        library.music = config
+            .music
+            .iter()
+            .filter_map(|(track, path)| Some((*track, load_if_present(&asset_server, path)?)))
+            .collect();
+        library.sfx = config
+            .sfx
+            .iter()
+            .filter_map(|(effect, path)| Some((*effect, load_if_present(&asset_server, path)?)))
+            .collect();
+    }
+}
+
+/// Moves `fade` towards `target` by at most `step`
+fn step_fade(fade: f32, target: f32, step: f32) -> f32 {
+    if fade < target {
+        (fade + step).min(target)
+    } else {
+        (fade - step).max(target)
+    }
+}
+
+fn crossfade_music(
+    time: Res<Time>,
+    state: Res<State<AppState>>,
+    volume: Res<AudioVolume>,
+    library: Res<SoundLibrary>,
+    audio: Res<Audio>,
+    sinks: Res<Assets<AudioSink>>,
+    mut player: ResMut<MusicPlayer>,
+) {
+    let wanted = MusicTrack::for_state(&state.0);
+    if !player
+        .playing
+        .last()
+        .is_some_and(|playing| playing.track == wanted)
+    {
+        if let Some(source) = library.music.get(&wanted) {
+            let sink =
+                audio.play_with_settings(source.clone(), PlaybackSettings::LOOP.with_volume(0.0));
+            player.playing.push(PlayingTrack {
+                track: wanted,
+                sink: sinks.get_handle(sink),
+                fade: 0.0,
+            });
+        }
+    }
+
+    let step = if library.crossfade > 0.0 {
+        time.delta_seconds() / library.crossfade
+    } else {
+        1.0
+    };
+    let current = player.playing.len().saturating_sub(1);
+    for (i, playing) in player.playing.iter_mut().enumerate() {
+        let target = if i == current && playing.track == wanted {
+            1.0
+        } else {
+            0.0
+        };
+        playing.fade = step_fade(playing.fade, target, step);
+        if let Some(sink) = sinks.get(&playing.sink) {
+            sink.set_volume(volume.music * playing.fade);
+        }
+    }
+
+    for sink in player.drop_faded(wanted) {
+        if let Some(sink) = sinks.get(&sink) {
+            sink.stop();
+        }
+    }
+}
+
+fn play_event_sounds(
+    audio: Res<Audio>,
+    volume: Res<AudioVolume>,
+    library: Res<SoundLibrary>,
+    mut ev_turn: EventReader<TurnEvent>,
+    mut ev_lifecycle: EventReader<BattleLifecycleEvent>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+) {
+    let effects = ev_turn
+        .iter()
+        .filter_map(SoundEffect::for_turn)
+        .chain(ev_lifecycle.iter().map(SoundEffect::for_lifecycle))
+        .chain(
+            button_query
+                .iter()
+                .filter_map(|interaction| match interaction {
+                    Interaction::Hovered => Some(SoundEffect::ButtonHover),
+                    Interaction::Clicked => Some(SoundEffect::ButtonClick),
+                    Interaction::None => None,
+                }),
+        );
+
+    for effect in effects {
+        library.play(&audio, &volume, effect);
+    }
+}
+
+fn play_battle_end_sound(
+    audio: Res<Audio>,
+    volume: Res<AudioVolume>,
+    library: Res<SoundLibrary>,
+    resolution: Option<Res<BattleResolution>>,
+) {
+    if let Some(resolution) = resolution {
+        library.play(
+            &audio,
+            &volume,
+            match resolution.winner {
+                Group::Player => SoundEffect::Victory,
+                Group::Enemy => SoundEffect::Defeat,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_file_lists_every_sound() {
+        let config: AudioConfig =
+            serde_json::from_str(include_str!("../assets/audio/sounds.audio.json"))
+                .expect("Invalid audio data file");
+
+        assert!(config.crossfade > 0.0);
+        for effect in SoundEffect::ALL {
+            assert!(config.sfx.contains_key(effect), "{effect:?} has no sound");
+        }
+        for track in [MusicTrack::Menu, MusicTrack::Battle] {
+            assert!(config.music.contains_key(&track), "{track:?} has no music");
+        }
+    }
+
+    #[test]
+    fn faded_tracks_are_dropped_even_if_wanted_again() {
+        let track = |track, fade| PlayingTrack {
+            track,
+            sink: Handle::default(),
+            fade,
+        };
+        // back in battle before the old battle music faded out
+        let mut player = MusicPlayer {
+            playing: vec![
+                track(MusicTrack::Battle, 0.0),
+                track(MusicTrack::Menu, 0.2),
+                track(MusicTrack::Battle, 0.0),
+            ],
+        };
+
+        assert_eq!(player.drop_faded(MusicTrack::Battle).len(), 1);
+        let tracks = player.playing.iter().map(|playing| playing.track);
+        assert_eq!(
+            tracks.collect::<Vec<_>>(),
+            [MusicTrack::Menu, MusicTrack::Battle]
+        );
+    }
+
+    #[test]
+    fn fades_stop_at_the_target() {
+        assert_eq!(step_fade(0.0, 1.0, 0.4), 0.4);
+        assert_eq!(step_fade(0.8, 1.0, 0.4), 1.0);
+        assert_eq!(step_fade(0.3, 0.0, 0.4), 0.0);
+    }
+}
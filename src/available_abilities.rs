@@ -1,16 +1,13 @@
 use crate::{
-    abilities::{Ability, AbilityProximity, AbilityTargetType, AbilityType, TargetedAbilityType},
-    character::AttributeType,
+    abilities::{
+        damage::DamageType, Ability, AbilityProximity, AbilityTargetType, AbilityType,
+        TargetedAbilityType,
+    },
+    character::{AttributeType, ModifierKind, Stacking},
     InitState,
 };
 use bevy::{prelude::*, utils::HashMap};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum AbilityTier {
-    //Normal,
-    //SideEffect,
-}
-
 #[derive(Resource)]
 pub struct AvailableAbilities(pub HashMap<String, Ability>);
 
@@ -28,9 +25,9 @@ pub fn init_available_abilities(
         Ability {
             name: "hit".to_string(),
             r#type: AbilityType::Targeted {
-                ab_type: TargetedAbilityType::ChangeAttribute {
-                    at_type: AttributeType::HitPoints,
+                ab_type: TargetedAbilityType::Damage {
                     potency: 15,
+                    damage_type: DamageType::Physical,
                 },
                 proximity: AbilityProximity::Melee,
             },
@@ -40,9 +37,9 @@ pub fn init_available_abilities(
         Ability {
             name: "shoot".to_string(),
             r#type: AbilityType::Targeted {
-                ab_type: TargetedAbilityType::ChangeAttribute {
-                    at_type: AttributeType::HitPoints,
+                ab_type: TargetedAbilityType::Damage {
                     potency: 10,
+                    damage_type: DamageType::Physical,
                 },
                 proximity: AbilityProximity::Ranged,
             },
@@ -52,15 +49,69 @@ pub fn init_available_abilities(
         Ability {
             name: "slam".to_string(),
             r#type: AbilityType::Targeted {
-                ab_type: TargetedAbilityType::ChangeAttribute {
-                    at_type: AttributeType::HitPoints,
+                ab_type: TargetedAbilityType::Damage {
                     potency: 20,
+                    damage_type: DamageType::Physical,
                 },
                 proximity: AbilityProximity::Ranged,
             },
             target: AbilityTargetType::Enemy.into(),
             range: 1,
         },
+        Ability {
+            name: "spores".to_string(),
+            r#type: AbilityType::Targeted {
+                ab_type: TargetedAbilityType::Damage {
+                    potency: 12,
+                    damage_type: DamageType::Spore,
+                },
+                proximity: AbilityProximity::Ranged,
+            },
+            target: AbilityTargetType::Enemy.into(),
+            range: 3,
+        },
+        Ability {
+            name: "weaken".to_string(),
+            r#type: AbilityType::Targeted {
+                ab_type: TargetedAbilityType::ModifyAttribute {
+                    at_type: AttributeType::Attack,
+                    change: ModifierKind::Flat(-3),
+                    duration: 3,
+                    stacking: Stacking::Refresh,
+                },
+                proximity: AbilityProximity::Ranged,
+            },
+            target: AbilityTargetType::Enemy.into(),
+            range: 3,
+        },
+        Ability {
+            name: "sunder".to_string(),
+            r#type: AbilityType::Targeted {
+                ab_type: TargetedAbilityType::ModifyAttribute {
+                    at_type: AttributeType::Defense,
+                    change: ModifierKind::Flat(-2),
+                    duration: 3,
+                    stacking: Stacking::Stack { max: 3 },
+                },
+                proximity: AbilityProximity::Melee,
+            },
+            target: AbilityTargetType::Enemy.into(),
+            range: 2,
+        },
+        Ability {
+            name: "harden".to_string(),
+            r#type: AbilityType::Targeted {
+                ab_type: TargetedAbilityType::ModifyAttribute {
+                    at_type: AttributeType::Defense,
+                    change: ModifierKind::Percent(30),
+                    duration: 2,
+                    stacking: Stacking::Refresh,
+                },
+                proximity: AbilityProximity::Ranged,
+            },
+            target: AbilityTargetType::Ally.into(),
+            range: 3,
+        },
     ]
     .into_iter()
     .map(|ability| (ability.name.clone(), ability))
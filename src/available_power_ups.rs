@@ -28,7 +28,23 @@ pub fn init_available_power_ups(mut commands: Commands, abs: Res<AvailableAbilit
             },
         },
         AvailablePowerUp {
-            name: "Raise hit points".to_string(),
+            name: "weaken".to_string(),
+            main_effect: PowerUp::Ability(get_ability("weaken", &abs)),
+            side_effect: PowerUp::ChangeAttribute {
+                r#type: AttributeType::HitPoints,
+                value: -10,
+            },
+        },
+        AvailablePowerUp {
+            name: "harden".to_string(),
+            main_effect: PowerUp::Ability(get_ability("harden", &abs)),
+            side_effect: PowerUp::ChangeAttribute {
+                r#type: AttributeType::Attack,
+                value: -1,
+            },
+        },
+        AvailablePowerUp {
+            name: "raise-hit-points".to_string(),
             main_effect: PowerUp::ChangeAttribute {
                 r#type: AttributeType::HitPoints,
                 value: 10,
@@ -39,7 +55,7 @@ pub fn init_available_power_ups(mut commands: Commands, abs: Res<AvailableAbilit
             },
         },
         AvailablePowerUp {
-            name: "Raise attack".to_string(),
+            name: "raise-attack".to_string(),
             main_effect: PowerUp::ChangeAttribute {
                 r#type: AttributeType::Attack,
                 value: 2,
@@ -50,7 +66,7 @@ pub fn init_available_power_ups(mut commands: Commands, abs: Res<AvailableAbilit
             },
         },
         AvailablePowerUp {
-            name: "Raise defense".to_string(),
+            name: "raise-defense".to_string(),
             main_effect: PowerUp::ChangeAttribute {
                 r#type: AttributeType::Defense,
                 value: 2,
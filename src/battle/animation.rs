@@ -0,0 +1,382 @@
+use bevy::{prelude::*, render::view::RenderLayers};
+use fluent::fluent_args;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    character::Group,
+    input::{Actions, InputAction},
+    locale::Locale,
+    settings::Settings,
+};
+
+use super::{
+    lifecycle::{BattleLifecycleEvent, LifeState},
+    log::BattleLogEvent,
+    Battle, BattleQueue, BattleState,
+};
+
+const WALK_STEP_DURATION: f32 = 0.15;
+const LUNGE_DURATION: f32 = 0.3;
+const PROJECTILE_SPEED: f32 = 1500.0;
+const FLOATING_TEXT_DURATION: f32 = 0.7;
+/// How far floating text rises, in pixels
+const FLOATING_TEXT_RISE: f32 = 40.0;
+
+/// How fast the abilities are animated, instant skips the animations
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AnimationSpeed {
+    #[default]
+    Normal,
+    Fast,
+    Instant,
+}
+
+impl AnimationSpeed {
+    pub const ALL: [AnimationSpeed; 3] = [Self::Normal, Self::Fast, Self::Instant];
+
+    fn multiplier(self) -> Option<f32> {
+        match self {
+            AnimationSpeed::Normal => Some(1.0),
+            AnimationSpeed::Fast => Some(2.5),
+            AnimationSpeed::Instant => None,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            AnimationSpeed::Normal => AnimationSpeed::Fast,
+            AnimationSpeed::Fast => AnimationSpeed::Instant,
+            AnimationSpeed::Instant => AnimationSpeed::Normal,
+        }
+    }
+}
+
+impl AnimationSpeed {
+    pub fn name(self, locale: &Locale) -> String {
+        locale.get(match self {
+            AnimationSpeed::Normal => "animation-speed-normal",
+            AnimationSpeed::Fast => "animation-speed-fast",
+            AnimationSpeed::Instant => "animation-speed-instant",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Animation {
+    /// Walks a unit along tile positions relative to the tile it ends on
+    Walk {
+        unit: Entity,
+        path: Vec<Vec2>,
+    },
+    /// Moves a unit towards the offset and back
+    Lunge {
+        unit: Entity,
+        towards: Vec2,
+    },
+    Projectile {
+        from: Vec3,
+        to: Vec3,
+    },
+    FloatingText {
+        text: String,
+        color: Color,
+        at: Vec3,
+        large: bool,
+    },
+}
+
+impl Animation {
+    fn duration(&self) -> f32 {
+        match self {
+            Animation::Walk { path, .. } => {
+                WALK_STEP_DURATION * path.len().saturating_sub(1) as f32
+            }
+            Animation::Lunge { .. } => LUNGE_DURATION,
+            Animation::Projectile { from, to } => 0.1 + from.distance(*to) / PROJECTILE_SPEED,
+            Animation::FloatingText { .. } => FLOATING_TEXT_DURATION,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct AnimationStep {
+    animation: Animation,
+    start: f32,
+    duration: f32,
+    spawned: Option<Entity>,
+}
+
+/// Animations of the resolved abilities, played one after another
+#[derive(Resource, Debug, Default)]
+pub struct BattleAnimations {
+    steps: Vec<AnimationStep>,
+    elapsed: f32,
+    end: f32,
+}
+
+impl BattleAnimations {
+    pub fn push(&mut self, animation: Animation) {
+        let duration = animation.duration();
+        self.steps.push(AnimationStep {
+            animation,
+            start: self.end,
+            duration,
+            spawned: None,
+        });
+        self.end += duration;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+/// Lifecycle events of the resolved abilities, held back until their
+/// animations finish so that units don't vanish or lose their turn early
+#[derive(Resource, Default)]
+pub struct DeferredLifecycle(pub Vec<BattleLifecycleEvent>);
+
+/// Entity spawned by an animation, removed once it finishes
+#[derive(Component)]
+pub struct AnimationEffect;
+
+/// Position along the polyline through the points, `t` going from 0 to 1
+fn sample_path(points: &[Vec2], t: f32) -> Vec2 {
+    match points {
+        [] => Vec2::ZERO,
+        [point] => *point,
+        _ => {
+            let position = t.clamp(0.0, 1.0) * (points.len() - 1) as f32;
+            let i = (position.floor() as usize).min(points.len() - 2);
+            points[i].lerp(points[i + 1], position - i as f32)
+        }
+    }
+}
+
+fn set_offset(transform: &mut Transform, offset: Vec2) {
+    transform.translation.x = offset.x;
+    transform.translation.y = offset.y;
+}
+
+pub fn play_battle_animations(
+    mut commands: Commands,
+    time: Res<Time>,
+    speed: Res<AnimationSpeed>,
+    asset_server: Res<AssetServer>,
+    mut animations: ResMut<BattleAnimations>,
+    mut unit_query: Query<&mut Transform, (With<LifeState>, Without<AnimationEffect>)>,
+    mut effect_query: Query<(&mut Transform, Option<&mut Text>), With<AnimationEffect>>,
+) {
+    if animations.is_finished() {
+        return;
+    }
+
+    animations.elapsed = match speed.multiplier() {
+        Some(multiplier) => animations.elapsed + time.delta_seconds() * multiplier,
+        None => animations.end,
+    };
+    let elapsed = animations.elapsed;
+
+    for step in &mut animations.steps {
+        let started = elapsed >= step.start;
+        let t = if step.duration > 0.0 {
+            ((elapsed - step.start) / step.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        match &step.animation {
+            // a pending walk keeps the unit on the tile it's leaving
+            Animation::Walk { unit, path } => {
+                if let Ok(mut transform) = unit_query.get_mut(*unit) {
+                    set_offset(&mut transform, sample_path(path, t));
+                }
+            }
+            Animation::Lunge { unit, towards } if started => {
+                if let Ok(mut transform) = unit_query.get_mut(*unit) {
+                    set_offset(
+                        &mut transform,
+                        sample_path(&[Vec2::ZERO, *towards, Vec2::ZERO], t),
+                    );
+                }
+            }
+            Animation::Projectile { from, to } if started && t < 1.0 => {
+                let translation = from.lerp(*to, t);
+                match step
+                    .spawned
+                    .and_then(|entity| effect_query.get_mut(entity).ok())
+                {
+                    Some((mut transform, _)) => transform.translation = translation,
+                    None if step.spawned.is_none() => {
+                        step.spawned = Some(
+                            commands
+                                .spawn((
+                                    SpriteBundle {
+                                        sprite: Sprite {
+                                            color: Color::rgb(1.0, 0.9, 0.5),
+                                            custom_size: Some(Vec2::splat(10.0)),
+                                            ..default()
+                                        },
+                                        transform: Transform::from_translation(translation),
+                                        ..default()
+                                    },
+                                    AnimationEffect,
+                                    RenderLayers::layer(1),
+                                    Battle,
+                                ))
+                                .id(),
+                        );
+                    }
+                    None => {}
+                }
+            }
+            Animation::FloatingText {
+                text,
+                color,
+                at,
+                large,
+            } if started && t < 1.0 => {
+                let translation = *at + Vec3::Y * FLOATING_TEXT_RISE * t;
+                let alpha = 1.0 - t * t;
+                match step
+                    .spawned
+                    .and_then(|entity| effect_query.get_mut(entity).ok())
+                {
+                    Some((mut transform, text)) => {
+                        transform.translation = translation;
+                        if let Some(mut text) = text {
+                            if let Some(section) = text.sections.first_mut() {
+                                section.style.color.set_a(alpha);
+                            }
+                        }
+                    }
+                    None if step.spawned.is_none() => {
+                        step.spawned = Some(
+                            commands
+                                .spawn((
+                                    Text2dBundle {
+                                        text: Text::from_section(
+                                            text.clone(),
+                                            TextStyle {
+                                                font: asset_server
+                                                    .load("fonts/FiraSans-Medium.ttf"),
+                                                font_size: if *large { 32.0 } else { 24.0 },
+                                                color: *color,
+                                            },
+                                        ),
+                                        transform: Transform::from_translation(translation),
+                                        ..default()
+                                    },
+                                    AnimationEffect,
+                                    RenderLayers::layer(1),
+                                    Battle,
+                                ))
+                                .id(),
+                        );
+                    }
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+
+        if t >= 1.0 {
+            if let Some(entity) = step.spawned.take() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+
+    animations
+        .steps
+        .retain(|step| step.start + step.duration > elapsed);
+    if animations.is_finished() {
+        animations.elapsed = 0.0;
+        animations.end = 0.0;
+    }
+}
+
+/// Continues the battle once the animations of the resolved abilities are over
+pub fn finish_ability_resolution(
+    animations: Res<BattleAnimations>,
+    mut deferred: ResMut<DeferredLifecycle>,
+    res_queue: Res<BattleQueue>,
+    group_query: Query<&Group>,
+    mut ev_lifecycle: EventWriter<BattleLifecycleEvent>,
+    mut next_state: ResMut<NextState<BattleState>>,
+) {
+    if !animations.is_finished() {
+        return;
+    }
+
+    let mut ends_turn = false;
+    for event in deferred.0.drain(..) {
+        ends_turn |= matches!(event, BattleLifecycleEvent::EndTurn);
+        ev_lifecycle.send(event);
+    }
+
+    if !ends_turn {
+        match group_query.get(res_queue.get_current()) {
+            Ok(Group::Player) => next_state.set(BattleState::AbilityChoosingPlayer),
+            Ok(Group::Enemy) => next_state.set(BattleState::AbilityCastingEnemy),
+            Err(_) => {}
+        }
+    }
+}
+
+pub fn cycle_animation_speed(
+    actions: Actions,
+    locale: Res<Locale>,
+    mut settings: ResMut<Settings>,
+    mut ev_battle_log: EventWriter<BattleLogEvent>,
+) {
+    if actions.just_pressed(InputAction::CycleAnimationSpeed) {
+        settings.animation_speed = settings.animation_speed.next();
+        ev_battle_log.send(BattleLogEvent::Info {
+            message: locale.format(
+                "log-animation-speed",
+                &fluent_args!["speed" => settings.animation_speed.name(&locale)],
+            ),
+        });
+    }
+}
+
+pub fn cleanup_battle_animations(
+    mut animations: ResMut<BattleAnimations>,
+    mut deferred: ResMut<DeferredLifecycle>,
+) {
+    *animations = BattleAnimations::default();
+    deferred.0.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_path_segments_evenly() {
+        let path = [Vec2::ZERO, Vec2::new(10.0, 0.0), Vec2::new(10.0, 10.0)];
+
+        assert_eq!(sample_path(&path, 0.0), Vec2::ZERO);
+        assert_eq!(sample_path(&path, 0.25), Vec2::new(5.0, 0.0));
+        assert_eq!(sample_path(&path, 0.75), Vec2::new(10.0, 5.0));
+        assert_eq!(sample_path(&path, 1.0), Vec2::new(10.0, 10.0));
+    }
+
+    #[test]
+    fn animations_play_one_after_another() {
+        let mut animations = BattleAnimations::default();
+        let unit = Entity::from_raw(0);
+        animations.push(Animation::Walk {
+            unit,
+            path: vec![Vec2::ZERO, Vec2::X, Vec2::Y],
+        });
+        animations.push(Animation::Lunge {
+            unit,
+            towards: Vec2::X,
+        });
+
+        assert_eq!(animations.steps[1].start, 2.0 * WALK_STEP_DURATION);
+        assert_eq!(animations.end, 2.0 * WALK_STEP_DURATION + LUNGE_DURATION);
+    }
+}
@@ -1,40 +1,219 @@
+use std::f32::consts::PI;
+
 use bevy::{
     prelude::*,
-    render::view::RenderLayers,
+    render::{mesh::VertexAttributeValues, view::RenderLayers},
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
     utils::HashMap,
 };
 use bevy_mod_picking::PickableBundle;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    battle::Battle,
+    theme::Theme,
+    utils::hex::{Hex, HexOrientation, OffsetKind},
+    GameState,
+};
 
-use crate::{battle::Battle, utils::hex::Hex, GameState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use super::{
+    generation::{generate_battle_field, BattleNode},
+    highlight::{TileCueShape, TileHighlight},
+    vision::Sight,
+    BattleInitState,
+};
 
-use super::BattleInitState;
+/// Tiles never get smaller than this, bigger maps are panned instead
+const MIN_TILE_SIZE: f32 = 40.0;
 
 #[derive(Component)]
 pub struct Tile;
 
+/// Which hexes of the grid have tiles
+#[derive(Debug, Clone)]
+pub enum BattleFieldShape {
+    /// `x` by `y` tiles in the offset coordinates of the orientation
+    Rectangle(UVec2),
+    /// Every hex up to the radius away from the origin
+    Hexagon(u32),
+    /// `x` by `y` tiles in axial coordinates, which slants the rows
+    Parallelogram(UVec2),
+    /// Rows of offset coordinates from the top down, with a `#` for every tile
+    Mask(Vec<String>),
+    /// Exactly these hexes, as generated maps have them
+    Tiles(Vec<Hex>),
+}
+
+impl BattleFieldShape {
+    pub fn hexes(&self, orientation: HexOrientation) -> Vec<Hex> {
+        let offset = move |x: usize, y: usize| {
+            Hex::from_offset((x as i32, y as i32).into(), orientation.offset_kind())
+        };
+
+        match self {
+            Self::Rectangle(size) => (0..size.x as usize)
+                .flat_map(|x| (0..size.y as usize).map(move |y| offset(x, y)))
+                .collect(),
+            Self::Hexagon(radius) => Hex::ZERO.spiral(*radius as i32),
+            Self::Parallelogram(size) => (0..size.x as i32)
+                .flat_map(|x| (0..size.y as i32).map(move |y| Hex::from_axial((x, y).into())))
+                .collect(),
+            Self::Mask(rows) => rows
+                .iter()
+                .rev()
+                .enumerate()
+                .flat_map(|(y, row)| {
+                    row.chars()
+                        .enumerate()
+                        .filter(|(_, c)| *c == '#')
+                        .map(move |(x, _)| offset(x, y))
+                })
+                .collect(),
+            Self::Tiles(hexes) => hexes.clone(),
+        }
+    }
+}
+
+/// What covers a tile, besides the bare ground
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Terrain {
+    #[default]
+    Ground,
+    /// Can be walked through and seen into, but not seen past
+    TallGrass,
+}
+
+impl Terrain {
+    pub fn blocks_sight(self) -> bool {
+        self == Self::TallGrass
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BattleFieldLayout {
-    pub size: UVec2,
+    pub shape: BattleFieldShape,
+    pub orientation: HexOrientation,
+    /// Hides what the units can't see, even below the hard difficulty
+    pub fog_of_war: bool,
+    /// Tiles not on bare ground
+    pub terrain: HashMap<Hex, Terrain>,
     pub player_start: Vec<Hex>,
     pub enemy_start: Vec<Hex>,
 }
 
+fn starts(kind: OffsetKind, positions: &[(i32, i32)]) -> Vec<Hex> {
+    positions
+        .iter()
+        .map(|&pos| Hex::from_offset(pos.into(), kind))
+        .collect()
+}
+
+impl Default for BattleFieldLayout {
+    fn default() -> Self {
+        BattleFieldLayout {
+            shape: BattleFieldShape::Rectangle((12, 7).into()),
+            orientation: HexOrientation::PointyTop,
+            fog_of_war: false,
+            terrain: HashMap::new(),
+            player_start: starts(OffsetKind::OddR, &[(0, 3), (0, 0), (0, 6)]),
+            enemy_start: starts(
+                OffsetKind::OddR,
+                &[(10, 2), (10, 4), (11, 3), (11, 1), (11, 5)],
+            ),
+        }
+    }
+}
+
+impl BattleFieldLayout {
+    pub fn hexes(&self) -> Vec<Hex> {
+        self.shape.hexes(self.orientation)
+    }
+
+    /// Handcrafted maps for the arena battles of a run
+    pub fn arenas() -> Vec<Self> {
+        vec![
+            Self::default(),
+            BattleFieldLayout {
+                shape: BattleFieldShape::Hexagon(4),
+                orientation: HexOrientation::FlatTop,
+                fog_of_war: false,
+                terrain: HashMap::new(),
+                player_start: starts(OffsetKind::OddQ, &[(-4, 0), (-4, -2), (-4, 2)]),
+                enemy_start: starts(
+                    OffsetKind::OddQ,
+                    &[(3, -1), (3, 1), (4, 0), (4, -2), (4, 2)],
+                ),
+            },
+            BattleFieldLayout {
+                shape: BattleFieldShape::Mask(
+                    [
+                        "..########..",
+                        ".##########.",
+                        "#####..#####",
+                        "####....####",
+                        "#####..#####",
+                        ".##########.",
+                        "..########..",
+                    ]
+                    .map(String::from)
+                    .to_vec(),
+                ),
+                orientation: HexOrientation::PointyTop,
+                // the rocks in the middle block the view
+                fog_of_war: true,
+                terrain: HashMap::new(),
+                player_start: starts(OffsetKind::OddR, &[(0, 3), (1, 1), (1, 5)]),
+                enemy_start: starts(
+                    OffsetKind::OddR,
+                    &[(10, 2), (10, 4), (11, 3), (10, 1), (10, 5)],
+                ),
+            },
+            BattleFieldLayout {
+                shape: BattleFieldShape::Parallelogram((10, 7).into()),
+                orientation: HexOrientation::PointyTop,
+                fog_of_war: false,
+                terrain: HashMap::new(),
+                player_start: [(0, 3), (0, 1), (0, 5)]
+                    .into_iter()
+                    .map(|pos| Hex::from_axial(pos.into()))
+                    .collect(),
+                enemy_start: [(8, 2), (8, 4), (9, 3), (9, 1), (9, 5)]
+                    .into_iter()
+                    .map(|pos| Hex::from_axial(pos.into()))
+                    .collect(),
+            },
+        ]
+    }
+}
+
 #[derive(Resource, Debug, Clone)]
 pub struct BattleField {
     tiles: HashMap<Hex, Entity>,
     rev_map: HashMap<Entity, Hex>,
     tile_size: f32,
+    orientation: HexOrientation,
+    /// World area covered by the tiles
+    bounds: Rect,
+    terrain: HashMap<Hex, Terrain>,
 }
 
 impl BattleField {
-    pub fn new(tiles: HashMap<Hex, Entity>, tile_size: f32) -> Self {
+    pub fn new(
+        tiles: HashMap<Hex, Entity>,
+        tile_size: f32,
+        orientation: HexOrientation,
+        bounds: Rect,
+        terrain: HashMap<Hex, Terrain>,
+    ) -> Self {
         let rev_map = tiles.iter().map(|(pos, entity)| (*entity, *pos)).collect();
 
         BattleField {
             tiles,
             rev_map,
             tile_size,
+            orientation,
+            bounds,
+            terrain,
         }
     }
 
@@ -54,6 +233,18 @@ impl BattleField {
         self.tile_size
     }
 
+    pub fn orientation(&self) -> HexOrientation {
+        self.orientation
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    pub fn terrain(&self, pos: &Hex) -> Terrain {
+        self.terrain.get(pos).copied().unwrap_or_default()
+    }
+
     pub fn hexes_by_dist(&self, pos: &Hex, close_to: Option<Hex>) -> Vec<(i32, Hex)> {
         let mut dists_to_hex = self
             .tiles
@@ -68,18 +259,17 @@ impl BattleField {
             .collect()
     }
 
-    pub fn in_range_and_empty(
-        &self,
-        from: Hex,
-        hex: Hex,
-        range: i32,
-        tile_children_query: &Query<&Children, With<Tile>>,
-    ) -> Option<Entity> {
-        let tile = self.tile(&hex).expect("Couldn't find tile");
-        let tile_has_children = tile_children_query.get(tile).is_ok();
-        (from.dist(hex) <= range && !tile_has_children).then_some(tile)
+    /// Steps it takes to walk from the hex to every tile, going around gaps
+    pub fn walk_distances(&self, from: Hex) -> HashMap<Hex, i32> {
+        from.walk_distances(|hex| self.tiles.contains_key(&hex))
+    }
+
+    /// Hexes walked over from one tile to the other, with both ends
+    pub fn walk_path(&self, from: Hex, to: Hex) -> Option<Vec<Hex>> {
+        from.walk_path(to, |hex| self.tiles.contains_key(&hex))
     }
 
+    /// Empty tile closest to the target which the caster can walk to
     pub fn get_in_range_and_empty(
         &self,
         target_hex: Hex,
@@ -87,74 +277,172 @@ impl BattleField {
         range: i32,
         tile_children_query: &Query<&Children, With<Tile>>,
     ) -> Option<Entity> {
+        let walk_distances = self.walk_distances(caster_hex);
         self.hexes_by_dist(&target_hex, Some(caster_hex))
             .iter()
+            .filter(|(_, hex)| walk_distances.get(hex).is_some_and(|dist| *dist <= range))
             .find_map(|(_, hex)| {
-                self.in_range_and_empty(caster_hex, *hex, range, tile_children_query)
+                let tile = self.tile(hex)?;
+                tile_children_query.get(tile).is_err().then_some(tile)
             })
     }
 }
 
+pub fn choose_battle_field_layout(mut game_state: ResMut<GameState>) {
+    let seed = game_state.seed.wrapping_add(game_state.round as u64);
+    game_state.battle_field_layout =
+        generate_battle_field(BattleNode::for_round(game_state.round), seed);
+}
+
+/// Regular hexagon mesh turned to the orientation
+fn tile_mesh(radius: f32, orientation: HexOrientation) -> Mesh {
+    // the polygon starts with a corner at the top
+    let mut mesh = Mesh::from(shape::RegularPolygon::new(radius, 6));
+    if orientation == HexOrientation::FlatTop {
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            let rotation = Quat::from_rotation_z(PI / 6.0);
+            for pos in positions.iter_mut() {
+                *pos = (rotation * Vec3::from(*pos)).into();
+            }
+        }
+    }
+    mesh
+}
+
 pub fn setup_battle_field(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     game_state: Res<GameState>,
+    theme: Res<Theme>,
+    windows: Query<&Window>,
     mut next_state: ResMut<NextState<BattleInitState>>,
 ) {
-    let size = game_state.battle_field_layout.size;
+    let layout = &game_state.battle_field_layout;
+    let orientation = layout.orientation;
+    let hexes = layout.hexes();
+    assert!(!hexes.is_empty(), "Battle field without tiles");
 
-    let world_size = Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT);
+    let window = windows.single();
+    let world_size = Vec2::new(window.width(), window.height());
 
-    let bottom_left_corner = Vec2::ZERO - 0.5 * world_size;
+    // where the centres of tiles of size 1 go, and how far the tiles reach
+    // past their centres
+    let (min, max) = hexes.iter().map(|hex| hex.to_pixel(orientation, 1.0)).fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), pos| (min.min(pos), max.max(pos)),
+    );
+    let reach = match orientation {
+        HexOrientation::PointyTop => Vec2::new(0.5 * 3.0f32.sqrt(), 1.0),
+        HexOrientation::FlatTop => Vec2::new(1.0, 0.5 * 3.0f32.sqrt()),
+    };
 
-    let tile_size = (world_size.x / (3.0f32.sqrt() * (size.x as f32 + 0.5)))
-        .min(world_size.y / (size.y as f32 * 1.5 + 0.5));
-
-    let hor_spacing = 3.0f32.sqrt() * tile_size;
-    let ver_spacing = 1.5 * tile_size;
-    let corner_pos = bottom_left_corner
-        + 0.5 * Vec2::new(2.0 * tile_size, ver_spacing)
-        + 0.5
-            * Vec2::new(
-                world_size.x - tile_size * 3.0f32.sqrt() * (size.x as f32 + 0.5),
-                world_size.y - tile_size * (1.5 * size.y as f32 + 0.5),
-            );
+    let tile_size = (world_size / (max - min + 2.0 * reach))
+        .min_element()
+        .max(MIN_TILE_SIZE);
+    // the middle of the map goes to the middle of the world
+    let center_offset = -0.5 * (min + max) * tile_size;
 
     let mut tiles = HashMap::new();
-    let tile_mesh: Mesh2dHandle = meshes
-        .add(Mesh::from(shape::RegularPolygon::new(tile_size - 1.0, 6)))
-        .into();
-    let tile_material = materials.add(ColorMaterial::from(Color::GRAY));
-
-    for x in 0..size.x {
-        for y in 0..size.y {
-            let transform = Transform::from_xyz(
-                corner_pos.x + (x as f32 + 0.5 * (y % 2) as f32) * hor_spacing,
-                corner_pos.y + y as f32 * ver_spacing,
-                0.0,
-            );
-
-            let id = commands
-                .spawn((
-                    Tile,
-                    MaterialMesh2dBundle {
-                        mesh: tile_mesh.clone(),
-                        transform,
-                        material: tile_material.clone(),
-                        ..default()
-                    },
-                    Battle,
-                    RenderLayers::layer(1),
-                    PickableBundle::default(),
-                ))
-                .id();
-
-            tiles.insert(Hex::from_oddr((x as i32, y as i32).into()), id);
-        }
+    let tile_mesh: Mesh2dHandle = meshes.add(tile_mesh(tile_size - 1.0, orientation)).into();
+    let tile_material = materials.add(ColorMaterial::from(theme.tile.color));
+
+    for hex in hexes {
+        let transform = Transform::from_translation(
+            (center_offset + hex.to_pixel(orientation, tile_size)).extend(0.0),
+        );
+
+        let id = commands
+            .spawn((
+                Tile,
+                TileHighlight::default(),
+                Sight::default(),
+                MaterialMesh2dBundle {
+                    mesh: tile_mesh.clone(),
+                    transform,
+                    material: tile_material.clone(),
+                    ..default()
+                },
+                Battle,
+                RenderLayers::layer(1),
+                PickableBundle::default(),
+            ))
+            .id();
+
+        commands.spawn((
+            ShapeBundle {
+                transform: transform.with_translation(transform.translation + Vec3::Z),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Stroke::color(theme.cue),
+            TileCueShape { tile: id },
+            Battle,
+            RenderLayers::layer(1),
+        ));
+
+        tiles.insert(hex, id);
     }
 
-    commands.insert_resource(BattleField::new(tiles, tile_size));
+    // out to the edges of the outermost tiles
+    let bounds = Rect::from_corners(
+        center_offset + min * tile_size,
+        center_offset + max * tile_size,
+    );
+    let bounds = Rect::from_center_size(bounds.center(), bounds.size() + 2.0 * tile_size);
+    commands.insert_resource(BattleField::new(
+        tiles,
+        tile_size,
+        orientation,
+        bounds,
+        layout.terrain.clone(),
+    ));
 
     next_state.set(BattleInitState::AfterBattleField);
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn starting_positions_are_on_the_map() {
+        for layout in BattleFieldLayout::arenas() {
+            let hexes = layout.hexes().into_iter().collect::<HashSet<_>>();
+            let starts = layout
+                .player_start
+                .iter()
+                .chain(&layout.enemy_start)
+                .collect::<Vec<_>>();
+
+            assert!(layout.player_start.len() >= 3, "{:?}", layout.shape);
+            assert!(layout.enemy_start.len() >= 5, "{:?}", layout.shape);
+            for (i, start) in starts.iter().enumerate() {
+                assert!(hexes.contains(*start), "{start:?} off {:?}", layout.shape);
+                assert!(!starts[i + 1..].contains(start), "{start:?} repeats");
+            }
+        }
+    }
+
+    #[test]
+    fn shapes_have_their_sizes() {
+        let rows = ["##.", ".##"].map(String::from).to_vec();
+        for orientation in [HexOrientation::PointyTop, HexOrientation::FlatTop] {
+            let count = |shape: BattleFieldShape| {
+                let hexes = shape.hexes(orientation);
+                let unique = hexes.iter().collect::<HashSet<_>>().len();
+                assert_eq!(unique, hexes.len(), "{shape:?} repeats hexes");
+                unique
+            };
+
+            assert_eq!(count(BattleFieldShape::Rectangle((12, 7).into())), 84);
+            assert_eq!(count(BattleFieldShape::Hexagon(4)), 61);
+            assert_eq!(count(BattleFieldShape::Parallelogram((3, 5).into())), 15);
+            assert_eq!(count(BattleFieldShape::Mask(rows.clone())), 4);
+        }
+    }
+}
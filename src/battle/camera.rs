@@ -0,0 +1,232 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+use crate::input::{Actions, InputAction};
+
+use super::{
+    battle_field::BattleField,
+    ui::{BattleCamera, BOTTOM_PANE_HEIGHT, RIGHT_PANE_WIDTH},
+    vision::Concealed,
+    BattleQueue, BattleState,
+};
+
+/// Panning speed with the keys and at the edges, in world units per second
+/// at the default zoom
+const PAN_SPEED: f32 = 700.0;
+/// Distance from the edges of the battle view which pans, in logical pixels
+const EDGE_SCROLL_MARGIN: f32 = 12.0;
+/// Zoom change per line scrolled
+const ZOOM_STEP: f32 = 0.1;
+/// Pixels scrolled by touchpads which count as one line
+const PIXELS_PER_LINE: f32 = 20.0;
+/// Projection scale when zoomed in the most
+const MIN_SCALE: f32 = 0.5;
+/// How quickly the camera glides to the active unit, higher is faster
+const FOCUS_SPEED: f32 = 8.0;
+
+/// State of the battle camera controls
+#[derive(Resource, Default)]
+pub struct CameraControl {
+    /// Point the camera glides to, dropped as soon as the player pans
+    focus: Option<Vec2>,
+    /// Unit whose turn the camera was centred on last
+    focused_unit: Option<Entity>,
+    /// Cursor position of the last frame while dragging the map
+    drag_from: Option<Vec2>,
+}
+
+/// Keeps the view inside the map, or centred on it where the map is smaller
+/// than the view
+fn clamp_to_bounds(center: Vec2, half_view: Vec2, bounds: Rect) -> Vec2 {
+    let clamp_axis = |center: f32, half_view: f32, min: f32, max: f32| {
+        if max - min <= 2.0 * half_view {
+            (min + max) / 2.0
+        } else {
+            center.clamp(min + half_view, max - half_view)
+        }
+    };
+
+    Vec2::new(
+        clamp_axis(center.x, half_view.x, bounds.min.x, bounds.max.x),
+        clamp_axis(center.y, half_view.y, bounds.min.y, bounds.max.y),
+    )
+}
+
+/// Direction to pan in while the cursor is at the edges of the view
+fn edge_scroll(cursor: Vec2, view: Rect) -> Vec2 {
+    let axis = |pos: f32, min: f32, max: f32| {
+        if pos < min + EDGE_SCROLL_MARGIN {
+            -1.0
+        } else if pos > max - EDGE_SCROLL_MARGIN {
+            1.0
+        } else {
+            0.0
+        }
+    };
+
+    Vec2::new(
+        axis(cursor.x, view.min.x, view.max.x),
+        axis(cursor.y, view.min.y, view.max.y),
+    )
+}
+
+/// Glides the camera to the unit whose turn just started, unless the player
+/// can't see it
+pub fn focus_active_unit(
+    state: Res<State<BattleState>>,
+    res_queue: Option<Res<BattleQueue>>,
+    mut control: ResMut<CameraControl>,
+    unit_query: Query<&GlobalTransform, Without<Concealed>>,
+) {
+    if !matches!(
+        state.0,
+        BattleState::AbilityChoosingPlayer | BattleState::AbilityCastingEnemy
+    ) {
+        return;
+    }
+    let Some(res_queue) = res_queue else {
+        return;
+    };
+
+    let unit = res_queue.get_current();
+    if control.focused_unit == Some(unit) {
+        return;
+    }
+    if let Ok(transform) = unit_query.get(unit) {
+        control.focused_unit = Some(unit);
+        control.focus = Some(transform.translation().truncate());
+    }
+}
+
+pub fn control_battle_camera(
+    time: Res<Time>,
+    actions: Actions,
+    buttons: Res<Input<MouseButton>>,
+    windows: Query<&Window>,
+    battle_field: Option<Res<BattleField>>,
+    mut control: ResMut<CameraControl>,
+    mut ev_mouse_wheel: EventReader<MouseWheel>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<BattleCamera>>,
+) {
+    let Some(battle_field) = battle_field else {
+        return;
+    };
+    let Ok((mut transform, mut projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+    let window = windows.single();
+
+    // the battle view in cursor coordinates, which start at the bottom left
+    let view = Rect::new(
+        0.0,
+        BOTTOM_PANE_HEIGHT,
+        window.width() - RIGHT_PANE_WIDTH,
+        window.height(),
+    );
+    let cursor = window
+        .cursor_position()
+        .filter(|cursor| !actions.is_blocked() && view.contains(*cursor));
+    // world area seen at a scale of 1
+    let base_area = projection.area.size() / projection.scale;
+    let world_per_pixel = base_area.x * projection.scale / view.width().max(1.0);
+
+    let mut pan = Vec2::ZERO;
+    for (action, direction) in [
+        (InputAction::PanUp, Vec2::Y),
+        (InputAction::PanDown, Vec2::NEG_Y),
+        (InputAction::PanLeft, Vec2::NEG_X),
+        (InputAction::PanRight, Vec2::X),
+    ] {
+        if actions.pressed(action) {
+            pan += direction;
+        }
+    }
+    if let Some(cursor) = cursor {
+        pan += edge_scroll(cursor, view);
+    }
+    let mut offset = pan.normalize_or_zero() * PAN_SPEED * projection.scale * time.delta_seconds();
+
+    // dragging with the middle mouse button
+    match (cursor, buttons.pressed(MouseButton::Middle)) {
+        (Some(cursor), true) => {
+            if let Some(from) = control.drag_from {
+                offset -= (cursor - from) * world_per_pixel;
+            }
+            control.drag_from = Some(cursor);
+        }
+        _ => control.drag_from = None,
+    }
+
+    let mut scrolled = 0.0;
+    for ev in ev_mouse_wheel.iter() {
+        if cursor.is_some() {
+            scrolled += match ev.unit {
+                MouseScrollUnit::Line => ev.y,
+                MouseScrollUnit::Pixel => ev.y / PIXELS_PER_LINE,
+            };
+        }
+    }
+
+    // zooming out stops once the whole map is in view
+    let max_scale = (battle_field.bounds().size() / base_area)
+        .max_element()
+        .max(1.0);
+    let scale = (projection.scale * (1.0 - ZOOM_STEP * scrolled)).clamp(MIN_SCALE, max_scale);
+    if projection.scale != scale {
+        projection.scale = scale;
+    }
+
+    let mut center = transform.translation.truncate();
+    if offset != Vec2::ZERO {
+        control.focus = None;
+        center += offset;
+    } else if let Some(focus) = control.focus {
+        center = center.lerp(focus, 1.0 - (-FOCUS_SPEED * time.delta_seconds()).exp());
+        if center.distance(focus) < 1.0 {
+            control.focus = None;
+        }
+    }
+
+    let center = clamp_to_bounds(center, base_area * scale / 2.0, battle_field.bounds());
+    if transform.translation.truncate() != center {
+        transform.translation = center.extend(transform.translation.z);
+    }
+}
+
+pub fn cleanup_camera_control(mut control: ResMut<CameraControl>) {
+    *control = CameraControl::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_stays_inside_the_map() {
+        let bounds = Rect::new(-500.0, -200.0, 500.0, 200.0);
+        let half_view = Vec2::new(200.0, 300.0);
+
+        // panned past the right edge, and the map is lower than the view
+        assert_eq!(
+            clamp_to_bounds(Vec2::new(450.0, 100.0), half_view, bounds),
+            Vec2::new(300.0, 0.0)
+        );
+        assert_eq!(
+            clamp_to_bounds(Vec2::new(-50.0, 0.0), half_view, bounds),
+            Vec2::new(-50.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn edges_of_the_view_pan() {
+        let view = Rect::new(0.0, 150.0, 1000.0, 700.0);
+
+        assert_eq!(
+            edge_scroll(Vec2::new(5.0, 695.0), view),
+            Vec2::new(-1.0, 1.0)
+        );
+        assert_eq!(edge_scroll(Vec2::new(500.0, 400.0), view), Vec2::ZERO);
+    }
+}
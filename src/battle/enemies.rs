@@ -1,22 +1,31 @@
 use bevy::prelude::*;
-use rand::seq::IteratorRandom;
+use rand::{rngs::StdRng, seq::IteratorRandom, SeedableRng};
 
 use crate::{
-    abilities::{AbilityTargetType, TurnEvent},
-    character::{Abilities, Attribute, Group},
+    abilities::{
+        Ability, AbilityProximity, AbilityTargetType, AbilityType, TargetedAbilityType, TurnEvent,
+    },
+    character::{Abilities, Attributes, CharacterName, Group, ModifierSource},
     enemies::{AvailableEnemies, EnemyTier},
     GameState,
 };
 
 use super::{
     battle_field::{BattleField, Tile},
-    BattleQueue,
+    lifecycle::LifeState,
+    vision::FogOfWar,
+    BattleQueue, BattleRng,
 };
 
-pub fn initialize_enemies(enemies: Res<AvailableEnemies>, mut game_state: ResMut<GameState>) {
-    let mut rng = rand::thread_rng();
+pub fn initialize_enemies(
+    mut commands: Commands,
+    enemies: Res<AvailableEnemies>,
+    mut game_state: ResMut<GameState>,
+) {
+    let mut rng = StdRng::seed_from_u64(game_state.seed.wrapping_add(game_state.round as u64));
 
-    let number_of_enemies = (game_state.round / 8 + 1).clamp(1, 4);
+    let number_of_enemies =
+        (game_state.round / 8 + 1).clamp(1, 4) + game_state.difficulty.extra_enemies();
 
     for enemy in enemies
         .0
@@ -28,40 +37,76 @@ pub fn initialize_enemies(enemies: Res<AvailableEnemies>, mut game_state: ResMut
     {
         let mut enemy = enemy.clone();
 
-        let power_multiplier = 1.02f32.powi(game_state.round);
+        let power_multiplier = 1.02f32.powi(game_state.round) * game_state.difficulty.enemy_power();
+        scale_to_power(&mut enemy.bundle.attributes, power_multiplier);
 
-        for (_, val) in enemy.bundle.attributes.0.iter_mut() {
-            match val {
-                Attribute::Value(v) => {
-                    *v = (*v as f32 * power_multiplier).round() as i32;
-                }
-                Attribute::Gauge { value, max, .. } => {
-                    *value = (*value as f32 * power_multiplier).round() as i32;
-                    *max = (*max as f32 * power_multiplier).round() as i32;
-                }
-            };
+        game_state.characters.push(enemy.clone());
+    }
+
+    commands.insert_resource(BattleRng(rng));
+}
+
+fn scale_to_power(attributes: &mut Attributes, multiplier: f32) {
+    for (typ, val) in attributes.0.iter_mut() {
+        if typ.scales_with_power() {
+            val.set_base((val.get_base() as f32 * multiplier).round() as i32);
         }
+    }
+}
 
-        game_state.characters.push(enemy.clone());
+/// Buffs and debuffs are only worth casting if they'd have any effect
+/// on the target
+fn is_worth_using(ability: &Ability, caster_name: &str, target: &Attributes) -> bool {
+    match ability.r#type {
+        AbilityType::Targeted {
+            ab_type:
+                TargetedAbilityType::ModifyAttribute {
+                    at_type, stacking, ..
+                },
+            ..
+        } => target.0.get(&at_type).is_some_and(|attribute| {
+            attribute.can_apply(
+                &ModifierSource::Ability {
+                    name: ability.name.clone(),
+                    caster: caster_name.to_string(),
+                },
+                stacking,
+            )
+        }),
+        _ => true,
     }
 }
 
 pub fn handle_enemy_turn(
     res_queue: ResMut<BattleQueue>,
+    mut rng: ResMut<BattleRng>,
     battle_field: Option<Res<BattleField>>,
-    abilities_query: Query<&Abilities>,
-    group_parent_query: Query<(&Group, &Parent)>,
+    fog: Res<FogOfWar>,
+    abilities_query: Query<(&Abilities, &CharacterName)>,
+    group_parent_query: Query<(&Group, &Parent, &LifeState, &Attributes)>,
     tile_children_query: Query<&Children, With<Tile>>,
     mut ev_ability: EventWriter<TurnEvent>,
 ) {
-    let mut rng = rand::thread_rng();
+    let rng = &mut rng.0;
+    let battle_field = battle_field.expect("Missing battle field");
 
-    let player_tile = group_parent_query
+    // only the players the enemies see can be attacked, the others are
+    // hunted down where they were seen last
+    let seen_player = group_parent_query
         .iter()
-        .filter_map(|(group, parent)| (*group == Group::Player).then_some(parent))
-        .choose(&mut rng)
-        .expect("Couldn't find a player character")
-        .get();
+        .filter_map(|(group, parent, life_state, attributes)| {
+            (*group == Group::Player && *life_state == LifeState::Alive)
+                .then_some((parent.get(), attributes))
+        })
+        .filter(|(tile, _)| {
+            let hex = battle_field.hex(*tile).expect("Missing player hex");
+            fog.sees(Group::Enemy, hex)
+        })
+        .choose(rng);
+    let player_hex = match seen_player {
+        Some((player_tile, _)) => battle_field.hex(player_tile),
+        None => fog.last_known(Group::Enemy).choose(rng),
+    };
 
     let active_enemy = res_queue.get_current();
     let enemy_tile = group_parent_query
@@ -69,28 +114,66 @@ pub fn handle_enemy_turn(
         .expect("Couldn't get enemy parent tile")
         .1
         .get();
-
-    let battle_field = battle_field.expect("Missing battle field");
-    let player_hex = battle_field.hex(player_tile).expect("Missing player hex");
     let enemy_hex = battle_field.hex(enemy_tile).expect("Missing enemy hex");
 
-    if let Ok(abilities) = abilities_query.get(active_enemy) {
-        if let Some(ability) = abilities
+    let allies = group_parent_query
+        .iter()
+        .filter(|&(group, _, life_state, _)| {
+            *group == Group::Enemy && *life_state == LifeState::Alive
+        })
+        .map(|(_, parent, _, attributes)| (parent.get(), attributes))
+        .collect::<Vec<_>>();
+
+    if let Ok((abilities, caster_name)) = abilities_query.get(active_enemy) {
+        if let Some((ability, target_tile)) = abilities
             .0
-            .iter()
-            .map(|(_, ability)| ability)
-            .filter(|&ability| {
-                let enemy_targeting: bool = ability.target.contains(AbilityTargetType::Enemy);
-                let player_in_range = player_hex.dist(enemy_hex) <= ability.range;
-                enemy_targeting && player_in_range
+            .values()
+            .filter(|ability| ability.r#type != AbilityType::Movement)
+            .flat_map(|ability| {
+                let mut targets = vec![];
+                if ability.target.contains(AbilityTargetType::Enemy) {
+                    targets.extend(seen_player);
+                }
+                if ability.target.contains(AbilityTargetType::Ally) {
+                    targets.extend(allies.iter().copied());
+                }
+
+                targets
+                    .into_iter()
+                    .filter(|&(tile, attributes)| {
+                        let hex = battle_field.hex(tile).expect("Missing target hex");
+                        let melee = matches!(
+                            ability.r#type,
+                            AbilityType::Targeted {
+                                proximity: AbilityProximity::Melee,
+                                ..
+                            }
+                        );
+                        // melee needs a free tile to walk up to unless already next to it
+                        let can_reach = !melee
+                            || hex.dist(enemy_hex) <= 1
+                            || battle_field
+                                .get_in_range_and_empty(
+                                    hex,
+                                    enemy_hex,
+                                    ability.range,
+                                    &tile_children_query,
+                                )
+                                .is_some();
+                        hex.dist(enemy_hex) <= ability.range
+                            && can_reach
+                            && is_worth_using(ability, &caster_name.0, attributes)
+                    })
+                    .map(move |(tile, _)| (ability, tile))
             })
-            .choose(&mut rng)
+            .choose(rng)
         {
             ev_ability.send(TurnEvent::Ability {
                 ability: ability.clone(),
                 by: active_enemy,
-                on: player_tile,
+                on: target_tile,
             });
+            ev_ability.send(TurnEvent::EndTurn);
         } else if let Some((ability, target_hex)) = {
             let mut move_abilities = abilities
                 .0
@@ -100,23 +183,31 @@ pub fn handle_enemy_turn(
                 .collect::<Vec<_>>();
 
             move_abilities.sort_by_key(|ab| ab.range);
-            move_abilities.last().and_then(|&ability| {
-                battle_field
-                    .hexes_by_dist(&player_hex, Some(enemy_hex))
-                    .iter()
-                    .find_map(|(_, move_target)| {
-                        move_target.line(enemy_hex).iter().skip(1).find_map(|&h| {
-                            battle_field
-                                .in_range_and_empty(
-                                    enemy_hex,
-                                    h,
-                                    ability.range,
-                                    &tile_children_query,
-                                )
-                                .map(|_| (ability, h))
+            move_abilities
+                .last()
+                .zip(player_hex)
+                .and_then(|(&ability, player_hex)| {
+                    // closer by walking around the gaps, not as the crow flies
+                    let to_player = battle_field.walk_distances(player_hex);
+                    let from_here = to_player.get(&enemy_hex).copied().unwrap_or(i32::MAX);
+                    battle_field
+                        .walk_distances(enemy_hex)
+                        .into_iter()
+                        .filter(|&(hex, dist)| {
+                            let empty = battle_field
+                                .tile(&hex)
+                                .is_some_and(|tile| tile_children_query.get(tile).is_err());
+                            dist > 0 && dist <= ability.range && empty
                         })
-                    })
-            })
+                        .filter_map(|(hex, dist)| Some((*to_player.get(&hex)?, dist, hex)))
+                        .filter(|&(to_player, _, _)| to_player < from_here)
+                        // ties are broken by position so the same seed plays the same
+                        .min_by_key(|&(to_player, dist, hex)| {
+                            let pos = hex.to_oddr();
+                            (to_player, dist, pos.y, pos.x)
+                        })
+                        .map(|(_, _, hex)| (ability, hex))
+                })
         } {
             let target_tile = battle_field.tile(&target_hex).expect("Couldn't find tile");
 
@@ -125,6 +216,7 @@ pub fn handle_enemy_turn(
                 by: active_enemy,
                 on: target_tile,
             });
+            ev_ability.send(TurnEvent::EndTurn);
         } else {
             ev_ability.send(TurnEvent::Pass(active_enemy));
         }
@@ -133,3 +225,22 @@ pub fn handle_enemy_turn(
         ev_ability.send(TurnEvent::Pass(active_enemy));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::character::AttributeType;
+
+    use super::*;
+
+    #[test]
+    fn power_leaves_chances_and_vision_alone() {
+        let mut attributes = Attributes::default();
+        scale_to_power(&mut attributes, 1.5);
+
+        let base = |typ| attributes.0[&typ].get_base();
+        assert_eq!(base(AttributeType::Attack), 15);
+        assert_eq!(base(AttributeType::Vision), 5);
+        assert_eq!(base(AttributeType::Accuracy), 95);
+        assert_eq!(base(AttributeType::Crit), 5);
+    }
+}
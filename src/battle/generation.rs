@@ -0,0 +1,294 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::utils::hex::{Hex, HexOrientation};
+
+use super::battle_field::{BattleFieldLayout, BattleFieldShape, Terrain};
+
+const PLAYER_STARTS: usize = 3;
+const ENEMY_STARTS: usize = 5;
+/// How far the spawn zones reach in from the sides of the map, for tiles of
+/// size 1
+const SPAWN_ZONE_DEPTH: f32 = 2.5;
+/// Tries before falling back to an open map
+const MAX_ATTEMPTS: usize = 20;
+/// Keeps the battle maps apart from the enemies rolled for the same round
+const MAP_SEED: u64 = 0x6d61_7073;
+
+/// Kind of battle a node of the run leads to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BattleNode {
+    Skirmish,
+    /// Tall grass and fog of war
+    Ambush,
+    /// One of the handcrafted maps
+    Arena,
+}
+
+impl BattleNode {
+    /// Runs don't branch yet, so the kind of battle follows from the round
+    pub fn for_round(round: i32) -> Self {
+        if round % 5 == 0 {
+            Self::Arena
+        } else if round % 3 == 0 {
+            Self::Ambush
+        } else {
+            Self::Skirmish
+        }
+    }
+
+    fn params(self) -> Option<GeneratorParams> {
+        match self {
+            // an even number of rows keeps the offset rows symmetric
+            Self::Skirmish => Some(GeneratorParams {
+                shape: BattleFieldShape::Rectangle((12, 8).into()),
+                orientation: HexOrientation::PointyTop,
+                obstacles: 3,
+                // grass only hides units under the fog of war
+                grass_patches: 0,
+                patch_radius: 1,
+                fog_of_war: false,
+            }),
+            Self::Ambush => Some(GeneratorParams {
+                shape: BattleFieldShape::Hexagon(5),
+                orientation: HexOrientation::FlatTop,
+                obstacles: 2,
+                grass_patches: 5,
+                patch_radius: 1,
+                fog_of_war: true,
+            }),
+            Self::Arena => None,
+        }
+    }
+}
+
+/// How a generated map looks
+#[derive(Debug, Clone)]
+struct GeneratorParams {
+    /// Must look the same turned halfway around its middle
+    shape: BattleFieldShape,
+    orientation: HexOrientation,
+    /// Clumps of hexes without tiles on each half of the map
+    obstacles: usize,
+    /// Patches of tall grass on each half of the map
+    grass_patches: usize,
+    /// How far clumps and patches reach past their middle at most
+    patch_radius: i32,
+    fog_of_war: bool,
+}
+
+/// Map for a battle of the kind, the same one for the same seed
+pub fn generate_battle_field(node: BattleNode, seed: u64) -> BattleFieldLayout {
+    let mut rng = StdRng::seed_from_u64(seed ^ MAP_SEED);
+    let Some(params) = node.params() else {
+        let mut arenas = BattleFieldLayout::arenas();
+        let index = rng.gen_range(0..arenas.len());
+        return arenas.swap_remove(index);
+    };
+
+    (0..MAX_ATTEMPTS)
+        .find_map(|_| try_generate(&params, &mut rng))
+        .unwrap_or_else(|| {
+            // the bare shape is always connected
+            let open = GeneratorParams {
+                obstacles: 0,
+                ..params.clone()
+            };
+            try_generate(&open, &mut rng).expect("Open map has unconnected spawns")
+        })
+}
+
+/// Mirrors every obstacle, patch and start through the middle of the map, so
+/// both sides get the same ground
+fn try_generate(params: &GeneratorParams, rng: &mut StdRng) -> Option<BattleFieldLayout> {
+    let orientation = params.orientation;
+    let base = params.shape.hexes(orientation);
+    let pixel = |hex: Hex| hex.to_pixel(orientation, 1.0);
+
+    let (min, max) = base.iter().map(|hex| pixel(*hex)).fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), pos| (min.min(pos), max.max(pos)),
+    );
+    let center = 0.5 * (min + max);
+    let mirror = |hex: Hex| Hex::from_pixel(2.0 * center - pixel(hex), orientation, 1.0);
+
+    let mut tiles = base.iter().copied().collect::<HashSet<_>>();
+    let player_zone = base
+        .iter()
+        .copied()
+        .filter(|hex| pixel(*hex).x < min.x + SPAWN_ZONE_DEPTH)
+        .collect::<Vec<_>>();
+    let zones = player_zone
+        .iter()
+        .flat_map(|hex| [*hex, mirror(*hex)])
+        .collect::<HashSet<_>>();
+    // features go on the player half and get mirrored onto the other
+    let player_half = base
+        .iter()
+        .copied()
+        .filter(|hex| pixel(*hex).x < center.x && !zones.contains(hex))
+        .collect::<Vec<_>>();
+
+    let patch = |rng: &mut StdRng, tiles: &HashSet<Hex>| {
+        let middle = *player_half.choose(rng)?;
+        let radius = rng.gen_range(0..=params.patch_radius);
+        Some(
+            middle
+                .range(radius)
+                .into_iter()
+                .flat_map(|hex| [hex, mirror(hex)])
+                .filter(|hex| tiles.contains(hex) && !zones.contains(hex))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    for _ in 0..params.obstacles {
+        for hex in patch(rng, &tiles)? {
+            tiles.remove(&hex);
+        }
+    }
+    let mut terrain = HashMap::new();
+    for _ in 0..params.grass_patches {
+        for hex in patch(rng, &tiles)? {
+            terrain.insert(hex, Terrain::TallGrass);
+        }
+    }
+
+    let player_start = player_zone
+        .choose_multiple(rng, PLAYER_STARTS)
+        .copied()
+        .collect::<Vec<_>>();
+    // the enemies facing the player's units stand where they do
+    let mut enemy_start = player_start
+        .iter()
+        .map(|hex| mirror(*hex))
+        .collect::<Vec<_>>();
+    let extra = player_zone
+        .iter()
+        .map(|hex| mirror(*hex))
+        .filter(|hex| !enemy_start.contains(hex))
+        .collect::<Vec<_>>();
+    enemy_start.extend(extra.choose_multiple(rng, ENEMY_STARTS - PLAYER_STARTS));
+
+    // islands cut off by the obstacles are dropped
+    let connected = player_start
+        .first()?
+        .walk_distances(|hex| tiles.contains(&hex));
+    if !enemy_start.iter().all(|hex| connected.contains_key(hex)) {
+        return None;
+    }
+    terrain.retain(|hex, _| connected.contains_key(hex));
+
+    Some(BattleFieldLayout {
+        shape: BattleFieldShape::Tiles(
+            base.into_iter()
+                .filter(|hex| connected.contains_key(hex))
+                .collect(),
+        ),
+        orientation,
+        fog_of_war: params.fog_of_war,
+        terrain,
+        player_start,
+        enemy_start,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENERATED: [BattleNode; 2] = [BattleNode::Skirmish, BattleNode::Ambush];
+
+    /// Path length from the hex to every tile, added up
+    fn total_dist(tiles: &HashSet<Hex>, from: Hex) -> usize {
+        let dists = from.walk_distances(|hex| tiles.contains(&hex));
+        dists.values().sum::<i32>() as usize
+    }
+
+    #[test]
+    fn generated_maps_are_connected() {
+        for node in GENERATED {
+            for seed in 0..300 {
+                let layout = generate_battle_field(node, seed);
+                let tiles = layout.hexes().into_iter().collect::<HashSet<_>>();
+                let starts = layout
+                    .player_start
+                    .iter()
+                    .chain(&layout.enemy_start)
+                    .collect::<Vec<_>>();
+
+                assert_eq!(layout.player_start.len(), PLAYER_STARTS);
+                assert_eq!(layout.enemy_start.len(), ENEMY_STARTS);
+                for (i, start) in starts.iter().enumerate() {
+                    assert!(tiles.contains(*start), "{node:?} {seed}: {start:?} off");
+                    assert!(!starts[i + 1..].contains(start), "{node:?} {seed}: repeats");
+                }
+                let reachable = layout.player_start[0].walk_distances(|hex| tiles.contains(&hex));
+                assert_eq!(
+                    reachable.into_keys().collect::<HashSet<_>>(),
+                    tiles,
+                    "{node:?} {seed}: not connected"
+                );
+                assert!(layout.terrain.keys().all(|hex| tiles.contains(hex)));
+            }
+        }
+    }
+
+    #[test]
+    fn generated_maps_are_fair() {
+        for node in GENERATED {
+            for seed in 0..300 {
+                let layout = generate_battle_field(node, seed);
+                let tiles = layout.hexes().into_iter().collect::<HashSet<_>>();
+                let grass_near = |hex: Hex| {
+                    hex.range(3)
+                        .iter()
+                        .filter(|hex| layout.terrain.contains_key(hex))
+                        .count()
+                };
+
+                for (player, enemy) in layout.player_start.iter().zip(&layout.enemy_start) {
+                    assert_eq!(
+                        total_dist(&tiles, *player),
+                        total_dist(&tiles, *enemy),
+                        "{node:?} {seed}: spawns not equally central"
+                    );
+                    assert_eq!(
+                        grass_near(*player),
+                        grass_near(*enemy),
+                        "{node:?} {seed}: cover not equal"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn maps_depend_on_the_seed() {
+        let layout = |seed| {
+            let layout = generate_battle_field(BattleNode::Skirmish, seed);
+            (layout.hexes(), layout.player_start)
+        };
+
+        assert_eq!(layout(7), layout(7));
+        assert!((0..10).any(|seed| layout(seed) != layout(7)));
+        assert!((0..10).any(|seed| layout(seed).0.len() < 96));
+    }
+
+    #[test]
+    fn battle_kinds_change_the_map() {
+        assert_eq!(BattleNode::for_round(1), BattleNode::Skirmish);
+        assert_eq!(BattleNode::for_round(3), BattleNode::Ambush);
+        assert_eq!(BattleNode::for_round(5), BattleNode::Arena);
+
+        let ambush = generate_battle_field(BattleNode::Ambush, 1);
+        assert!(ambush.fog_of_war);
+        assert_eq!(ambush.orientation, HexOrientation::FlatTop);
+        let skirmish = generate_battle_field(BattleNode::Skirmish, 1);
+        assert!(!skirmish.fog_of_war);
+        assert!(skirmish.terrain.is_empty());
+    }
+}
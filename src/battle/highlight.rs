@@ -0,0 +1,169 @@
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    theme::{Theme, TileCue, TileStyle},
+    utils::hex::HexOrientation,
+};
+
+use super::{
+    battle_field::{BattleField, Terrain, Tile},
+    vision::Sight,
+};
+
+const CUE_WIDTH: f32 = 2.0;
+const THICK_CUE_WIDTH: f32 = 5.0;
+const HATCH_SPACING: f32 = 8.0;
+const BLADE_SPACING: f32 = 12.0;
+const BLADE_LENGTH: f32 = 6.0;
+/// How bright the tiles the player can't see are drawn
+const UNSEEN_BRIGHTNESS: f32 = 0.4;
+
+/// Why a tile stands out, drawn in the colour and cue of the theme
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileHighlight {
+    #[default]
+    None,
+    Range,
+    Path,
+    ValidTarget,
+    InvalidTarget,
+}
+
+impl TileHighlight {
+    fn style(self, theme: &Theme) -> TileStyle {
+        match self {
+            Self::None => theme.tile,
+            Self::Range => theme.range,
+            Self::Path => theme.path,
+            Self::ValidTarget => theme.valid_target,
+            Self::InvalidTarget => theme.invalid_target,
+        }
+    }
+}
+
+/// Shape drawn over a tile for the cue of its highlight. It isn't a child
+/// of the tile, as the children of tiles are the units standing on them.
+#[derive(Component)]
+pub struct TileCueShape {
+    pub tile: Entity,
+}
+
+fn hexagon(builder: &mut PathBuilder, radius: f32, orientation: HexOrientation) {
+    // turned like the tile meshes
+    let first = match orientation {
+        HexOrientation::PointyTop => PI / 2.0,
+        HexOrientation::FlatTop => 0.0,
+    };
+    let corner = |i: i32| Vec2::from_angle(first + i as f32 * PI / 3.0) * radius;
+    builder.move_to(corner(0));
+    for i in 1..6 {
+        builder.line_to(corner(i));
+    }
+    builder.close();
+}
+
+/// Diagonal lines across the circle inside the tile
+fn hatch(builder: &mut PathBuilder, radius: f32, direction: Vec2) {
+    let normal = direction.perp();
+    let count = (radius / HATCH_SPACING) as i32;
+    for i in -count..=count {
+        let offset = i as f32 * HATCH_SPACING;
+        let half_length = (radius * radius - offset * offset).sqrt();
+        builder.move_to(normal * offset - direction * half_length);
+        builder.line_to(normal * offset + direction * half_length);
+    }
+}
+
+/// Short upright strokes in staggered rows across the circle inside the tile
+fn blades(builder: &mut PathBuilder, radius: f32) {
+    let count = (radius / BLADE_SPACING) as i32;
+    for row in -count..=count {
+        let stagger = 0.5 * row.rem_euclid(2) as f32;
+        for column in -count..=count {
+            let root = Vec2::new(column as f32 + stagger, row as f32) * BLADE_SPACING;
+            if root.length() + BLADE_LENGTH <= radius {
+                builder.move_to(root);
+                builder.line_to(root + Vec2::Y * BLADE_LENGTH);
+            }
+        }
+    }
+}
+
+fn cue_path(cue: TileCue, tile_size: f32, orientation: HexOrientation) -> Path {
+    // the circle inside the tile, a bit smaller so the cue stays clear of the edges
+    let inner_radius = 0.75 * tile_size;
+
+    match cue {
+        TileCue::Dot => GeometryBuilder::build_as(&shapes::Circle {
+            radius: 0.2 * tile_size,
+            center: Vec2::ZERO,
+        }),
+        _ => {
+            let mut builder = PathBuilder::new();
+            match cue {
+                TileCue::Outline => hexagon(&mut builder, 0.85 * tile_size, orientation),
+                TileCue::ThickOutline => hexagon(&mut builder, 0.8 * tile_size, orientation),
+                TileCue::Hatch => hatch(&mut builder, inner_radius, Vec2::splat(FRAC_1_SQRT_2)),
+                TileCue::Blades => blades(&mut builder, inner_radius),
+                TileCue::None | TileCue::Dot => {}
+            }
+            builder.build()
+        }
+    }
+}
+
+pub fn draw_tile_highlights(
+    theme: Res<Theme>,
+    battle_field: Option<Res<BattleField>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut tile_query: Query<(Ref<TileHighlight>, Ref<Sight>, &mut Handle<ColorMaterial>), With<Tile>>,
+    mut cue_query: Query<(&TileCueShape, &mut Path, &mut Stroke, &mut Visibility)>,
+) {
+    let Some(battle_field) = battle_field else {
+        return;
+    };
+
+    for (cue_shape, mut path, mut stroke, mut visibility) in &mut cue_query {
+        let Ok((highlight, sight, mut material)) = tile_query.get_mut(cue_shape.tile) else {
+            continue;
+        };
+        if !highlight.is_changed() && !sight.is_changed() && !theme.is_changed() {
+            continue;
+        }
+
+        let terrain = battle_field
+            .hex(cue_shape.tile)
+            .map(|hex| battle_field.terrain(&hex))
+            .unwrap_or_default();
+        let style = match (*highlight, terrain) {
+            (TileHighlight::None, Terrain::TallGrass) => theme.tall_grass,
+            _ => highlight.style(&theme),
+        };
+        let color = match *sight {
+            Sight::Seen => style.color,
+            Sight::Unseen => style.color * UNSEEN_BRIGHTNESS,
+        };
+        *material = materials.add(ColorMaterial::from(color));
+        *path = cue_path(
+            style.cue,
+            battle_field.tile_size(),
+            battle_field.orientation(),
+        );
+        *stroke = Stroke::new(
+            theme.cue,
+            if style.cue == TileCue::ThickOutline {
+                THICK_CUE_WIDTH
+            } else {
+                CUE_WIDTH
+            },
+        );
+        *visibility = if style.cue == TileCue::None {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
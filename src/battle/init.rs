@@ -5,11 +5,16 @@ use bevy_mod_picking::{PickableBundle, PickableMesh};
 
 use crate::{
     character::{AttributeType, Group},
-    utils::bar::Bar,
+    utils::bar::UnitBars,
     GameState,
 };
 
-use super::{battle_field::BattleField, lifecycle::LifeState, BattleQueue, BattleState};
+use super::{
+    battle_field::BattleField,
+    lifecycle::LifeState,
+    log::{BattleLogEvent, LogUnit},
+    BattleQueue, BattleState,
+};
 
 pub fn get_scaling(image: Option<&Image>, tile_size: f32) -> Vec3 {
     image
@@ -22,11 +27,13 @@ pub fn setup_battle(
     asset_server: Res<AssetServer>,
     images: Res<Assets<Image>>,
     game_state: Res<GameState>,
+    unit_bars: Res<UnitBars>,
     battle_field: Res<BattleField>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut next_state: ResMut<NextState<BattleState>>,
+    mut ev_battle_log: EventWriter<BattleLogEvent>,
 ) {
-    let mut queue = BattleQueue::default();
+    let mut units = vec![];
 
     let mut player_start = game_state
         .battle_field_layout
@@ -85,18 +92,34 @@ pub fn setup_battle(
                                 .unwrap_or(Vec2::ZERO),
                         ))),
                     ),
-                    Bar::new(AttributeType::HitPoints),
+                    unit_bars.0.clone(),
                     LifeState::Alive,
                 ))
                 .id();
 
-            queue.queue.push_back(id);
+            units.push((id, character));
         });
     }
 
+    let queue = BattleQueue::new(
+        units
+            .iter()
+            .map(|(id, character)| (*id, character.bundle.attributes.value(AttributeType::Speed))),
+    );
+    let (first, character) = units
+        .into_iter()
+        .find(|(id, _)| *id == queue.get_current())
+        .expect("Battle without units");
+    ev_battle_log.send(BattleLogEvent::TurnStarted {
+        unit: LogUnit::new(first, &character.bundle.name),
+    });
+
     commands.insert_resource(queue);
 
-    next_state.set(BattleState::AbilityChoosingPlayer);
+    next_state.set(match character.bundle.group {
+        Group::Player => BattleState::AbilityChoosingPlayer,
+        Group::Enemy => BattleState::AbilityCastingEnemy,
+    });
 }
 
 pub fn resize_meshes_for_sprites(
@@ -0,0 +1,264 @@
+use bevy::prelude::*;
+use fluent::fluent_args;
+
+use crate::{
+    character::{Abilities, AttributeType, Attributes, CharacterCategory, CharacterName, Group},
+    locale::Locale,
+};
+
+use super::{battle_field::Tile, lifecycle::LifeState, ui::RIGHT_PANE_WIDTH, vision::Concealed};
+
+#[derive(Component)]
+pub struct UnitInspectionPanel;
+
+/// Unit shown in the inspection panel, the hovered one takes precedence over
+/// the one pinned by clicking
+#[derive(Resource, Default)]
+pub struct InspectedUnit {
+    hovered: Option<Entity>,
+    pinned: Option<Entity>,
+}
+
+impl InspectedUnit {
+    fn get(&self) -> Option<Entity> {
+        self.hovered.or(self.pinned)
+    }
+}
+
+pub fn build_inspection_panel(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    flex_direction: FlexDirection::Column,
+                    margin: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            UnitInspectionPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_sections([
+                    TextSection::new(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Medium.ttf"),
+                            font_size: 20.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    TextSection::new(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Medium.ttf"),
+                            font_size: 12.0,
+                            color: Color::rgb(0.85, 0.85, 0.85),
+                        },
+                    ),
+                ])
+                .with_style(Style {
+                    max_size: Size::width(Val::Px(RIGHT_PANE_WIDTH - 10.0)),
+                    ..default()
+                }),
+            );
+        });
+}
+
+/// Picks the unit under the mouse, either directly or through its tile
+/// while targeting makes the units themselves unpickable
+pub fn select_inspected_unit(
+    mut inspected: ResMut<InspectedUnit>,
+    unit_query: Query<
+        (Entity, &Interaction, &LifeState),
+        (With<CharacterName>, Without<Concealed>),
+    >,
+    tile_query: Query<(&Interaction, &Children), With<Tile>>,
+    life_query: Query<&LifeState, Without<Concealed>>,
+) {
+    // units out of sight can't be inspected either
+    let alive = |unit: &Entity| life_query.get(*unit) == Ok(&LifeState::Alive);
+
+    let from_units = unit_query
+        .iter()
+        .filter(|(_, interaction, life_state)| {
+            **interaction != Interaction::None && **life_state == LifeState::Alive
+        })
+        .map(|(unit, interaction, _)| (unit, *interaction));
+    let from_tiles = tile_query
+        .iter()
+        .filter(|(interaction, _)| **interaction != Interaction::None)
+        .filter_map(|(interaction, children)| {
+            children
+                .iter()
+                .find(|child| alive(child))
+                .map(|unit| (*unit, *interaction))
+        });
+
+    let hovered = from_units.chain(from_tiles).next();
+    inspected.hovered = hovered.map(|(unit, _)| unit);
+
+    if let Some((unit, Interaction::Clicked)) = hovered {
+        inspected.pinned = Some(unit);
+    }
+    if inspected.pinned.is_some_and(|pinned| !alive(&pinned)) {
+        inspected.pinned = None;
+    }
+}
+
+fn describe_unit(
+    category: &CharacterCategory,
+    group: &Group,
+    attributes: &Attributes,
+    abilities: &Abilities,
+    locale: &Locale,
+) -> String {
+    let mut lines = vec![locale.format(
+        "inspect-unit",
+        &fluent_args![
+            "category" => category.name(locale),
+            "group" => group.name(locale)
+        ],
+    )];
+
+    lines.push(format!("\n{}", locale.get("inspect-attributes")));
+    lines.extend(AttributeType::ALL.iter().filter_map(|typ| {
+        attributes
+            .0
+            .get(typ)
+            .map(|attribute| attribute.describe(*typ, locale))
+    }));
+
+    lines.push(format!("\n{}", locale.get("inspect-abilities")));
+    let mut ability_lines = abilities
+        .0
+        .values()
+        .map(|ability| {
+            locale.format(
+                "inspect-ability",
+                &fluent_args![
+                    "ability" => locale.ability_name(&ability.name),
+                    "range" => ability.range
+                ],
+            )
+        })
+        .collect::<Vec<_>>();
+    ability_lines.sort();
+    lines.extend(ability_lines);
+
+    let statuses = AttributeType::ALL
+        .iter()
+        .filter_map(|typ| Some((typ, attributes.0.get(typ)?)))
+        .flat_map(|(typ, attribute)| {
+            attribute
+                .modifiers()
+                .iter()
+                .filter(|modifier| modifier.duration.is_some())
+                .map(move |modifier| {
+                    locale.format(
+                        "inspect-status",
+                        &fluent_args![
+                            "attribute" => typ.name(locale),
+                            "modifier" => modifier.describe(locale)
+                        ],
+                    )
+                })
+        })
+        .collect::<Vec<_>>();
+    if !statuses.is_empty() {
+        lines.push(format!("\n{}", locale.get("inspect-statuses")));
+        lines.extend(statuses);
+    }
+
+    lines.join("\n")
+}
+
+pub fn update_inspection_panel(
+    inspected: Res<InspectedUnit>,
+    locale: Res<Locale>,
+    unit_query: Query<(
+        &CharacterName,
+        &CharacterCategory,
+        &Group,
+        &Attributes,
+        &Abilities,
+    )>,
+    mut panel_query: Query<(&mut Style, &Children), With<UnitInspectionPanel>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let unit = inspected
+        .get()
+        .and_then(|unit| unit_query.get(unit).ok())
+        .map(|(name, category, group, attributes, abilities)| {
+            (
+                format!("{}\n", locale.character_name(&name.0)),
+                describe_unit(category, group, attributes, abilities, &locale),
+            )
+        });
+
+    for (mut style, children) in &mut panel_query {
+        let display = if unit.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+
+        let Some((name, description)) = &unit else {
+            continue;
+        };
+        for child in children {
+            let Ok(mut text) = text_query.get_mut(*child) else {
+                continue;
+            };
+            if text.sections[0].value != *name || text.sections[1].value != *description {
+                text.sections[0].value = name.clone();
+                text.sections[1].value = description.clone();
+            }
+        }
+    }
+}
+
+pub fn cleanup_inspected_unit(mut inspected: ResMut<InspectedUnit>) {
+    *inspected = InspectedUnit::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        character::{AttributeModifier, ModifierSource},
+        locale::Language,
+    };
+
+    #[test]
+    fn lists_attributes_and_statuses() {
+        let mut attributes = Attributes::with_overrides(&[(AttributeType::Defense, 8)]);
+        let defense = attributes.0.get_mut(&AttributeType::Defense).unwrap();
+        defense.add_modifier(
+            AttributeModifier::flat(
+                -2,
+                ModifierSource::Ability {
+                    name: "sunder".to_string(),
+                    caster: "mushroom".to_string(),
+                },
+            )
+            .with_duration(3),
+        );
+
+        let description = describe_unit(
+            &CharacterCategory::Human,
+            &Group::Player,
+            &attributes,
+            &Abilities::default(),
+            &Locale::new(Language::English),
+        );
+
+        assert!(description.contains("Defense: 6 (base 8, -2 from mushroom's sunder (3 turns))"));
+        assert!(description.contains("Statuses\nDefense -2 from mushroom's sunder (3 turns)"));
+    }
+}
@@ -1,16 +1,22 @@
 use crate::{
     abilities::{Ability, AbilityProximity, AbilityTargetType, AbilityType, TurnEvent},
     character::{Abilities, CharacterName, Group},
-    GameState, HOVERED_BUTTON, NORMAL_BUTTON,
-};
-use bevy::{
-    ecs::query::{QueryIter, ReadOnlyWorldQuery},
-    prelude::*,
+    input::{Actions, InputAction, InputMap, HOTKEYS},
+    locale::Locale,
+    theme::Theme,
+    utils::hex::Hex,
+    GameState,
 };
+use bevy::prelude::*;
 use bevy_mod_picking::PickableBundle;
+use fluent::fluent_args;
 
 use super::{
     battle_field::{BattleField, Tile},
+    highlight::TileHighlight,
+    tooltip::HoveredTarget,
+    undo::{PendingTurn, TurnButton, TurnSnapshots},
+    vision::FogOfWar,
     Battle, BattleQueue, BattleState,
 };
 
@@ -19,32 +25,48 @@ pub struct AbilityButton {
     ability_name: String,
 }
 
+impl AbilityButton {
+    pub fn ability_name(&self) -> &str {
+        &self.ability_name
+    }
+}
+
 #[derive(Resource)]
 pub struct ChosenAbility {
     ability: Ability,
     allowed_targets: Vec<Entity>,
 }
 
-#[derive(Component)]
-pub struct Highlighted {
-    color: Handle<ColorMaterial>,
+impl ChosenAbility {
+    pub fn ability(&self) -> &Ability {
+        &self.ability
+    }
 }
 
 fn get_ability_range(
     ability: &Ability,
     caster_tile: Entity,
     battle_field: &BattleField,
+    fog: &FogOfWar,
     tile_children_query: &Query<&Children, With<Tile>>,
 ) -> Vec<Entity> {
+    let caster_hex = battle_field
+        .hex(caster_tile)
+        .expect("Caster hex not on the battle field");
+    // units walk around the gaps while everything else flies straight over
+    let walk_distances = battle_field.walk_distances(caster_hex);
+    let dist = |target_hex: Hex| match ability.r#type {
+        AbilityType::Movement => walk_distances.get(&target_hex).copied(),
+        _ => Some(target_hex.dist(caster_hex)),
+    };
+
     battle_field
         .tiles()
         .iter()
+        // what's out of sight can't be aimed at
+        .filter(|(target_hex, _)| fog.sees(Group::Player, **target_hex))
         .filter_map(|(target_hex, tile)| {
-            let caster_hex = battle_field
-                .hex(caster_tile)
-                .expect("Caster hex not on the battle field");
-
-            if target_hex.dist(caster_hex) <= ability.range {
+            if dist(*target_hex).is_some_and(|dist| dist <= ability.range) {
                 if let AbilityType::Targeted {
                     ab_type: _,
                     proximity: AbilityProximity::Melee,
@@ -70,200 +92,403 @@ fn get_ability_range(
 
 pub fn cancel_action(
     mut commands: Commands,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    keys: Res<Input<KeyCode>>,
+    actions: Actions,
     buttons: Res<Input<MouseButton>>,
-    mut colors_query: Query<(Entity, &mut Handle<ColorMaterial>), With<Tile>>,
+    mut highlight_query: Query<&mut TileHighlight, With<Tile>>,
     mut next_state: ResMut<NextState<BattleState>>,
 ) {
-    if keys.just_pressed(KeyCode::Escape) || buttons.just_pressed(MouseButton::Right) {
+    if actions.just_pressed(InputAction::Cancel) || buttons.just_pressed(MouseButton::Right) {
         commands.remove_resource::<ChosenAbility>();
         next_state.set(BattleState::AbilityChoosingPlayer);
-        clean_highlights(&mut commands, &mut materials, &mut colors_query.iter_mut());
+        clean_highlights(highlight_query.iter_mut());
     }
 }
 
-fn clean_highlights<F>(
-    commands: &mut Commands,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    iter: &mut QueryIter<(Entity, &mut Handle<ColorMaterial>), F>,
-) where
-    F: ReadOnlyWorldQuery,
-{
-    for (entity, mut color_handle) in iter {
-        commands.entity(entity).remove::<Highlighted>();
-
-        *color_handle = materials.add(ColorMaterial::from(Color::GRAY));
+fn clean_highlights<'a>(highlights: impl Iterator<Item = Mut<'a, TileHighlight>>) {
+    for mut highlight in highlights {
+        highlight.set_if_neq(TileHighlight::None);
+    }
+}
+
+fn target_type(
+    children: Option<&Children>,
+    group_query: &Query<&Group, (Without<Button>, Without<Tile>)>,
+) -> AbilityTargetType {
+    children
+        .and_then(|children| {
+            children.iter().next().map(|child| {
+                match *group_query
+                    .get(*child)
+                    .expect("Missing group for a character on a tile")
+                {
+                    Group::Player => AbilityTargetType::Ally,
+                    Group::Enemy => AbilityTargetType::Enemy,
+                }
+            })
+        })
+        .unwrap_or(AbilityTargetType::Empty)
+}
+
+impl ChosenAbility {
+    fn is_valid(&self, tile: Entity, target_type: AbilityTargetType) -> bool {
+        self.ability.target.contains(target_type) && self.allowed_targets.contains(&tile)
+    }
+
+    /// Highlight of a tile while it isn't hovered
+    fn resting_highlight(&self, tile: Entity) -> TileHighlight {
+        if self.allowed_targets.contains(&tile) {
+            TileHighlight::Range
+        } else {
+            TileHighlight::None
+        }
+    }
+}
+
+/// Tile selected with the keyboard while targeting
+#[derive(Resource, Default)]
+pub struct TargetCursor {
+    tile: Option<Entity>,
+}
+
+/// Keyboard counterpart of hovering and clicking tiles
+pub enum TargetInput {
+    Hover(Entity),
+    Leave(Entity),
+    Confirm(Entity),
+}
+
+pub fn move_target_cursor(
+    actions: Actions,
+    res_queue: Res<BattleQueue>,
+    battle_field: Option<Res<BattleField>>,
+    res_ability: Option<Res<ChosenAbility>>,
+    mut cursor: ResMut<TargetCursor>,
+    parent_query: Query<&Parent, With<CharacterName>>,
+    tile_query: Query<Option<&Children>, With<Tile>>,
+    group_query: Query<&Group, (Without<Button>, Without<Tile>)>,
+    mut ev_target: EventWriter<TargetInput>,
+) {
+    let (Some(battle_field), Some(chosen)) = (battle_field, res_ability) else {
+        return;
+    };
+
+    let current = cursor.tile.unwrap_or_else(|| {
+        parent_query
+            .get(res_queue.get_current())
+            .expect("Missing tile for the active unit")
+            .get()
+    });
+    let current_hex = battle_field
+        .hex(current)
+        .expect("Cursor not on the battle field");
+
+    let step = [
+        (InputAction::CursorUp, IVec2::NEG_Y),
+        (InputAction::CursorDown, IVec2::Y),
+        (InputAction::CursorLeft, IVec2::NEG_X),
+        (InputAction::CursorRight, IVec2::X),
+    ]
+    .into_iter()
+    .find(|(action, _)| actions.just_pressed(*action))
+    .map(|(_, step)| step);
+
+    let cycle = if actions.just_pressed(InputAction::NextTarget) {
+        Some(1)
+    } else if actions.just_pressed(InputAction::PreviousTarget) {
+        Some(-1)
+    } else {
+        None
+    };
+
+    let mut next = cursor.tile;
+    if let Some(step) = step {
+        // steps along the rows and columns of the map
+        let kind = battle_field.orientation().offset_kind();
+        let next_hex = Hex::from_offset(current_hex.to_offset(kind) + step, kind);
+        if let Some(tile) = battle_field.tile(&next_hex) {
+            next = Some(tile);
+        }
+    } else if let Some(cycle) = cycle {
+        let mut targets = chosen
+            .allowed_targets
+            .iter()
+            .copied()
+            .filter(|tile| {
+                let children = tile_query.get(*tile).expect("Missing target tile");
+                chosen.is_valid(*tile, target_type(children, &group_query))
+            })
+            .collect::<Vec<_>>();
+        // the allowed targets come from a map, so sort them to cycle in reading order
+        targets.sort_by_key(|tile| {
+            let pos = battle_field
+                .hex(*tile)
+                .expect("Target not on the battle field")
+                .to_offset(battle_field.orientation().offset_kind());
+            (pos.y, pos.x)
+        });
+
+        if !targets.is_empty() {
+            let len = targets.len() as i32;
+            let index = match targets.iter().position(|tile| Some(*tile) == cursor.tile) {
+                Some(index) => (index as i32 + cycle).rem_euclid(len),
+                None if cycle > 0 => 0,
+                None => len - 1,
+            };
+            next = Some(targets[index as usize]);
+        }
+    }
+
+    if next != cursor.tile {
+        if let Some(previous) = cursor.tile {
+            ev_target.send(TargetInput::Leave(previous));
+        }
+        if let Some(next) = next {
+            ev_target.send(TargetInput::Hover(next));
+        }
+        cursor.tile = next;
+    }
+
+    if actions.just_pressed(InputAction::Confirm) {
+        if let Some(tile) = cursor.tile {
+            ev_target.send(TargetInput::Confirm(tile));
+        }
     }
 }
 
 pub fn choose_target(
     mut commands: Commands,
     res_queue: ResMut<BattleQueue>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    battle_field: Option<Res<BattleField>>,
+    theme: Res<Theme>,
     res_ability: Option<Res<ChosenAbility>>,
+    mut pending_turn: ResMut<PendingTurn>,
+    mut hovered_target: ResMut<HoveredTarget>,
     mut ev_ability: EventWriter<TurnEvent>,
-    mut interaction_query: ParamSet<(
-        Query<
-            (
-                Entity,
-                &Interaction,
-                &mut Handle<ColorMaterial>,
-                Option<&Children>,
-                Option<&Highlighted>,
-            ),
-            (Changed<Interaction>, Without<Button>, With<Tile>),
-        >,
-        Query<(Entity, &mut Handle<ColorMaterial>), With<Tile>>,
-    )>,
+    mut ev_target: EventReader<TargetInput>,
+    changed_query: Query<
+        (Entity, &Interaction),
+        (Changed<Interaction>, Without<Button>, With<Tile>),
+    >,
+    mut tile_query: Query<
+        (Entity, &mut TileHighlight, Option<&Children>),
+        (Without<Button>, With<Tile>),
+    >,
+    parent_query: Query<&Parent, With<CharacterName>>,
     group_query: Query<&Group, (Without<Button>, Without<Tile>)>,
     mut ability_buttons_query: Query<&mut BackgroundColor, (With<AbilityButton>, With<Button>)>,
+    snapshots: TurnSnapshots,
 ) {
+    let (Some(chosen), Some(battle_field)) = (res_ability, battle_field) else {
+        return;
+    };
+
+    let inputs = changed_query
+        .iter()
+        .map(|(entity, interaction)| (entity, *interaction))
+        .chain(ev_target.iter().map(|input| match *input {
+            TargetInput::Hover(tile) => (tile, Interaction::Hovered),
+            TargetInput::Leave(tile) => (tile, Interaction::None),
+            TargetInput::Confirm(tile) => (tile, Interaction::Clicked),
+        }))
+        .collect::<Vec<_>>();
+
     let mut should_unhighlight = false;
 
-    for (entity, interaction, mut color_handle, children, highlighted) in
-        interaction_query.p0().iter_mut()
-    {
-        let target_type = children
-            .and_then(|children| {
-                children.iter().next().map(|child| {
-                    match *group_query
-                        .get(*child)
-                        .expect("Missing group for a character on a tile")
-                    {
-                        Group::Player => AbilityTargetType::Ally,
-                        Group::Enemy => AbilityTargetType::Enemy,
+    for (entity, interaction) in inputs {
+        let Ok((_, _, children)) = tile_query.get(entity) else {
+            continue;
+        };
+
+        let is_valid = chosen.is_valid(entity, target_type(children, &group_query));
+
+        match (interaction, is_valid) {
+            (Interaction::Hovered, _) => {
+                clear_path(&chosen, &mut tile_query);
+                if is_valid && chosen.ability.r#type == AbilityType::Movement {
+                    let path = walk_path(
+                        &battle_field,
+                        &parent_query,
+                        res_queue.get_current(),
+                        entity,
+                    );
+                    for tile in path {
+                        if let Ok((_, mut highlight, _)) = tile_query.get_mut(tile) {
+                            *highlight = TileHighlight::Path;
+                        }
                     }
-                })
-            })
-            .unwrap_or(AbilityTargetType::Empty);
-
-        if let Some(ChosenAbility {
-            ref ability,
-            ref allowed_targets,
-        }) = res_ability.as_ref().map(|res| res.as_ref())
-        {
-            let is_valid =
-                ability.target.contains(target_type) && allowed_targets.contains(&entity);
-
-            match (*interaction, is_valid) {
-                (Interaction::Hovered, true) => {
-                    *color_handle = materials.add(ColorMaterial::from(Color::LIME_GREEN));
-                }
-                (Interaction::Hovered, false) => {
-                    *color_handle = materials.add(ColorMaterial::from(Color::RED));
                 }
-                (Interaction::Clicked, true) => {
-                    ev_ability.send(TurnEvent::Ability {
-                        ability: ability.clone(),
-                        by: res_queue.get_current(),
-                        on: entity,
-                    });
 
-                    commands.remove_resource::<ChosenAbility>();
+                let (_, mut highlight, _) = tile_query.get_mut(entity).expect("Missing tile");
+                *highlight = if is_valid {
+                    TileHighlight::ValidTarget
+                } else {
+                    TileHighlight::InvalidTarget
+                };
+                hovered_target.0 = is_valid.then_some(entity);
+            }
+            (Interaction::Clicked, true) => {
+                hovered_target.0 = None;
+                pending_turn.push(chosen.ability.clone(), snapshots.take());
+                ev_ability.send(TurnEvent::Ability {
+                    ability: chosen.ability.clone(),
+                    by: res_queue.get_current(),
+                    on: entity,
+                });
+
+                commands.remove_resource::<ChosenAbility>();
 
-                    for mut color in &mut ability_buttons_query {
-                        *color = NORMAL_BUTTON.into();
-                    }
+                for mut color in &mut ability_buttons_query {
+                    *color = theme.normal_button.into();
+                }
 
-                    should_unhighlight = true;
+                should_unhighlight = true;
+                break;
+            }
+            (Interaction::Clicked, false) => (),
+            (Interaction::None, _) => {
+                if hovered_target.0 == Some(entity) {
+                    hovered_target.0 = None;
+                    clear_path(&chosen, &mut tile_query);
                 }
-                (Interaction::Clicked, false) => (),
-                (Interaction::None, _) => match highlighted {
-                    Some(Highlighted { color }) => {
-                        *color_handle = color.clone();
-                    }
-                    _ => {
-                        *color_handle = materials.add(ColorMaterial::from(Color::GRAY));
-                    }
-                },
+                let (_, mut highlight, _) = tile_query.get_mut(entity).expect("Missing tile");
+                *highlight = chosen.resting_highlight(entity);
             }
         }
     }
 
     if should_unhighlight {
-        clean_highlights(
-            &mut commands,
-            &mut materials,
-            &mut interaction_query.p1().iter_mut(),
-        );
+        clean_highlights(tile_query.iter_mut().map(|(_, highlight, _)| highlight));
     }
 }
 
+/// Puts the tiles of the walking path shown before back to how they were
+fn clear_path(
+    chosen: &ChosenAbility,
+    tile_query: &mut Query<
+        (Entity, &mut TileHighlight, Option<&Children>),
+        (Without<Button>, With<Tile>),
+    >,
+) {
+    for (tile, mut highlight, _) in tile_query.iter_mut() {
+        if *highlight == TileHighlight::Path {
+            *highlight = chosen.resting_highlight(tile);
+        }
+    }
+}
+
+/// Tiles between the caster and the hovered tile which it would walk over
+fn walk_path(
+    battle_field: &BattleField,
+    parent_query: &Query<&Parent, With<CharacterName>>,
+    caster: Entity,
+    target_tile: Entity,
+) -> Vec<Entity> {
+    let Some(caster_hex) = parent_query
+        .get(caster)
+        .ok()
+        .and_then(|tile| battle_field.hex(tile.get()))
+    else {
+        return vec![];
+    };
+    let Some(target_hex) = battle_field.hex(target_tile) else {
+        return vec![];
+    };
+
+    let path = battle_field
+        .walk_path(caster_hex, target_hex)
+        .unwrap_or_default();
+    path.iter()
+        .skip(1)
+        .take(path.len().saturating_sub(2))
+        .filter_map(|hex| battle_field.tile(hex))
+        .collect()
+}
+
+/// Button of the available actions row pressed with a hotkey this frame
+pub fn hotkey_button(
+    actions: &Actions,
+    node_query: &Query<&Children, With<AvailableActionsNode>>,
+) -> Option<Entity> {
+    let index = actions.hotkey()?;
+    node_query.iter().next()?.get(index).copied()
+}
+
 pub fn choose_action(
     mut commands: Commands,
     res_queue: Res<BattleQueue>,
     battle_field: Option<Res<BattleField>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut interaction_query: ParamSet<(
-        Query<
-            (&Interaction, &mut BackgroundColor, &AbilityButton),
-            (Changed<Interaction>, With<Button>),
-        >,
-        Query<&mut Handle<ColorMaterial>, (Without<Button>, With<Tile>)>,
-    )>,
+    fog: Res<FogOfWar>,
+    theme: Res<Theme>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &AbilityButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut highlight_query: Query<&mut TileHighlight, (Without<Button>, With<Tile>)>,
     abilities_query: Query<&Abilities, Without<Button>>,
     parent_query: Query<&Parent, With<CharacterName>>,
     tile_children_query: Query<&Children, With<Tile>>,
+    actions: Actions,
+    node_query: Query<&Children, With<AvailableActionsNode>>,
+    ability_button_query: Query<&AbilityButton>,
     mut next_state: ResMut<NextState<BattleState>>,
 ) {
     let mut allowed_targets = vec![];
 
-    for (interaction, mut color, ability_button) in &mut interaction_query.p0() {
+    let mut clicked = hotkey_button(&actions, &node_query)
+        .and_then(|button| ability_button_query.get(button).ok())
+        .map(|button| button.ability_name.clone());
+
+    for (interaction, mut color, ability_button) in &mut interaction_query {
         match *interaction {
             Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
+                *color = theme.hovered_button.into();
             }
             Interaction::Clicked => {
-                let abilities = abilities_query
-                    .get(res_queue.get_current())
-                    .expect("Can't find abilities for active entity!");
-
-                let chosen_ability = abilities
-                    .0
-                    .get(&ability_button.ability_name)
-                    .expect("Chosen ability can't be found for the current active entity");
-
-                let player_tile = parent_query
-                    .get(res_queue.get_current())
-                    .expect("Missing tile for player")
-                    .get();
-
-                allowed_targets = get_ability_range(
-                    chosen_ability,
-                    player_tile,
-                    battle_field.as_ref().expect("Missing battlefield"),
-                    &tile_children_query,
-                );
-
-                commands.insert_resource(ChosenAbility {
-                    ability: chosen_ability.clone(),
-                    allowed_targets: allowed_targets.clone(),
-                });
-
-                next_state.set(BattleState::AbilityTargeting);
-
-                break;
+                clicked = Some(ability_button.ability_name.clone());
             }
             Interaction::None => {
-                *color = NORMAL_BUTTON.into();
+                *color = theme.normal_button.into();
             }
         }
     }
 
-    for tile in allowed_targets {
-        let mut color_query = interaction_query.p1();
+    if let Some(ability_name) = clicked {
+        let abilities = abilities_query
+            .get(res_queue.get_current())
+            .expect("Can't find abilities for active entity!");
 
-        let mut color_handle = color_query
-            .get_mut(tile)
-            .expect("Missing entity to highlight");
+        let chosen_ability = abilities
+            .0
+            .get(&ability_name)
+            .expect("Chosen ability can't be found for the current active entity");
+
+        let player_tile = parent_query
+            .get(res_queue.get_current())
+            .expect("Missing tile for player")
+            .get();
+
+        allowed_targets = get_ability_range(
+            chosen_ability,
+            player_tile,
+            battle_field.as_ref().expect("Missing battlefield"),
+            &fog,
+            &tile_children_query,
+        );
 
-        let highlight_color = materials.add(ColorMaterial::from(Color::rgba(0.62, 1.0, 0.53, 1.0)));
-        commands.entity(tile).insert(Highlighted {
-            color: highlight_color.clone(),
+        commands.insert_resource(ChosenAbility {
+            ability: chosen_ability.clone(),
+            allowed_targets: allowed_targets.clone(),
         });
 
-        *color_handle = highlight_color;
+        next_state.set(BattleState::AbilityTargeting);
+    }
+
+    for tile in allowed_targets {
+        let mut highlight = highlight_query
+            .get_mut(tile)
+            .expect("Missing entity to highlight");
+
+        *highlight = TileHighlight::Range;
     }
 }
 
@@ -274,11 +499,25 @@ pub fn setup_available_actions(
     mut commands: Commands,
     res_queue: Res<BattleQueue>,
     asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    theme: Res<Theme>,
+    input_map: Res<InputMap>,
+    pending_turn: Option<Res<PendingTurn>>,
     mut query_node: Query<Entity, With<AvailableActionsNode>>,
     query_abilities: Query<&Abilities, Without<AvailableActionsNode>>,
 ) {
     let caster = res_queue.get_current();
 
+    let new_turn;
+    let pending_turn = match pending_turn.as_deref() {
+        Some(pending_turn) if pending_turn.unit() == caster => pending_turn,
+        _ => {
+            new_turn = PendingTurn::new(caster);
+            commands.insert_resource(PendingTurn::new(caster));
+            &new_turn
+        }
+    };
+
     let abilities = query_abilities
         .get(caster)
         .expect("Couldn't find abilities of current active entity");
@@ -286,45 +525,86 @@ pub fn setup_available_actions(
     for entity in query_node.iter_mut() {
         commands.entity(entity).despawn_descendants();
 
-        let children = abilities
+        let button = |commands: &mut Commands, label: &str| {
+            commands
+                .spawn(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(250.0), Val::Px(65.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: theme.normal_button.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Medium.ttf"),
+                                font_size: 30.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        }),
+                    );
+                })
+                .id()
+        };
+
+        let label = |index: usize, name: String| {
+            if index < HOTKEYS.len() {
+                locale.format(
+                    "battle-hotkey",
+                    &fluent_args!["number" => index + 1, "label" => name],
+                )
+            } else {
+                name
+            }
+        };
+
+        // sorted so every ability keeps its number between turns
+        let mut usable = abilities
             .0
             .iter()
-            .map(|(name, _)| {
-                commands
-                    .spawn(ButtonBundle {
-                        style: Style {
-                            size: Size::new(Val::Px(250.0), Val::Px(65.0)),
-                            // horizontally center child text
-                            justify_content: JustifyContent::Center,
-                            // vertically center child text
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },
-                        background_color: NORMAL_BUTTON.into(),
-                        ..default()
-                    })
-                    .insert(AbilityButton {
-                        ability_name: name.clone(),
-                    })
-                    .with_children(|parent| {
-                        parent.spawn(
-                            TextBundle::from_section(
-                                name.clone(),
-                                TextStyle {
-                                    font: asset_server.load("fonts/FiraSans-Medium.ttf"),
-                                    font_size: 30.0,
-                                    color: Color::WHITE,
-                                },
-                            )
-                            .with_style(Style {
-                                margin: UiRect::all(Val::Px(5.0)),
-                                ..default()
-                            }),
-                        );
-                    })
-                    .id()
-            })
+            .filter(|(_, ability)| pending_turn.can_use(ability))
+            .map(|(name, _)| name)
             .collect::<Vec<_>>();
+        usable.sort();
+
+        let mut children = vec![];
+        for (index, name) in usable.into_iter().enumerate() {
+            let button = button(&mut commands, &label(index, locale.ability_name(name)));
+            commands.entity(button).insert(AbilityButton {
+                ability_name: name.clone(),
+            });
+            children.push(button);
+        }
+
+        if pending_turn.can_undo() {
+            let undo = button(
+                &mut commands,
+                &label(
+                    children.len(),
+                    locale.format(
+                        "battle-undo",
+                        &fluent_args!["keys" => input_map.hint(InputAction::Undo)],
+                    ),
+                ),
+            );
+            children.push(commands.entity(undo).insert(TurnButton::Undo).id());
+        }
+        let end_turn = button(
+            &mut commands,
+            &label(children.len(), locale.get("battle-end-turn")),
+        );
+        children.push(commands.entity(end_turn).insert(TurnButton::EndTurn).id());
 
         commands.entity(entity).push_children(children.as_ref());
     }
@@ -344,7 +624,14 @@ pub fn cleanup_battle(
         .retain(|char| char.bundle.group == Group::Player);
 }
 
-pub fn init_targeting(mut commands: Commands, char_query: Query<Entity, With<CharacterName>>) {
+pub fn init_targeting(
+    mut commands: Commands,
+    mut cursor: ResMut<TargetCursor>,
+    mut hovered_target: ResMut<HoveredTarget>,
+    char_query: Query<Entity, With<CharacterName>>,
+) {
+    cursor.tile = None;
+    hovered_target.0 = None;
     for chara in char_query.iter() {
         commands.entity(chara).remove::<PickableBundle>();
     }
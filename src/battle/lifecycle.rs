@@ -1,9 +1,12 @@
 use bevy::prelude::*;
 
-use crate::character::{CharacterName, Group};
+use crate::{
+    character::{AttributeType, Attributes, CharacterName, Group},
+    locale::Locale,
+};
 
 use super::{
-    log::BattleLogEvent,
+    log::{BattleLogEvent, LogUnit},
     resolution::BattleResolution,
     {BattleQueue, BattleState},
 };
@@ -26,20 +29,22 @@ pub fn handle_lifecycle_event(
     mut ev_battle_log: EventWriter<BattleLogEvent>,
     mut group_alive_query: Query<(&mut LifeState, &Group)>,
     mut vis_query: Query<(&mut Visibility, &CharacterName)>,
+    mut attributes_query: Query<(&CharacterName, &mut Attributes)>,
     mut next_state: ResMut<NextState<BattleState>>,
+    locale: Res<Locale>,
 ) {
     for lifecycle_event in ev_lifecycle.iter() {
         match lifecycle_event {
             BattleLifecycleEvent::CharacterDied(entity) => {
                 let (mut life_state, group) = group_alive_query.get_mut(*entity).unwrap();
                 *life_state = LifeState::Dead;
-                if *group == Group::Enemy {
-                    if let Ok((mut visibility, name)) = vis_query.get_mut(*entity) {
+                if let Ok((mut visibility, name)) = vis_query.get_mut(*entity) {
+                    if *group == Group::Enemy {
                         *visibility = Visibility::Hidden;
-                        ev_battle_log.send(BattleLogEvent {
-                            message: format!("{} defeated!", name.0),
-                        })
                     }
+                    ev_battle_log.send(BattleLogEvent::Died {
+                        unit: LogUnit::new(*entity, name),
+                    })
                 }
             }
             BattleLifecycleEvent::EndTurn => {
@@ -71,8 +76,28 @@ pub fn handle_lifecycle_event(
                     let res_queue = res_queue
                         .as_mut()
                         .expect("Turn ended before battle queue got initialized");
-                    res_queue.queue.rotate_left(1);
+
+                    let current = res_queue.get_current();
+                    if let Ok((name, mut attributes)) = attributes_query.get_mut(current) {
+                        for (at_type, attribute) in attributes.0.iter_mut() {
+                            for expired in attribute.tick_modifiers() {
+                                ev_battle_log.send(BattleLogEvent::StatusExpired {
+                                    unit: LogUnit::new(current, name),
+                                    attribute: *at_type,
+                                    change: expired.kind,
+                                    source: expired.source.describe(&locale),
+                                });
+                            }
+                        }
+                    }
+
+                    res_queue.next_turn();
                     let active_entity = res_queue.get_current();
+                    if let Ok((name, _)) = attributes_query.get(active_entity) {
+                        ev_battle_log.send(BattleLogEvent::TurnStarted {
+                            unit: LogUnit::new(active_entity, name),
+                        });
+                    }
                     let (_, group) = group_alive_query
                         .get(active_entity)
                         .expect("Current character missing group");
@@ -86,3 +111,24 @@ pub fn handle_lifecycle_event(
         }
     }
 }
+
+/// Sorts the turn order again whenever the attributes of a unit changed, as
+/// its Speed might have
+pub fn order_battle_queue(
+    res_queue: Option<ResMut<BattleQueue>>,
+    changed_query: Query<(), (Changed<Attributes>, With<LifeState>)>,
+    attributes_query: Query<&Attributes>,
+) {
+    let Some(mut res_queue) = res_queue else {
+        return;
+    };
+    if changed_query.is_empty() {
+        return;
+    }
+
+    res_queue.reorder(|unit| {
+        attributes_query
+            .get(unit)
+            .map_or(0, |attributes| attributes.value(AttributeType::Speed))
+    });
+}
@@ -1,45 +1,870 @@
-use bevy::prelude::*;
+use std::collections::{HashSet, VecDeque};
 
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+use fluent::fluent_args;
+use serde::Serialize;
+
+use crate::{
+    abilities::damage::DamageType,
+    character::{AttributeType, CharacterName, Group, ModifierKind},
+    input::{Actions, InputAction},
+    locale::Locale,
+    theme::Theme,
+    utils::hex::Hex,
+    GameState,
+};
+
+use super::{
+    battle_field::BattleField, lifecycle::LifeState, ui::HighlightsUnits, vision::FogOfWar,
+};
+
+/// Entries kept in memory per battle, older ones are only in the export
+const MAX_KEPT_ENTRIES: usize = 1000;
+/// Entries shown in the log panel, older ones are despawned
+const MAX_SHOWN_ENTRIES: usize = 200;
+const SCROLL_LINE_HEIGHT: f32 = 20.0;
+
+/// Area of the log panel which clips the entries
+#[derive(Component)]
+pub struct BattleLogPanel;
+
+/// Moves inside `BattleLogPanel` when scrolling
+#[derive(Component)]
+pub struct BattleLogList {
+    /// Offset from the top of the panel, always <= 0
+    position: f32,
+    /// Follow new entries until the player scrolls up
+    stick_to_bottom: bool,
+    /// Id of the newest entry with a line in the panel
+    last_shown: Option<u64>,
+}
+
+impl Default for BattleLogList {
+    fn default() -> Self {
+        Self {
+            position: 0.0,
+            stick_to_bottom: true,
+            last_shown: None,
+        }
+    }
+}
+
+/// A single line of the log panel
 #[derive(Component)]
-pub struct BattleLogText;
+pub struct BattleLogEntryText {
+    is_damage: bool,
+    units: Vec<u32>,
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum BattleLogFilterButton {
+    OnlyDamage,
+    OnlyMyUnits,
+}
+
+#[derive(Resource, Default)]
+pub struct BattleLogFilter {
+    pub only_damage: bool,
+    pub only_my_units: bool,
+}
+
+impl BattleLogFilter {
+    fn is_active(&self, button: BattleLogFilterButton) -> bool {
+        match button {
+            BattleLogFilterButton::OnlyDamage => self.only_damage,
+            BattleLogFilterButton::OnlyMyUnits => self.only_my_units,
+        }
+    }
+
+    fn shows(&self, entry: &BattleLogEntryText, my_units: &HashSet<u32>) -> bool {
+        (!self.only_damage || entry.is_damage)
+            && (!self.only_my_units || entry.units.iter().any(|id| my_units.contains(id)))
+    }
+}
+
+/// A unit as referred to by the battle log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogUnit {
+    pub id: u32,
+    pub name: String,
+}
+
+impl LogUnit {
+    pub fn new(entity: Entity, name: &CharacterName) -> Self {
+        Self {
+            id: entity.index(),
+            name: name.0.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum BattleLogEvent {
+    TurnStarted {
+        unit: LogUnit,
+    },
+    Moved {
+        unit: LogUnit,
+        from: (i32, i32),
+        to: (i32, i32),
+    },
+    Damaged {
+        actor: LogUnit,
+        target: LogUnit,
+        ability: String,
+        damage_type: DamageType,
+        damage: i32,
+        critical: bool,
+        shield_absorbed: i32,
+        overkill: i32,
+        hp_left: i32,
+        /// Every stage of the damage calculation
+        stages: Vec<String>,
+    },
+    Missed {
+        actor: LogUnit,
+        target: LogUnit,
+        ability: String,
+        stages: Vec<String>,
+    },
+    StatusApplied {
+        actor: LogUnit,
+        target: LogUnit,
+        ability: String,
+        attribute: AttributeType,
+        change: ModifierKind,
+        duration: u32,
+        before: i32,
+        after: i32,
+        floored: bool,
+    },
+    StatusExpired {
+        unit: LogUnit,
+        attribute: AttributeType,
+        change: ModifierKind,
+        source: String,
+    },
+    Waited {
+        unit: LogUnit,
+    },
+    /// The player took back an ability used this turn
+    Undone {
+        unit: LogUnit,
+        ability: String,
+    },
+    Died {
+        unit: LogUnit,
+    },
+    /// Messages that aren't part of the battle itself
+    Info {
+        message: String,
+    },
+}
+
+impl BattleLogEvent {
+    /// Text shown in the battle log panel, `None` for entries which are only
+    /// kept for the structured log. Units the player couldn't see go unnamed,
+    /// and where they moved isn't shown at all.
+    pub fn text(&self, damage_breakdown: bool, unseen: &[u32], locale: &Locale) -> Option<String> {
+        let stage_lines = |stages: &[String]| {
+            if damage_breakdown {
+                stages
+                    .iter()
+                    .map(|stage| format!("\n  - {stage}"))
+                    .collect::<String>()
+            } else {
+                String::new()
+            }
+        };
+        let name = |unit: &LogUnit| {
+            if unseen.contains(&unit.id) {
+                locale.get("log-unseen-unit")
+            } else {
+                locale.character_name(&unit.name)
+            }
+        };
+        let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+
+        Some(match self {
+            Self::TurnStarted { .. } => return None,
+            Self::Moved { unit, .. } if unseen.contains(&unit.id) => return None,
+            Self::Moved { unit, from, to } => locale.format(
+                "log-moved",
+                &fluent_args![
+                    "unit" => name(unit),
+                    "from-x" => from.0,
+                    "from-y" => from.1,
+                    "to-x" => to.0,
+                    "to-y" => to.1
+                ],
+            ),
+            Self::Damaged {
+                actor,
+                target,
+                ability,
+                damage_type,
+                damage,
+                critical,
+                shield_absorbed,
+                hp_left,
+                stages,
+                ..
+            } => {
+                locale.format(
+                    "log-damaged",
+                    &fluent_args![
+                        "actor" => name(actor),
+                        "target" => name(target),
+                        "ability" => locale.ability_name(ability),
+                        "damage" => *damage,
+                        "damage-type" => damage_type.name(locale),
+                        "critical" => yes_no(*critical),
+                        "shield" => *shield_absorbed,
+                        "hp" => *hp_left
+                    ],
+                ) + &stage_lines(stages)
+            }
+            Self::Missed {
+                actor,
+                target,
+                ability,
+                stages,
+            } => {
+                locale.format(
+                    "log-missed",
+                    &fluent_args![
+                        "actor" => name(actor),
+                        "target" => name(target),
+                        "ability" => locale.ability_name(ability)
+                    ],
+                ) + &stage_lines(stages)
+            }
+            Self::StatusApplied {
+                actor,
+                target,
+                ability,
+                attribute,
+                change,
+                duration,
+                before,
+                after,
+                floored,
+            } => locale.format(
+                "log-status-applied",
+                &fluent_args![
+                    "actor" => name(actor),
+                    "target" => name(target),
+                    "ability" => locale.ability_name(ability),
+                    "attribute" => attribute.name(locale),
+                    "change" => change.to_string(),
+                    "duration" => *duration,
+                    "before" => *before,
+                    "after" => *after,
+                    "floored" => yes_no(*floored)
+                ],
+            ),
+            Self::StatusExpired {
+                unit,
+                attribute,
+                change,
+                source,
+            } => locale.format(
+                "log-status-expired",
+                &fluent_args![
+                    "unit" => name(unit),
+                    "attribute" => attribute.name(locale),
+                    "change" => change.to_string(),
+                    "source" => source.as_str()
+                ],
+            ),
+            Self::Waited { unit } => {
+                locale.format("log-waited", &fluent_args!["unit" => name(unit)])
+            }
+            Self::Undone { unit, ability } => locale.format(
+                "log-undone",
+                &fluent_args![
+                    "unit" => name(unit),
+                    "ability" => locale.ability_name(ability)
+                ],
+            ),
+            Self::Died { unit } => locale.format("log-died", &fluent_args!["unit" => name(unit)]),
+            Self::Info { message } => message.clone(),
+        })
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Damaged { .. } => Color::rgb(1.0, 0.55, 0.55),
+            Self::Missed { .. } => Color::GRAY,
+            Self::StatusApplied { .. } => Color::rgb(0.55, 0.75, 1.0),
+            Self::StatusExpired { .. } => Color::rgb(0.5, 0.6, 0.75),
+            Self::Died { .. } => Color::ORANGE_RED,
+            Self::Info { .. } => Color::YELLOW,
+            Self::TurnStarted { .. }
+            | Self::Moved { .. }
+            | Self::Waited { .. }
+            | Self::Undone { .. } => Color::rgb(0.85, 0.85, 0.85),
+        }
+    }
+
+    pub fn is_damage(&self) -> bool {
+        matches!(self, Self::Damaged { .. } | Self::Missed { .. })
+    }
+
+    /// Ids of all units this entry refers to
+    pub fn mentions(&self) -> Vec<u32> {
+        match self {
+            Self::TurnStarted { unit }
+            | Self::Moved { unit, .. }
+            | Self::StatusExpired { unit, .. }
+            | Self::Waited { unit }
+            | Self::Undone { unit, .. }
+            | Self::Died { unit } => vec![unit.id],
+            Self::Damaged { actor, target, .. }
+            | Self::Missed { actor, target, .. }
+            | Self::StatusApplied { actor, target, .. } => {
+                if actor.id == target.id {
+                    vec![actor.id]
+                } else {
+                    vec![actor.id, target.id]
+                }
+            }
+            Self::Info { .. } => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BattleLogEntry {
+    /// Increases with every entry of the battle
+    #[serde(skip)]
+    pub id: u64,
+    /// Units the player couldn't see when the entry was written
+    #[serde(skip)]
+    pub unseen: Vec<u32>,
+    pub round: i32,
+    pub turn: u32,
+    #[serde(flatten)]
+    pub event: BattleLogEvent,
+}
 
 #[derive(Resource)]
 pub struct BattleLog {
-    messages: Vec<String>,
+    round: i32,
+    turn: u32,
+    next_id: u64,
+    entries: VecDeque<BattleLogEntry>,
 }
 
-pub struct BattleLogEvent {
-    pub message: String,
+impl BattleLog {
+    pub fn new(round: i32) -> Self {
+        Self {
+            round,
+            turn: 0,
+            next_id: 0,
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, event: BattleLogEvent) -> &mut BattleLogEntry {
+        if let BattleLogEvent::TurnStarted { .. } = event {
+            self.turn += 1;
+        }
+
+        if self.entries.len() == MAX_KEPT_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(BattleLogEntry {
+            id: self.next_id,
+            unseen: vec![],
+            round: self.round,
+            turn: self.turn,
+            event,
+        });
+        self.next_id += 1;
+        self.entries.back_mut().unwrap()
+    }
+
+    /// Entries pushed after the one with the id, or all of the kept ones
+    pub fn entries_after(&self, id: Option<u64>) -> impl Iterator<Item = &BattleLogEntry> {
+        let start = self.entries.partition_point(|entry| Some(entry.id) <= id);
+        self.entries.range(start..)
+    }
 }
 
-pub fn update_battle_log(
-    asset_server: Res<AssetServer>,
+impl BattleLogEntry {
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("Couldn't serialize battle log entry") + "\n"
+    }
+}
+
+/// Where the entries are exported as JSON lines while they're recorded, so
+/// the export has the whole battle while the log keeps only the latest ones
+#[derive(Resource, Default)]
+pub struct BattleLogExport {
+    #[cfg(not(target_arch = "wasm32"))]
+    file: Option<(std::path::PathBuf, std::io::BufWriter<std::fs::File>)>,
+}
+
+impl BattleLogExport {
+    /// Creates the file next to the settings, named after the run so that
+    /// runs don't overwrite each other
+    #[cfg(not(target_arch = "wasm32"))]
+    fn create(seed: u64, round: i32) -> Self {
+        let file_name = format!("battle_logs/run-{seed:016x}-round-{round}.jsonl");
+        let Some(path) = crate::settings::config_path(&file_name) else {
+            warn!("Couldn't find a config directory to export the battle log to");
+            return Self::default();
+        };
+
+        match path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::File::create(&path))
+        {
+            Ok(file) => Self {
+                file: Some((path, std::io::BufWriter::new(file))),
+            },
+            Err(err) => {
+                warn!("Couldn't export battle log to {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn create(_seed: u64, _round: i32) -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(&mut self, entry: &BattleLogEntry) {
+        use std::io::Write;
+
+        let Some((path, file)) = &mut self.file else {
+            return;
+        };
+        if let Err(err) = file.write_all(entry.to_json_line().as_bytes()) {
+            warn!("Couldn't export battle log to {}: {err}", path.display());
+            self.file = None;
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write(&mut self, entry: &BattleLogEntry) {
+        info!("{}", entry.to_json_line().trim_end());
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct BattleLogSettings {
+    /// Log every stage of the damage calculation
+    pub damage_breakdown: bool,
+}
+
+pub fn toggle_damage_breakdown(
+    actions: Actions,
+    locale: Res<Locale>,
+    mut settings: ResMut<BattleLogSettings>,
+    mut ev_battle_log: EventWriter<BattleLogEvent>,
+) {
+    if actions.just_pressed(InputAction::ToggleDamageBreakdown) {
+        settings.damage_breakdown = !settings.damage_breakdown;
+        ev_battle_log.send(BattleLogEvent::Info {
+            message: locale.get(if settings.damage_breakdown {
+                "log-damage-breakdown-on"
+            } else {
+                "log-damage-breakdown-off"
+            }),
+        });
+    }
+}
+
+pub fn record_battle_log(
     mut battle_log: ResMut<BattleLog>,
+    mut export: ResMut<BattleLogExport>,
+    battle_field: Option<Res<BattleField>>,
+    fog: Res<FogOfWar>,
+    unit_query: Query<(Entity, &Group, &Parent), With<CharacterName>>,
     mut ev_battle_log: EventReader<BattleLogEvent>,
-    mut query: Query<&mut Text, With<BattleLogText>>,
 ) {
     for log_event in ev_battle_log.iter() {
-        battle_log.messages.push(log_event.message.clone());
-
-        for mut text in &mut query {
-            text.sections.push(TextSection::new(
-                format!("\n{}", log_event.message),
-                TextStyle {
-                    font: asset_server.load("fonts/FiraSans-Medium.ttf"),
-                    font_size: 10.0,
-                    color: Color::WHITE,
+        // enemies count as seen if the player sees where they are, or both
+        // ends of their move
+        let unseen = log_event
+            .mentions()
+            .into_iter()
+            .filter(|id| {
+                let Some((_, group, parent)) =
+                    unit_query.iter().find(|(entity, ..)| entity.index() == *id)
+                else {
+                    return false;
+                };
+                let hexes = match log_event {
+                    BattleLogEvent::Moved { from, to, .. } => {
+                        vec![Hex::from_oddr((*from).into()), Hex::from_oddr((*to).into())]
+                    }
+                    _ => battle_field
+                        .as_ref()
+                        .and_then(|battle_field| battle_field.hex(parent.get()))
+                        .into_iter()
+                        .collect(),
+                };
+                *group == Group::Enemy && !hexes.into_iter().all(|hex| fog.sees(Group::Player, hex))
+            })
+            .collect();
+
+        let entry = battle_log.push(log_event.clone());
+        entry.unseen = unseen;
+        export.write(entry);
+    }
+}
+
+/// Adds lines for the new entries of the log to the panel, and drops the
+/// oldest lines once there are too many
+pub fn update_battle_log(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<BattleLogSettings>,
+    locale: Res<Locale>,
+    battle_log: Res<BattleLog>,
+    mut list_query: Query<(Entity, &mut BattleLogList, Option<&Children>)>,
+) {
+    if !battle_log.is_changed() {
+        return;
+    }
+    let Ok((list, mut list_state, children)) = list_query.get_single_mut() else {
+        return;
+    };
+
+    let mut shown = children.into_iter().flatten().copied().collect::<Vec<_>>();
+
+    for entry in battle_log.entries_after(list_state.last_shown) {
+        list_state.last_shown = Some(entry.id);
+        let Some(message) = entry
+            .event
+            .text(settings.damage_breakdown, &entry.unseen, &locale)
+        else {
+            continue;
+        };
+
+        let entry_text = commands
+            .spawn((
+                TextBundle::from_section(
+                    message,
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Medium.ttf"),
+                        font_size: 12.0,
+                        color: entry.event.color(),
+                    },
+                )
+                .with_style(Style {
+                    max_size: Size::width(Val::Px(super::ui::RIGHT_PANE_WIDTH - 20.0)),
+                    margin: UiRect::bottom(Val::Px(2.0)),
+                    ..default()
+                }),
+                Interaction::default(),
+                BattleLogEntryText {
+                    is_damage: entry.event.is_damage(),
+                    units: entry.event.mentions(),
                 },
+                HighlightsUnits(entry.event.mentions()),
             ))
+            .id();
+        commands.entity(list).add_child(entry_text);
+        shown.push(entry_text);
+    }
+
+    if shown.len() > MAX_SHOWN_ENTRIES {
+        for &oldest in &shown[..shown.len() - MAX_SHOWN_ENTRIES] {
+            commands.entity(oldest).despawn_recursive();
         }
     }
 }
 
-pub fn setup_battle_log(mut commands: Commands) {
-    commands.insert_resource(BattleLog {
-        messages: Vec::new(),
-    });
+pub fn scroll_battle_log(
+    mut ev_mouse_wheel: EventReader<MouseWheel>,
+    panel_query: Query<(&Node, &Interaction), With<BattleLogPanel>>,
+    mut list_query: Query<(&mut BattleLogList, &mut Style, &Node)>,
+) {
+    let Ok((panel, interaction)) = panel_query.get_single() else {
+        return;
+    };
+    let Ok((mut list, mut style, list_node)) = list_query.get_single_mut() else {
+        return;
+    };
+
+    let mut delta = 0.0;
+    if *interaction != Interaction::None {
+        for ev in ev_mouse_wheel.iter() {
+            delta += match ev.unit {
+                MouseScrollUnit::Line => ev.y * SCROLL_LINE_HEIGHT,
+                MouseScrollUnit::Pixel => ev.y,
+            };
+        }
+    } else {
+        ev_mouse_wheel.clear();
+    }
+
+    let max_scroll = (list_node.size().y - panel.size().y).max(0.0);
+    if delta != 0.0 {
+        list.position = (list.position + delta).clamp(-max_scroll, 0.0);
+        list.stick_to_bottom = list.position <= -max_scroll;
+    } else if list.stick_to_bottom {
+        list.position = -max_scroll;
+    } else {
+        list.position = list.position.clamp(-max_scroll, 0.0);
+    }
+
+    if style.position.top != Val::Px(list.position) {
+        style.position.top = Val::Px(list.position);
+    }
 }
 
+pub fn battle_log_filter_button_interaction(
+    theme: Res<Theme>,
+    mut filter: ResMut<BattleLogFilter>,
+    mut button_query: Query<
+        (&Interaction, &BattleLogFilterButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, button, mut color) in &mut button_query {
+        if *interaction == Interaction::Clicked {
+            match button {
+                BattleLogFilterButton::OnlyDamage => filter.only_damage = !filter.only_damage,
+                BattleLogFilterButton::OnlyMyUnits => filter.only_my_units = !filter.only_my_units,
+            }
+        }
+
+        *color = match (*interaction, filter.is_active(*button)) {
+            (_, true) => theme.active_button.into(),
+            (Interaction::Hovered, false) => theme.hovered_button.into(),
+            _ => theme.normal_button.into(),
+        };
+    }
+}
+
+pub fn apply_battle_log_filter(
+    filter: Res<BattleLogFilter>,
+    unit_query: Query<(Entity, &Group), With<LifeState>>,
+    mut entry_query: Query<(Ref<BattleLogEntryText>, &mut Style)>,
+) {
+    let my_units = unit_query
+        .iter()
+        .filter(|(_, group)| **group == Group::Player)
+        .map(|(entity, _)| entity.index())
+        .collect::<HashSet<_>>();
+
+    for (entry, mut style) in &mut entry_query {
+        if !filter.is_changed() && !entry.is_added() {
+            continue;
+        }
+
+        style.display = if filter.shows(&entry, &my_units) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+pub fn setup_battle_log(mut commands: Commands, game_state: Res<GameState>) {
+    commands.insert_resource(BattleLog::new(game_state.round));
+    commands.insert_resource(BattleLogExport::create(game_state.seed, game_state.round));
+}
+
+/// Writes out what's left of the exported log once the battle is over
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export_battle_log(mut export: ResMut<BattleLogExport>) {
+    use std::io::Write;
+
+    let Some((path, mut file)) = export.file.take() else {
+        return;
+    };
+    match file.flush() {
+        Ok(()) => info!("Battle log exported to {}", path.display()),
+        Err(err) => warn!("Couldn't export battle log to {}: {err}", path.display()),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn export_battle_log() {}
+
 pub fn cleanup_battle_log(mut commands: Commands) {
     commands.remove_resource::<BattleLog>();
+    commands.remove_resource::<BattleLogExport>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::Language;
+
+    fn unit(id: u32, name: &str) -> LogUnit {
+        LogUnit {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn entries_are_stamped_with_turns() {
+        let mut log = BattleLog::new(3);
+        log.push(BattleLogEvent::TurnStarted {
+            unit: unit(0, "player"),
+        });
+        log.push(BattleLogEvent::Waited {
+            unit: unit(0, "player"),
+        });
+        log.push(BattleLogEvent::TurnStarted {
+            unit: unit(1, "mushroom"),
+        });
+        log.push(BattleLogEvent::Died {
+            unit: unit(1, "mushroom"),
+        });
+
+        let turns = log
+            .entries_after(None)
+            .map(|entry| (entry.round, entry.turn))
+            .collect::<Vec<_>>();
+        assert_eq!(turns, [(3, 1), (3, 1), (3, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn exports_one_json_object_per_line() {
+        let mut log = BattleLog::new(1);
+        log.push(BattleLogEvent::Moved {
+            unit: unit(4, "player"),
+            from: (0, 3),
+            to: (2, 3),
+        });
+        log.push(BattleLogEvent::Missed {
+            actor: unit(4, "player"),
+            target: unit(5, "mushroom"),
+            ability: "hit".to_string(),
+            stages: vec![],
+        });
+
+        let values = log
+            .entries_after(None)
+            .map(|entry| serde_json::from_str::<serde_json::Value>(&entry.to_json_line()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0]["type"], "Moved");
+        assert_eq!(values[0]["round"], 1);
+        assert_eq!(values[0]["to"], serde_json::json!([2, 3]));
+        assert_eq!(values[1]["target"]["name"], "mushroom");
+    }
+
+    #[test]
+    fn breakdown_only_shown_on_request() {
+        let event = BattleLogEvent::Missed {
+            actor: unit(0, "player"),
+            target: unit(1, "mushroom"),
+            ability: "hit".to_string(),
+            stages: vec!["missed (90% to hit)".to_string()],
+        };
+
+        let locale = Locale::new(Language::English);
+
+        assert_eq!(
+            event.text(false, &[], &locale).unwrap(),
+            "player used hit on mushroom, but missed"
+        );
+        assert!(event
+            .text(true, &[], &locale)
+            .unwrap()
+            .contains("\n  - missed (90% to hit)"));
+        assert!(BattleLogEvent::TurnStarted {
+            unit: unit(0, "player")
+        }
+        .text(false, &[], &locale)
+        .is_none());
+    }
+
+    #[test]
+    fn unseen_units_are_not_given_away() {
+        let locale = Locale::new(Language::English);
+        let moved = BattleLogEvent::Moved {
+            unit: unit(1, "mushroom"),
+            from: (5, 3),
+            to: (4, 3),
+        };
+        let damaged = BattleLogEvent::Missed {
+            actor: unit(1, "mushroom"),
+            target: unit(0, "player"),
+            ability: "hit".to_string(),
+            stages: vec![],
+        };
+
+        assert!(moved.text(false, &[1], &locale).is_none());
+        assert!(moved.text(false, &[0], &locale).is_some());
+        let text = damaged.text(false, &[1], &locale).unwrap();
+        assert!(!text.contains("mushroom"));
+        assert!(text.contains("player"));
+    }
+
+    #[test]
+    fn keeps_a_limited_number_of_entries() {
+        let mut log = BattleLog::new(1);
+        for i in 0..MAX_KEPT_ENTRIES + 10 {
+            log.push(BattleLogEvent::Info {
+                message: i.to_string(),
+            });
+        }
+
+        assert_eq!(log.entries_after(None).count(), MAX_KEPT_ENTRIES);
+        assert!(matches!(
+            &log.entries_after(None).next().unwrap().event,
+            BattleLogEvent::Info { message } if message == "10"
+        ));
+    }
+
+    #[test]
+    fn new_entries_follow_the_last_shown() {
+        let mut log = BattleLog::new(1);
+        for unit_id in 0..3 {
+            log.push(BattleLogEvent::Waited {
+                unit: unit(unit_id, "player"),
+            });
+        }
+
+        assert_eq!(log.entries_after(None).count(), 3);
+        let ids = log.entries_after(Some(0)).map(|entry| entry.id);
+        assert_eq!(ids.collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(log.entries_after(Some(2)).count(), 0);
+    }
+
+    #[test]
+    fn filters_by_damage_and_units() {
+        let damage = BattleLogEntryText {
+            is_damage: true,
+            units: vec![1, 2],
+        };
+        let expired = BattleLogEntryText {
+            is_damage: false,
+            units: vec![2],
+        };
+        let my_units = HashSet::from([1]);
+
+        let only_damage = BattleLogFilter {
+            only_damage: true,
+            only_my_units: false,
+        };
+        assert!(only_damage.shows(&damage, &my_units));
+        assert!(!only_damage.shows(&expired, &my_units));
+
+        let only_mine = BattleLogFilter {
+            only_damage: false,
+            only_my_units: true,
+        };
+        assert!(only_mine.shows(&damage, &my_units));
+        assert!(!only_mine.shows(&expired, &my_units));
+    }
 }
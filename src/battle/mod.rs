@@ -1,22 +1,35 @@
+pub mod animation;
 pub mod battle_field;
+pub mod camera;
 pub mod enemies;
+pub mod generation;
+pub mod highlight;
 pub mod init;
+pub mod inspect;
 pub mod interactions;
 pub mod lifecycle;
 pub mod log;
 pub mod resolution;
+pub mod stat_changes;
+pub mod stats;
+pub mod timeline;
+pub mod tooltip;
 pub mod ui;
+pub mod undo;
+pub mod vision;
 
-use std::collections::VecDeque;
+use std::{cmp::Reverse, collections::VecDeque};
 
-use bevy::prelude::*;
+use bevy::{prelude::*, render::view::VisibilitySystems, transform::TransformSystem};
 use bevy_mod_picking::{InteractablePickingPlugin, PickingPlugin};
+use rand::rngs::StdRng;
 
 use crate::{utils::bar::BarPlugin, AppState};
 
 use self::{
-    battle_field::*, enemies::*, init::*, interactions::*, lifecycle::*, log::*, resolution::*,
-    ui::*,
+    animation::*, battle_field::*, camera::*, enemies::*, highlight::*, init::*, inspect::*,
+    interactions::*, lifecycle::*, log::*, resolution::*, stat_changes::*, stats::*, timeline::*,
+    tooltip::*, ui::*, undo::*, vision::*,
 };
 
 pub struct BattlePlugin;
@@ -27,24 +40,60 @@ impl Plugin for BattlePlugin {
             .add_state::<BattleState>()
             .add_event::<BattleLogEvent>()
             .add_event::<BattleLifecycleEvent>()
+            .add_event::<TargetInput>()
+            .init_resource::<TargetCursor>()
+            .init_resource::<HoveredTarget>()
+            .init_resource::<InspectedUnit>()
+            .init_resource::<BattleLogSettings>()
+            .init_resource::<BattleLogFilter>()
+            .init_resource::<AnimationSpeed>()
+            .init_resource::<BattleAnimations>()
+            .init_resource::<DeferredLifecycle>()
+            .init_resource::<CameraControl>()
+            .init_resource::<FogOfWar>()
             .add_plugin(PickingPlugin)
             .add_plugin(InteractablePickingPlugin)
             .add_plugin(BarPlugin)
             .add_systems(
                 (
                     setup_battle_log,
+                    setup_battle_stats,
                     setup_battle_ui,
                     initialize_enemies,
+                    choose_battle_field_layout,
                     setup_battle_field,
+                    setup_fog_of_war,
                 )
                     .chain()
                     .in_schedule(OnEnter(AppState::Battle)),
             )
             .add_system(setup_battle.in_schedule(OnEnter(BattleInitState::AfterBattleField)))
-            .add_systems((cleanup_battle, cleanup_battle_log).in_schedule(OnExit(AppState::Battle)))
-            .add_system(choose_action.in_set(OnUpdate(BattleState::AbilityChoosingPlayer)))
             .add_systems(
-                (choose_target, cancel_action).in_set(OnUpdate(BattleState::AbilityTargeting)),
+                (
+                    cleanup_battle,
+                    export_battle_log,
+                    cleanup_battle_log,
+                    cleanup_battle_stats,
+                    cleanup_pending_turn,
+                    cleanup_inspected_unit,
+                    cleanup_battle_animations,
+                    cleanup_camera_control,
+                    cleanup_fog_of_war,
+                )
+                    .chain()
+                    .in_schedule(OnExit(AppState::Battle)),
+            )
+            .add_systems(
+                (choose_action, handle_turn_controls)
+                    .in_set(OnUpdate(BattleState::AbilityChoosingPlayer)),
+            )
+            .add_systems(
+                (
+                    move_target_cursor.before(choose_target),
+                    choose_target,
+                    cancel_action,
+                )
+                    .in_set(OnUpdate(BattleState::AbilityTargeting)),
             )
             .add_system(init_targeting.in_schedule(OnEnter(BattleState::AbilityTargeting)))
             .add_system(cleanup_targeting.in_schedule(OnExit(BattleState::AbilityTargeting)))
@@ -57,11 +106,65 @@ impl Plugin for BattlePlugin {
                     resize_meshes_for_sprites,
                     resize_battle_camera_viewport,
                     update_battle_log,
+                    scroll_battle_log,
+                    battle_log_filter_button_interaction,
+                    apply_battle_log_filter,
+                    highlight_hovered_units,
                     update_top_text,
                     handle_lifecycle_event,
+                    toggle_damage_breakdown,
+                    setup_stat_change_text,
+                    update_stat_change_text,
+                    update_tooltip,
+                    select_inspected_unit,
+                    update_inspection_panel.after(select_inspected_unit),
                 )
                     .in_set(OnUpdate(AppState::Battle)),
             )
+            .add_systems(
+                (
+                    update_turn_timeline,
+                    order_battle_queue.before(handle_lifecycle_event),
+                    cycle_animation_speed,
+                    draw_tile_highlights,
+                    focus_active_unit.before(control_battle_camera),
+                    control_battle_camera,
+                    update_vision,
+                )
+                    .in_set(OnUpdate(AppState::Battle)),
+            )
+            // moved units have to be reparented before they're offset to their old tile
+            .add_system(
+                play_battle_animations
+                    .run_if(in_state(AppState::Battle))
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(TransformSystem::TransformPropagate),
+            )
+            // after everything else that shows or hides units this frame
+            .add_system(
+                hide_concealed_units
+                    .run_if(in_state(AppState::Battle))
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(VisibilitySystems::VisibilityPropagate),
+            )
+            .add_system(
+                finish_ability_resolution
+                    .before(handle_lifecycle_event)
+                    .in_set(OnUpdate(BattleState::AbilityResolution)),
+            )
+            // the log has to include the final deaths before it's exported
+            .add_system(
+                record_battle_log
+                    .after(handle_lifecycle_event)
+                    .before(update_battle_log)
+                    .in_set(OnUpdate(AppState::Battle)),
+            )
+            // stats have to include the final deaths before the resolution screen shows them
+            .add_system(
+                record_battle_stats
+                    .after(handle_lifecycle_event)
+                    .in_set(OnUpdate(AppState::Battle)),
+            )
             .add_system(setup_battle_resolution.in_schedule(OnEnter(BattleState::BattleEnd)))
             .add_system(
                 battle_resolution_button_interaction.in_set(OnUpdate(BattleState::BattleEnd)),
@@ -69,14 +172,60 @@ impl Plugin for BattlePlugin {
     }
 }
 
+/// Source of all randomness during a battle, seeded from the run seed
+/// and the round so that battles can be replayed
+#[derive(Resource)]
+pub struct BattleRng(pub StdRng);
+
+/// Turn order, every round the units act from the fastest to the slowest
 #[derive(Resource, Default)]
 pub struct BattleQueue {
-    pub queue: VecDeque<Entity>,
+    /// Order of the units in every round
+    order: Vec<Entity>,
+    /// Units yet to act this round, the current one first
+    queue: VecDeque<Entity>,
 }
 
 impl BattleQueue {
+    /// Units with the same Speed keep the order they're given in
+    pub fn new(units: impl IntoIterator<Item = (Entity, i32)>) -> Self {
+        let mut units = units.into_iter().collect::<Vec<_>>();
+        units.sort_by_key(|&(_, speed)| Reverse(speed));
+        let order = units.into_iter().map(|(unit, _)| unit).collect::<Vec<_>>();
+
+        Self {
+            queue: order.iter().copied().collect(),
+            order,
+        }
+    }
+
     pub fn get_current(&self) -> Entity {
-        *self.queue.get(0).expect("Error: turn queue is empty!")
+        *self.queue.front().expect("Error: turn queue is empty!")
+    }
+
+    /// Hands the turn to the next unit, starting a new round after the last
+    pub fn next_turn(&mut self) {
+        self.queue.pop_front();
+        if self.queue.is_empty() {
+            self.queue = self.order.iter().copied().collect();
+        }
+    }
+
+    /// Sorts the units again after their Speed changed. The current unit
+    /// keeps its turn and who already acted this round doesn't act again.
+    pub fn reorder(&mut self, speed: impl Fn(Entity) -> i32) {
+        self.order.sort_by_key(|unit| Reverse(speed(*unit)));
+        if let Some(current) = self.queue.pop_front() {
+            self.queue
+                .make_contiguous()
+                .sort_by_key(|unit| Reverse(speed(*unit)));
+            self.queue.push_front(current);
+        }
+    }
+
+    /// Turns from the current one on, going on through the rounds after
+    pub fn upcoming(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.queue.iter().chain(self.order.iter().cycle()).copied()
     }
 }
 
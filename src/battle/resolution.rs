@@ -1,8 +1,11 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
+use fluent::fluent_args;
 
-use crate::{character::Group, AppState, GameState, HOVERED_BUTTON, NORMAL_BUTTON};
+use crate::{character::Group, locale::Locale, run::RunSave, theme::Theme, AppState, GameState};
 
-use super::Battle;
+use super::{lifecycle::LifeState, stats::BattleStats, Battle};
 
 #[derive(Resource)]
 pub struct BattleResolution {
@@ -16,6 +19,7 @@ pub enum BattleResolutionButton {
 }
 
 pub fn battle_resolution_button_interaction(
+    theme: Res<Theme>,
     mut game_state: ResMut<GameState>,
     mut interaction_query: Query<
         (&Interaction, &BattleResolutionButton, &mut BackgroundColor),
@@ -26,10 +30,10 @@ pub fn battle_resolution_button_interaction(
     for (interaction, button, mut color) in &mut interaction_query {
         match *interaction {
             Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
+                *color = theme.hovered_button.into();
             }
             Interaction::None => {
-                *color = NORMAL_BUTTON.into();
+                *color = theme.normal_button.into();
             }
             Interaction::Clicked => match button {
                 BattleResolutionButton::MainMenu => {
@@ -47,14 +51,48 @@ pub fn battle_resolution_button_interaction(
 
 pub fn setup_battle_resolution(
     mut commands: Commands,
+    mut game_state: ResMut<GameState>,
     res_resolution: Res<BattleResolution>,
+    stats: Res<BattleStats>,
+    group_query: Query<(Entity, &Group), With<LifeState>>,
     asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    theme: Res<Theme>,
 ) {
     let text_style = TextStyle {
         font: asset_server.load("fonts/FiraSans-Medium.ttf"),
         font_size: 50.0,
         color: Color::WHITE,
     };
+    let stats_style = TextStyle {
+        font_size: 18.0,
+        ..text_style.clone()
+    };
+
+    let player_units = group_query
+        .iter()
+        .filter(|(_, group)| **group == Group::Player)
+        .map(|(entity, _)| entity.index())
+        .collect::<HashSet<_>>();
+    game_state.run_stats.add_battle(&stats, &player_units);
+    // a lost run can't be continued
+    if res_resolution.winner == Group::Enemy {
+        RunSave::delete();
+    }
+
+    let (title, button_text, button) = match res_resolution.winner {
+        Group::Player => (
+            "resolution-victory",
+            "resolution-continue",
+            BattleResolutionButton::Continue,
+        ),
+        Group::Enemy => (
+            "resolution-defeat",
+            "resolution-main-menu",
+            BattleResolutionButton::MainMenu,
+        ),
+    };
+
     commands
         .spawn((
             NodeBundle {
@@ -77,63 +115,58 @@ pub fn setup_battle_resolution(
                         flex_direction: FlexDirection::Column,
                         align_items: AlignItems::Center,
                         justify_content: JustifyContent::Center,
+                        padding: UiRect::all(Val::Px(10.0)),
                         ..default()
                     },
                     background_color: Color::rgb(0.15, 0.15, 0.15).into(),
                     z_index: ZIndex::Global(1),
                     ..default()
                 })
-                .with_children(|parent| match res_resolution.winner {
-                    Group::Player => {
-                        parent.spawn(TextBundle::from_section("Victory!", text_style.clone()));
-                        parent
-                            .spawn((
-                                ButtonBundle {
-                                    style: Style {
-                                        size: Size::AUTO,
-                                        // horizontally center child text
-                                        justify_content: JustifyContent::Center,
-                                        // vertically center child text
-                                        align_items: AlignItems::Center,
-                                        ..default()
-                                    },
-                                    background_color: NORMAL_BUTTON.into(),
-                                    ..default()
-                                },
-                                BattleResolutionButton::Continue,
-                            ))
-                            .with_children(|parent| {
-                                parent.spawn(TextBundle::from_section(
-                                    "Continue",
-                                    text_style.clone(),
-                                ));
-                            });
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        locale.get(title),
+                        text_style.clone(),
+                    ));
+
+                    let mut lines =
+                        vec![locale.format("stats-turns", &fluent_args!["count" => stats.turns])];
+                    lines.extend(stats.units().map(|(_, unit)| unit.describe(&locale)));
+                    if res_resolution.winner == Group::Enemy {
+                        lines.push(format!("\n{}", locale.get("resolution-run-total")));
+                        lines.extend(game_state.run_stats.lines(&locale));
                     }
-                    Group::Enemy => {
-                        parent.spawn(TextBundle::from_section("You lost!", text_style.clone()));
-                        parent
-                            .spawn((
-                                ButtonBundle {
-                                    style: Style {
-                                        size: Size::AUTO,
-                                        // horizontally center child text
-                                        justify_content: JustifyContent::Center,
-                                        // vertically center child text
-                                        align_items: AlignItems::Center,
-                                        ..default()
-                                    },
-                                    background_color: NORMAL_BUTTON.into(),
+                    for line in lines {
+                        parent.spawn(
+                            TextBundle::from_section(line, stats_style.clone()).with_style(Style {
+                                margin: UiRect::vertical(Val::Px(2.0)),
+                                ..default()
+                            }),
+                        );
+                    }
+
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    size: Size::AUTO,
+                                    // horizontally center child text
+                                    justify_content: JustifyContent::Center,
+                                    // vertically center child text
+                                    align_items: AlignItems::Center,
+                                    margin: UiRect::top(Val::Px(10.0)),
                                     ..default()
                                 },
-                                BattleResolutionButton::MainMenu,
-                            ))
-                            .with_children(|parent| {
-                                parent.spawn(TextBundle::from_section(
-                                    "Back to main menu",
-                                    text_style.clone(),
-                                ));
-                            });
-                    }
+                                background_color: theme.normal_button.into(),
+                                ..default()
+                            },
+                            button,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                locale.get(button_text),
+                                text_style.clone(),
+                            ));
+                        });
                 });
         });
 }
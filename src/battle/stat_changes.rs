@@ -0,0 +1,116 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use fluent::fluent_args;
+
+use crate::{
+    character::{AttributeType, AttributeValueType, Attributes},
+    locale::Locale,
+    theme::Theme,
+};
+
+use super::{battle_field::BattleField, lifecycle::LifeState, Battle};
+
+/// Text under a unit listing how its value attributes currently differ
+/// from their base values
+#[derive(Component)]
+pub struct StatChangeText {
+    unit: Entity,
+}
+
+fn stat_change_sections(
+    attributes: &Attributes,
+    style: &TextStyle,
+    locale: &Locale,
+    theme: &Theme,
+) -> Vec<TextSection> {
+    AttributeType::ALL
+        .iter()
+        .filter(|typ| typ.definition().value_type == AttributeValueType::Value)
+        .filter_map(|typ| {
+            let attribute = attributes.0.get(typ)?;
+            let change = attribute.get_value() - attribute.get_base();
+            (change != 0).then(|| {
+                TextSection::new(
+                    locale.format(
+                        "attribute-change",
+                        &fluent_args![
+                            "attribute" => typ.name(locale),
+                            "change" => format!("{change:+}")
+                        ],
+                    ) + "\n",
+                    TextStyle {
+                        color: if change > 0 {
+                            theme.increase
+                        } else {
+                            theme.decrease
+                        },
+                        ..style.clone()
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+pub fn setup_stat_change_text(
+    mut commands: Commands,
+    query: Query<Entity, (Added<Attributes>, With<LifeState>)>,
+) {
+    for unit in query.iter() {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::default().with_alignment(TextAlignment::Center),
+                text_anchor: Anchor::TopCenter,
+                ..default()
+            },
+            StatChangeText { unit },
+            RenderLayers::layer(1),
+            Battle,
+        ));
+    }
+}
+
+pub fn update_stat_change_text(
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    theme: Res<Theme>,
+    battle_field: Option<Res<BattleField>>,
+    unit_query: Query<(&GlobalTransform, &Attributes, &LifeState)>,
+    mut text_query: Query<(&StatChangeText, &mut Text, &mut Transform, &mut Visibility)>,
+) {
+    let Some(battle_field) = battle_field else {
+        return;
+    };
+
+    let style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Medium.ttf"),
+        font_size: 14.0,
+        color: Color::WHITE,
+    };
+
+    for (stat_change, mut text, mut transform, mut visibility) in &mut text_query {
+        let Ok((unit_transform, attributes, life_state)) = unit_query.get(stat_change.unit) else {
+            continue;
+        };
+
+        *visibility = match life_state {
+            LifeState::Alive => Visibility::Inherited,
+            LifeState::Dead => Visibility::Hidden,
+        };
+
+        transform.translation =
+            unit_transform.translation() + Vec3::new(0.0, -0.6 * battle_field.tile_size(), 10.0);
+
+        let sections = stat_change_sections(attributes, &style, &locale, &theme);
+        if sections
+            .iter()
+            .map(|section| (&section.value, section.style.color))
+            .ne(text
+                .sections
+                .iter()
+                .map(|section| (&section.value, section.style.color)))
+        {
+            text.sections = sections;
+        }
+    }
+}
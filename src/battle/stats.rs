@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use bevy::prelude::*;
+use fluent::fluent_args;
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+
+use super::log::{BattleLogEvent, LogUnit};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnitStats {
+    pub name: String,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub kills: u32,
+    pub turns: u32,
+    pub abilities_used: u32,
+    pub overkill: i32,
+}
+
+impl UnitStats {
+    pub fn describe(&self, locale: &Locale) -> String {
+        locale.format(
+            "stats-unit",
+            &fluent_args![
+                "unit" => locale.character_name(&self.name),
+                "dealt" => self.damage_dealt,
+                "taken" => self.damage_taken,
+                "kills" => self.kills,
+                "turns" => self.turns,
+                "abilities" => self.abilities_used,
+                "overkill" => self.overkill
+            ],
+        )
+    }
+}
+
+/// Statistics of the current battle, collected from the battle log events
+#[derive(Resource, Debug, Clone, Default)]
+pub struct BattleStats {
+    pub turns: u32,
+    /// Keyed by `LogUnit::id`, so units are listed in spawn order
+    units: BTreeMap<u32, UnitStats>,
+    /// Last unit which hurt or debuffed a unit, credited when it dies
+    last_attacker: HashMap<u32, u32>,
+}
+
+impl BattleStats {
+    fn unit(&mut self, unit: &LogUnit) -> &mut UnitStats {
+        self.units.entry(unit.id).or_insert_with(|| UnitStats {
+            name: unit.name.clone(),
+            ..default()
+        })
+    }
+
+    pub fn units(&self) -> impl Iterator<Item = (u32, &UnitStats)> {
+        self.units.iter().map(|(id, stats)| (*id, stats))
+    }
+
+    pub fn record(&mut self, event: &BattleLogEvent) {
+        match event {
+            BattleLogEvent::TurnStarted { unit } => {
+                self.turns += 1;
+                self.unit(unit).turns += 1;
+            }
+            BattleLogEvent::Moved { unit, .. } => {
+                self.unit(unit).abilities_used += 1;
+            }
+            BattleLogEvent::Damaged {
+                actor,
+                target,
+                damage,
+                overkill,
+                ..
+            } => {
+                let dealt = damage - overkill;
+
+                let actor_stats = self.unit(actor);
+                actor_stats.abilities_used += 1;
+                actor_stats.damage_dealt += dealt;
+                actor_stats.overkill += overkill;
+
+                self.unit(target).damage_taken += dealt;
+                self.last_attacker.insert(target.id, actor.id);
+            }
+            BattleLogEvent::Missed { actor, .. } => {
+                self.unit(actor).abilities_used += 1;
+            }
+            BattleLogEvent::StatusApplied { actor, target, .. } => {
+                self.unit(actor).abilities_used += 1;
+                if actor.id != target.id {
+                    self.last_attacker.insert(target.id, actor.id);
+                }
+            }
+            BattleLogEvent::Died { unit } => {
+                if let Some(killer) = self.last_attacker.get(&unit.id) {
+                    if let Some(killer) = self.units.get_mut(killer) {
+                        killer.kills += 1;
+                    }
+                }
+            }
+            // undoing restores the stats from before the action
+            BattleLogEvent::StatusExpired { .. }
+            | BattleLogEvent::Undone { .. }
+            | BattleLogEvent::Waited { .. }
+            | BattleLogEvent::Info { .. } => {}
+        }
+    }
+}
+
+/// Totals of the player's units over the whole run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunStats {
+    pub battles: u32,
+    pub turns: u32,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub kills: u32,
+    pub abilities_used: u32,
+    pub overkill: i32,
+}
+
+impl RunStats {
+    pub fn add_battle(&mut self, battle: &BattleStats, player_units: &HashSet<u32>) {
+        self.battles += 1;
+        self.turns += battle.turns;
+        for (_, unit) in battle.units().filter(|(id, _)| player_units.contains(id)) {
+            self.damage_dealt += unit.damage_dealt;
+            self.damage_taken += unit.damage_taken;
+            self.kills += unit.kills;
+            self.abilities_used += unit.abilities_used;
+            self.overkill += unit.overkill;
+        }
+    }
+
+    pub fn lines(&self, locale: &Locale) -> Vec<String> {
+        [
+            ("stats-battles", self.battles as i32),
+            ("stats-turns", self.turns as i32),
+            ("stats-damage-dealt", self.damage_dealt),
+            ("stats-damage-taken", self.damage_taken),
+            ("stats-kills", self.kills as i32),
+            ("stats-abilities-used", self.abilities_used as i32),
+            ("stats-overkill", self.overkill),
+        ]
+        .into_iter()
+        .map(|(id, count)| locale.format(id, &fluent_args!["count" => count]))
+        .collect()
+    }
+}
+
+pub fn setup_battle_stats(mut commands: Commands) {
+    commands.init_resource::<BattleStats>();
+}
+
+pub fn record_battle_stats(
+    mut stats: ResMut<BattleStats>,
+    mut ev_battle_log: EventReader<BattleLogEvent>,
+) {
+    for event in ev_battle_log.iter() {
+        stats.record(event);
+    }
+}
+
+pub fn cleanup_battle_stats(mut commands: Commands) {
+    commands.remove_resource::<BattleStats>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abilities::damage::DamageType;
+
+    fn unit(id: u32, name: &str) -> LogUnit {
+        LogUnit {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    fn damaged(actor: &LogUnit, target: &LogUnit, damage: i32, overkill: i32) -> BattleLogEvent {
+        BattleLogEvent::Damaged {
+            actor: actor.clone(),
+            target: target.clone(),
+            ability: "hit".to_string(),
+            damage_type: DamageType::Physical,
+            damage,
+            critical: false,
+            shield_absorbed: 0,
+            overkill,
+            hp_left: 0,
+            stages: vec![],
+        }
+    }
+
+    #[test]
+    fn collects_damage_kills_and_overkill() {
+        let player = unit(0, "player");
+        let mushroom = unit(1, "mushroom");
+        let mut stats = BattleStats::default();
+
+        stats.record(&BattleLogEvent::TurnStarted {
+            unit: player.clone(),
+        });
+        stats.record(&damaged(&player, &mushroom, 20, 0));
+        stats.record(&BattleLogEvent::TurnStarted {
+            unit: mushroom.clone(),
+        });
+        stats.record(&damaged(&mushroom, &player, 5, 0));
+        stats.record(&BattleLogEvent::TurnStarted {
+            unit: player.clone(),
+        });
+        stats.record(&damaged(&player, &mushroom, 30, 8));
+        stats.record(&BattleLogEvent::Died {
+            unit: mushroom.clone(),
+        });
+
+        let units = stats.units().collect::<Vec<_>>();
+        assert_eq!(stats.turns, 3);
+        assert_eq!(
+            units[0].1,
+            &UnitStats {
+                name: "player".to_string(),
+                damage_dealt: 42,
+                damage_taken: 5,
+                kills: 1,
+                turns: 2,
+                abilities_used: 2,
+                overkill: 8,
+            }
+        );
+        assert_eq!(units[1].1.damage_taken, 42);
+        assert_eq!(units[1].1.kills, 0);
+    }
+
+    #[test]
+    fn run_totals_only_count_player_units() {
+        let player = unit(0, "player");
+        let mushroom = unit(1, "mushroom");
+        let mut stats = BattleStats::default();
+        stats.record(&damaged(&player, &mushroom, 10, 0));
+        stats.record(&damaged(&mushroom, &player, 7, 0));
+
+        let mut run = RunStats::default();
+        run.add_battle(&stats, &HashSet::from([player.id]));
+        run.add_battle(&stats, &HashSet::from([player.id]));
+
+        assert_eq!(run.battles, 2);
+        assert_eq!(run.damage_dealt, 20);
+        assert_eq!(run.damage_taken, 14);
+    }
+}
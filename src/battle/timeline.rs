@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+
+use crate::{character::Group, theme::Theme};
+
+use super::{lifecycle::LifeState, ui::HighlightsUnits, vision::Concealed, BattleQueue};
+
+/// Number of upcoming turns shown in the timeline
+const TIMELINE_LENGTH: usize = 8;
+const PORTRAIT_SIZE: f32 = 48.0;
+
+/// Strip of portraits of the units acting next
+#[derive(Component, Default)]
+pub struct TurnTimeline {
+    /// Units in the strip, and whether they were concealed
+    shown: Vec<(Entity, bool)>,
+}
+
+pub fn build_turn_timeline(parent: &mut ChildBuilder) {
+    parent.spawn((
+        NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            ..default()
+        },
+        TurnTimeline::default(),
+    ));
+}
+
+/// The next turns starting with the current one, dead units are skipped
+fn upcoming_turns(
+    queue: &BattleQueue,
+    alive: impl Fn(Entity) -> bool,
+    length: usize,
+) -> Vec<Entity> {
+    // enough rounds to fill the strip even with a single unit left
+    queue
+        .upcoming()
+        .take((length + 1) * queue.order.len())
+        .filter(|unit| alive(*unit))
+        .take(length)
+        .collect()
+}
+
+/// Concealed enemies keep their place in the strip, but without a portrait
+pub fn update_turn_timeline(
+    mut commands: Commands,
+    res_queue: Option<Res<BattleQueue>>,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    unit_query: Query<(&LifeState, &Group, &Handle<Image>, Option<&Concealed>)>,
+    mut timeline_query: Query<(Entity, &mut TurnTimeline)>,
+) {
+    let Some(res_queue) = res_queue else {
+        return;
+    };
+
+    let upcoming = upcoming_turns(
+        &res_queue,
+        |unit| unit_query.get(unit).map(|(life_state, ..)| *life_state) == Ok(LifeState::Alive),
+        TIMELINE_LENGTH,
+    )
+    .into_iter()
+    .map(|unit| {
+        let concealed = matches!(unit_query.get(unit), Ok((.., Some(_))));
+        (unit, concealed)
+    })
+    .collect::<Vec<_>>();
+
+    for (timeline, mut turn_timeline) in &mut timeline_query {
+        if turn_timeline.shown == upcoming && !theme.is_changed() {
+            continue;
+        }
+
+        commands.entity(timeline).despawn_descendants();
+        commands.entity(timeline).with_children(|parent| {
+            for (i, &(unit, concealed)) in upcoming.iter().enumerate() {
+                let Ok((_, group, image, _)) = unit_query.get(unit) else {
+                    continue;
+                };
+
+                let border_color = if i == 0 {
+                    theme.active_unit
+                } else {
+                    match group {
+                        Group::Player => theme.ally,
+                        Group::Enemy => theme.enemy,
+                    }
+                };
+
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::all(Val::Px(PORTRAIT_SIZE)),
+                                border: UiRect::all(Val::Px(if i == 0 { 3.0 } else { 2.0 })),
+                                margin: UiRect::right(Val::Px(4.0)),
+                                ..default()
+                            },
+                            background_color: border_color.into(),
+                            ..default()
+                        },
+                        Interaction::default(),
+                        HighlightsUnits(vec![unit.index()]),
+                    ))
+                    .with_children(|parent| {
+                        if concealed {
+                            parent
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        size: Size::all(Val::Percent(100.0)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: theme.normal_button.into(),
+                                    ..default()
+                                })
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "?",
+                                        TextStyle {
+                                            font: asset_server.load("fonts/FiraSans-Medium.ttf"),
+                                            font_size: 30.0,
+                                            color: Color::WHITE,
+                                        },
+                                    ));
+                                });
+                        } else {
+                            parent.spawn(ImageBundle {
+                                style: Style {
+                                    size: Size::all(Val::Percent(100.0)),
+                                    ..default()
+                                },
+                                image: UiImage::new(image.clone()),
+                                ..default()
+                            });
+                        }
+                    });
+            }
+        });
+
+        turn_timeline.shown = upcoming.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_dead_units_and_wraps_around() {
+        let units = (0..3).map(Entity::from_raw).collect::<Vec<_>>();
+        let queue = BattleQueue::new(units.iter().map(|unit| (*unit, 10)));
+
+        let upcoming = upcoming_turns(&queue, |unit| unit != units[1], 5);
+
+        assert_eq!(upcoming, [units[0], units[2], units[0], units[2], units[0]]);
+        assert!(upcoming_turns(&queue, |_| false, 5).is_empty());
+    }
+
+    #[test]
+    fn faster_units_act_first_every_round() {
+        let units = (0..3).map(Entity::from_raw).collect::<Vec<_>>();
+        let mut speeds = vec![8, 12, 10];
+        let mut queue = BattleQueue::new(units.iter().copied().zip(speeds.clone()));
+
+        assert_eq!(
+            upcoming_turns(&queue, |_| true, 4),
+            [units[1], units[2], units[0], units[1]]
+        );
+
+        // the slowest unit gets hasted after the fastest acted
+        queue.next_turn();
+        speeds[0] = 20;
+        queue.reorder(|unit| speeds[unit.index() as usize]);
+        assert_eq!(
+            upcoming_turns(&queue, |_| true, 5),
+            [units[2], units[0], units[0], units[1], units[2]]
+        );
+    }
+}
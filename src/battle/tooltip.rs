@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use fluent::fluent_args;
+
+use crate::{
+    abilities::forecast_ability,
+    character::{Abilities, Attributes, CharacterCategory, CharacterName},
+    locale::Locale,
+};
+
+use super::{
+    battle_field::Tile,
+    interactions::{AbilityButton, ChosenAbility},
+    ui::BOTTOM_PANE_HEIGHT,
+    Battle, BattleQueue,
+};
+
+#[derive(Component)]
+pub struct BattleTooltip;
+
+/// Valid target tile hovered by the mouse or the keyboard cursor
+#[derive(Resource, Default)]
+pub struct HoveredTarget(pub Option<Entity>);
+
+pub fn build_tooltip(commands: &mut Commands, asset_server: &Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(10.0),
+                        bottom: Val::Px(BOTTOM_PANE_HEIGHT + 10.0),
+                        ..default()
+                    },
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.1, 0.1, 0.1, 0.9).into(),
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+            BattleTooltip,
+            Battle,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Medium.ttf"),
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+/// Shows the forecast of the chosen ability on the hovered target, or the
+/// description of the hovered ability button
+pub fn update_tooltip(
+    res_queue: Option<Res<BattleQueue>>,
+    res_ability: Option<Res<ChosenAbility>>,
+    hovered_target: Res<HoveredTarget>,
+    locale: Res<Locale>,
+    button_query: Query<(&Interaction, &AbilityButton)>,
+    abilities_query: Query<&Abilities>,
+    unit_query: Query<(&CharacterName, &CharacterCategory, &Attributes)>,
+    tile_query: Query<&Children, With<Tile>>,
+    mut tooltip_query: Query<(&mut Style, &Children), With<BattleTooltip>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let Some(res_queue) = res_queue else {
+        return;
+    };
+    let caster = res_queue.get_current();
+
+    let forecast = || {
+        let ability = res_ability.as_ref()?.ability();
+        let target = *tile_query.get(hovered_target.0?).ok()?.first()?;
+        let (target_name, category, target_attributes) = unit_query.get(target).ok()?;
+        let (caster_name, _, caster_attributes) = unit_query.get(caster).ok()?;
+
+        let mut lines = vec![locale.format(
+            "battle-ability-on",
+            &fluent_args![
+                "ability" => locale.ability_name(&ability.name),
+                "target" => locale.character_name(&target_name.0)
+            ],
+        )];
+        lines.extend(forecast_ability(
+            ability,
+            (caster_name, caster_attributes),
+            (category, target_attributes),
+            &locale,
+        ));
+        Some(lines.join("\n"))
+    };
+
+    let description = || {
+        let (_, button) = button_query
+            .iter()
+            .find(|(interaction, _)| **interaction != Interaction::None)?;
+        let ability = abilities_query
+            .get(caster)
+            .ok()?
+            .0
+            .get(button.ability_name())?;
+        Some(ability.description(&locale))
+    };
+
+    let tooltip = forecast().or_else(description);
+
+    for (mut style, children) in &mut tooltip_query {
+        let display = if tooltip.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+
+        let Some(tooltip) = &tooltip else {
+            continue;
+        };
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if let Some(section) = text.sections.first_mut() {
+                    if section.value != *tooltip {
+                        section.value = tooltip.clone();
+                    }
+                }
+            }
+        }
+    }
+}
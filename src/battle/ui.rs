@@ -5,46 +5,92 @@ use bevy::{
         camera::{ScalingMode, Viewport},
         view::RenderLayers,
     },
+    utils::HashSet,
     window::WindowResized,
 };
 use bevy_mod_picking::PickingCameraBundle;
+use fluent::fluent_args;
 
-use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::{
+    input::{InputAction, InputMap},
+    locale::Locale,
+    settings::OpenSettingsButton,
+    theme::Theme,
+};
 
 use super::{
-    log::BattleLogText,
+    inspect::build_inspection_panel,
+    lifecycle::LifeState,
+    log::{BattleLogFilterButton, BattleLogList, BattleLogPanel},
+    timeline::build_turn_timeline,
+    tooltip::build_tooltip,
     {AvailableActionsNode, Battle, BattleState},
 };
 
-const BOTTOM_PANE_HEIGHT: f32 = 150.0;
-const RIGHT_PANE_WIDTH: f32 = 220.0;
+pub const BOTTOM_PANE_HEIGHT: f32 = 150.0;
+pub const RIGHT_PANE_WIDTH: f32 = 220.0;
 
 #[derive(Component)]
 pub struct TopText;
 
+/// UI node which highlights the units with these ids (`LogUnit::id`) on the
+/// board while it's hovered
+#[derive(Component)]
+pub struct HighlightsUnits(pub Vec<u32>);
+
 #[derive(Component)]
 pub struct BattleCamera;
 
-pub fn update_top_text(state: Res<State<BattleState>>, mut query: Query<&mut Text, With<TopText>>) {
-    if state.is_changed() {
+pub fn update_top_text(
+    state: Res<State<BattleState>>,
+    locale: Res<Locale>,
+    input_map: Res<InputMap>,
+    mut query: Query<&mut Text, With<TopText>>,
+) {
+    if state.is_changed() || locale.is_changed() || input_map.is_changed() {
+        let cursor = [
+            InputAction::CursorUp,
+            InputAction::CursorDown,
+            InputAction::CursorLeft,
+            InputAction::CursorRight,
+        ]
+        .map(|action| input_map.hint(action))
+        .join("/");
         let update_text = match state.0 {
-            BattleState::BattleInit => "",
-            BattleState::BattleEnd => "",
-            BattleState::AbilityChoosingPlayer => "Select an ability",
-            BattleState::AbilityTargeting => "Select a target (Esc or RMB to cancel)",
-            BattleState::AbilityCastingEnemy => "Enemy's turn",
-            BattleState::AbilityResolution => "Resolving an ability",
+            BattleState::BattleInit | BattleState::BattleEnd => String::new(),
+            BattleState::AbilityChoosingPlayer => locale.get("battle-choosing-ability"),
+            BattleState::AbilityTargeting => locale.format(
+                "battle-targeting",
+                &fluent_args![
+                    "next" => input_map.hint(InputAction::NextTarget),
+                    "cursor" => cursor,
+                    "confirm" => input_map.hint(InputAction::Confirm),
+                    "cancel" => input_map.hint(InputAction::Cancel)
+                ],
+            ),
+            BattleState::AbilityCastingEnemy => locale.get("battle-enemy-turn"),
+            BattleState::AbilityResolution => locale.format(
+                "battle-resolving",
+                &fluent_args!["speed" => input_map.hint(InputAction::CycleAnimationSpeed)],
+            ),
         };
 
         for mut text in &mut query {
             if let Some(section) = text.sections.first_mut() {
-                section.value = update_text.into();
+                section.value = update_text.clone();
             }
         }
     }
 }
 
-pub fn build_right_pane(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>) {
+pub fn build_right_pane(
+    parent: &mut ChildBuilder,
+    asset_server: &Res<AssetServer>,
+    locale: &Locale,
+    theme: &Theme,
+) {
+    let font = asset_server.load("fonts/FiraSans-Medium.ttf");
+
     parent
         .spawn(NodeBundle {
             style: Style {
@@ -60,32 +106,136 @@ pub fn build_right_pane(parent: &mut ChildBuilder, asset_server: &Res<AssetServe
             parent
                 .spawn(NodeBundle {
                     style: Style {
-                        size: Size::width(Val::Percent(100.0)),
+                        flex_direction: FlexDirection::Column,
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                         ..default()
                     },
                     background_color: Color::rgb(0.15, 0.15, 0.15).into(),
                     ..default()
                 })
                 .with_children(|parent| {
-                    // text
                     parent
-                        .spawn(
-                            TextBundle::from_section(
-                                "Battle Log",
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    size: Size::height(Val::Px(30.0)),
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: theme.normal_button.into(),
+                                ..default()
+                            },
+                            OpenSettingsButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                locale.get("menu-settings"),
                                 TextStyle {
-                                    font: asset_server.load("fonts/FiraSans-Medium.ttf"),
-                                    font_size: 30.0,
+                                    font: font.clone(),
+                                    font_size: 20.0,
                                     color: Color::WHITE,
                                 },
-                            )
-                            .with_style(Style {
-                                max_size: Size::width(Val::Px(RIGHT_PANE_WIDTH - 10.0)),
-                                flex_wrap: FlexWrap::Wrap,
-                                margin: UiRect::all(Val::Px(5.0)),
-                                ..default()
-                            }),
+                            ));
+                        });
+
+                    build_inspection_panel(parent, asset_server);
+
+                    // text
+                    parent.spawn(
+                        TextBundle::from_section(
+                            locale.get("battle-log"),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 30.0,
+                                color: Color::WHITE,
+                            },
                         )
-                        .insert(BattleLogText);
+                        .with_style(Style {
+                            max_size: Size::width(Val::Px(RIGHT_PANE_WIDTH - 10.0)),
+                            flex_wrap: FlexWrap::Wrap,
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        }),
+                    );
+
+                    // filters
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                justify_content: JustifyContent::SpaceEvenly,
+                                margin: UiRect::bottom(Val::Px(5.0)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (filter, label) in [
+                                (
+                                    BattleLogFilterButton::OnlyDamage,
+                                    "battle-log-filter-damage",
+                                ),
+                                (
+                                    BattleLogFilterButton::OnlyMyUnits,
+                                    "battle-log-filter-my-units",
+                                ),
+                            ] {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: Style {
+                                                padding: UiRect::all(Val::Px(4.0)),
+                                                ..default()
+                                            },
+                                            background_color: theme.normal_button.into(),
+                                            ..default()
+                                        },
+                                        filter,
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle::from_section(
+                                            locale.get(label),
+                                            TextStyle {
+                                                font: font.clone(),
+                                                font_size: 14.0,
+                                                color: Color::WHITE,
+                                            },
+                                        ));
+                                    });
+                            }
+                        });
+
+                    // scrollable entries
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    align_self: AlignSelf::Stretch,
+                                    flex_grow: 1.0,
+                                    overflow: Overflow::Hidden,
+                                    margin: UiRect::horizontal(Val::Px(5.0)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            Interaction::default(),
+                            BattleLogPanel,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        flex_direction: FlexDirection::Column,
+                                        flex_shrink: 0.0,
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                BattleLogList::default(),
+                            ));
+                        });
                 });
         });
 }
@@ -135,6 +285,8 @@ pub fn setup_battle_ui(
     mut commands: Commands,
     windows: Query<&Window>,
     asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    theme: Res<Theme>,
 ) {
     let window = windows.single();
 
@@ -151,8 +303,8 @@ pub fn setup_battle_ui(
             },
             projection: OrthographicProjection {
                 scaling_mode: ScalingMode::AutoMin {
-                    min_width: WINDOW_WIDTH,
-                    min_height: WINDOW_HEIGHT,
+                    min_width: window.width(),
+                    min_height: window.height(),
                 },
                 ..default()
             },
@@ -196,6 +348,7 @@ pub fn setup_battle_ui(
                         .spawn(NodeBundle {
                             style: Style {
                                 size: Size::AUTO,
+                                flex_direction: FlexDirection::Column,
                                 ..default()
                             },
                             ..default()
@@ -211,11 +364,14 @@ pub fn setup_battle_ui(
                                     },
                                 ))
                                 .insert(TopText);
+                            build_turn_timeline(parent);
                         });
                     build_bottom_pane(parent);
                 });
-            build_right_pane(parent, &asset_server);
+            build_right_pane(parent, &asset_server, &locale, &theme);
         });
+
+    build_tooltip(&mut commands, &asset_server);
 }
 
 pub fn resize_battle_camera_viewport(
@@ -230,3 +386,27 @@ pub fn resize_battle_camera_viewport(
         camera.viewport = Some(get_viewport(e.width, e.height, window.scale_factor()));
     }
 }
+
+/// Tints the units of every hovered `HighlightsUnits` node
+pub fn highlight_hovered_units(
+    theme: Res<Theme>,
+    node_query: Query<(&Interaction, &HighlightsUnits)>,
+    mut unit_query: Query<(Entity, &mut Sprite), With<LifeState>>,
+) {
+    let highlighted = node_query
+        .iter()
+        .filter(|(interaction, _)| **interaction != Interaction::None)
+        .flat_map(|(_, units)| units.0.iter().copied())
+        .collect::<HashSet<_>>();
+
+    for (entity, mut sprite) in &mut unit_query {
+        let color = if highlighted.contains(&entity.index()) {
+            theme.highlighted_unit
+        } else {
+            Color::WHITE
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
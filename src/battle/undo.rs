@@ -0,0 +1,344 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    abilities::{Ability, AbilityType, TurnEvent},
+    character::{Attributes, CharacterName},
+    input::{Actions, InputAction},
+    theme::Theme,
+};
+
+use super::{
+    interactions::{hotkey_button, AvailableActionsNode},
+    log::{BattleLogEvent, LogUnit},
+    stats::BattleStats,
+    BattleQueue, BattleState,
+};
+
+/// State of a unit right before an action of the current turn
+#[derive(Debug, Clone)]
+pub struct UnitSnapshot {
+    unit: Entity,
+    tile: Entity,
+    attributes: Attributes,
+}
+
+/// Everything an undoable action can change, actions that kill can't be
+/// undone as they roll for damage
+#[derive(Debug, Clone, Default)]
+pub struct TurnSnapshot {
+    units: Vec<UnitSnapshot>,
+    stats: BattleStats,
+}
+
+#[derive(SystemParam)]
+pub struct TurnSnapshots<'w, 's> {
+    unit_query: Query<'w, 's, (Entity, &'static Parent, &'static Attributes)>,
+    stats: Res<'w, BattleStats>,
+}
+
+impl TurnSnapshots<'_, '_> {
+    pub fn take(&self) -> TurnSnapshot {
+        TurnSnapshot {
+            units: self
+                .unit_query
+                .iter()
+                .map(|(unit, parent, attributes)| UnitSnapshot {
+                    unit,
+                    tile: parent.get(),
+                    attributes: attributes.clone(),
+                })
+                .collect(),
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingAction {
+    ability: Ability,
+    snapshot: TurnSnapshot,
+}
+
+/// Actions the player used during the current turn, the latest one last.
+/// The turn only ends once the player confirms it, until then actions can
+/// be taken back in reverse order, down to the last one which made undoing
+/// unfair.
+#[derive(Resource, Debug)]
+pub struct PendingTurn {
+    unit: Entity,
+    actions: Vec<PendingAction>,
+    /// Number of actions at the bottom of the stack which can't be undone
+    locked: usize,
+}
+
+impl PendingTurn {
+    pub fn new(unit: Entity) -> Self {
+        Self {
+            unit,
+            actions: vec![],
+            locked: 0,
+        }
+    }
+
+    pub fn unit(&self) -> Entity {
+        self.unit
+    }
+
+    pub fn push(&mut self, ability: Ability, snapshot: TurnSnapshot) {
+        let involves_randomness = ability.involves_randomness();
+        self.actions.push(PendingAction { ability, snapshot });
+        if involves_randomness {
+            self.lock();
+        }
+    }
+
+    /// Blocks undo of the actions used so far, for actions which rolled the
+    /// RNG or revealed something the player didn't know before. Actions
+    /// used afterwards can still be undone.
+    pub fn lock(&mut self) {
+        self.locked = self.actions.len();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.actions.len() > self.locked
+    }
+
+    /// A turn allows one movement and one targeted ability
+    pub fn can_use(&self, ability: &Ability) -> bool {
+        let is_movement = |ability: &Ability| ability.r#type == AbilityType::Movement;
+        !self
+            .actions
+            .iter()
+            .any(|action| is_movement(&action.ability) == is_movement(ability))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    fn pop(&mut self) -> Option<PendingAction> {
+        if self.can_undo() {
+            self.actions.pop()
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum TurnButton {
+    Undo,
+    EndTurn,
+}
+
+fn undo_last_action(
+    commands: &mut Commands,
+    pending_turn: &mut PendingTurn,
+    unit_query: &mut Query<(&CharacterName, &Parent, &mut Attributes)>,
+    stats: &mut BattleStats,
+    ev_battle_log: &mut EventWriter<BattleLogEvent>,
+) -> bool {
+    let Some(action) = pending_turn.pop() else {
+        return false;
+    };
+
+    *stats = action.snapshot.stats;
+
+    for snapshot in action.snapshot.units {
+        let Ok((_, parent, mut attributes)) = unit_query.get_mut(snapshot.unit) else {
+            continue;
+        };
+
+        if parent.get() != snapshot.tile {
+            commands
+                .entity(snapshot.tile)
+                .push_children(&[snapshot.unit]);
+        }
+        *attributes = snapshot.attributes;
+    }
+
+    if let Ok((name, ..)) = unit_query.get(pending_turn.unit) {
+        ev_battle_log.send(BattleLogEvent::Undone {
+            unit: LogUnit::new(pending_turn.unit, name),
+            ability: action.ability.name,
+        });
+    }
+
+    true
+}
+
+pub fn handle_turn_controls(
+    mut commands: Commands,
+    actions: Actions,
+    node_query: Query<&Children, With<AvailableActionsNode>>,
+    turn_button_query: Query<&TurnButton>,
+    res_queue: Res<BattleQueue>,
+    theme: Res<Theme>,
+    pending_turn: Option<ResMut<PendingTurn>>,
+    mut button_query: Query<
+        (&Interaction, &TurnButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut unit_query: Query<(&CharacterName, &Parent, &mut Attributes)>,
+    mut stats: ResMut<BattleStats>,
+    mut ev_ability: EventWriter<TurnEvent>,
+    mut ev_battle_log: EventWriter<BattleLogEvent>,
+    mut next_state: ResMut<NextState<BattleState>>,
+) {
+    let Some(mut pending_turn) = pending_turn else {
+        return;
+    };
+
+    let mut clicked = if actions.just_pressed(InputAction::Undo) {
+        Some(TurnButton::Undo)
+    } else {
+        hotkey_button(&actions, &node_query)
+            .and_then(|button| turn_button_query.get(button).ok())
+            .copied()
+    };
+
+    for (interaction, button, mut color) in &mut button_query {
+        match *interaction {
+            Interaction::Clicked => clicked = Some(*button),
+            Interaction::Hovered => *color = theme.hovered_button.into(),
+            Interaction::None => *color = theme.normal_button.into(),
+        }
+    }
+
+    match clicked {
+        Some(TurnButton::Undo)
+            if undo_last_action(
+                &mut commands,
+                &mut pending_turn,
+                &mut unit_query,
+                &mut stats,
+                &mut ev_battle_log,
+            ) =>
+        {
+            // rebuild the ability buttons
+            next_state.set(BattleState::AbilityChoosingPlayer);
+        }
+        Some(TurnButton::EndTurn) => {
+            let unit = res_queue.get_current();
+            ev_ability.send(if pending_turn.is_empty() {
+                TurnEvent::Pass(unit)
+            } else {
+                TurnEvent::EndTurn
+            });
+            commands.remove_resource::<PendingTurn>();
+        }
+        _ => {}
+    }
+}
+
+pub fn cleanup_pending_turn(mut commands: Commands) {
+    commands.remove_resource::<PendingTurn>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        abilities::{
+            damage::DamageType, AbilityProximity, AbilityTargetType, AbilityType,
+            TargetedAbilityType,
+        },
+        character::{AttributeType, ModifierKind, Stacking},
+    };
+
+    fn ability(name: &str, r#type: AbilityType) -> Ability {
+        Ability {
+            name: name.to_string(),
+            r#type,
+            target: AbilityTargetType::Enemy.into(),
+            range: 1,
+        }
+    }
+
+    fn targeted(name: &str, ab_type: TargetedAbilityType) -> Ability {
+        ability(
+            name,
+            AbilityType::Targeted {
+                ab_type,
+                proximity: AbilityProximity::Ranged,
+            },
+        )
+    }
+
+    fn weaken() -> Ability {
+        targeted(
+            "weaken",
+            TargetedAbilityType::ModifyAttribute {
+                at_type: AttributeType::Attack,
+                change: ModifierKind::Flat(-3),
+                duration: 3,
+                stacking: Stacking::Refresh,
+            },
+        )
+    }
+
+    #[test]
+    fn one_movement_and_one_targeted_ability_per_turn() {
+        let mut turn = PendingTurn::new(Entity::from_raw(0));
+        let movement = ability("move", AbilityType::Movement);
+
+        turn.push(movement.clone(), TurnSnapshot::default());
+        assert!(!turn.can_use(&movement));
+        assert!(turn.can_use(&weaken()));
+
+        turn.push(weaken(), TurnSnapshot::default());
+        assert!(!turn.can_use(&weaken()));
+    }
+
+    #[test]
+    fn undoes_actions_in_reverse_order() {
+        let mut turn = PendingTurn::new(Entity::from_raw(0));
+        turn.push(
+            ability("move", AbilityType::Movement),
+            TurnSnapshot::default(),
+        );
+        turn.push(weaken(), TurnSnapshot::default());
+
+        assert_eq!(turn.pop().unwrap().ability.name, "weaken");
+        assert_eq!(turn.pop().unwrap().ability.name, "move");
+        assert!(turn.pop().is_none());
+        assert!(turn.is_empty());
+    }
+
+    #[test]
+    fn randomness_blocks_undo() {
+        let mut turn = PendingTurn::new(Entity::from_raw(0));
+        turn.push(
+            ability("move", AbilityType::Movement),
+            TurnSnapshot::default(),
+        );
+        turn.push(
+            targeted(
+                "hit",
+                TargetedAbilityType::Damage {
+                    potency: 10,
+                    damage_type: DamageType::Physical,
+                },
+            ),
+            TurnSnapshot::default(),
+        );
+
+        assert!(!turn.can_undo());
+        assert!(turn.pop().is_none());
+    }
+
+    #[test]
+    fn lock_freezes_only_earlier_actions() {
+        let mut turn = PendingTurn::new(Entity::from_raw(0));
+        turn.push(
+            ability("move", AbilityType::Movement),
+            TurnSnapshot::default(),
+        );
+        turn.lock();
+        assert!(!turn.can_undo());
+
+        turn.push(weaken(), TurnSnapshot::default());
+        assert_eq!(turn.pop().unwrap().ability.name, "weaken");
+        assert!(turn.pop().is_none());
+    }
+}
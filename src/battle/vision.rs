@@ -0,0 +1,293 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    character::{AttributeType, Attributes, CharacterName, Group},
+    utils::hex::Hex,
+    GameState,
+};
+
+use super::{
+    battle_field::{BattleField, Tile},
+    lifecycle::LifeState,
+    undo::PendingTurn,
+};
+
+/// Whether the player can see a tile right now
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sight {
+    #[default]
+    Seen,
+    Unseen,
+}
+
+/// Enemy standing where the player can't see it
+#[derive(Component)]
+pub struct Concealed;
+
+/// What each side of the battle sees, when the map or the difficulty hide
+/// everything out of sight
+#[derive(Resource, Debug, Default)]
+pub struct FogOfWar {
+    enabled: bool,
+    seen: HashMap<Group, HashSet<Hex>>,
+    /// Where each side last saw the units of the other one
+    last_known: HashMap<Group, HashMap<Entity, Hex>>,
+}
+
+impl FogOfWar {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..default()
+        }
+    }
+
+    pub fn sees(&self, group: Group, hex: Hex) -> bool {
+        !self.enabled
+            || self
+                .seen
+                .get(&group)
+                .is_some_and(|seen| seen.contains(&hex))
+    }
+
+    /// Where the group last saw the units it's fighting, which may be
+    /// out of date by now
+    pub fn last_known(&self, group: Group) -> impl Iterator<Item = Hex> + '_ {
+        self.last_known
+            .get(&group)
+            .into_iter()
+            .flat_map(|positions| positions.values().copied())
+    }
+}
+
+/// Hexes with a tile up to `radius` away from `from`, which neither the gaps
+/// in the map nor terrain in between block the view to
+pub fn field_of_view(battle_field: &BattleField, from: Hex, radius: i32) -> Vec<Hex> {
+    from.range(radius)
+        .into_iter()
+        .filter(|hex| {
+            let line = from.line(*hex);
+            line.iter().all(|hex| battle_field.tile(hex).is_some())
+                && line
+                    .iter()
+                    .skip(1)
+                    .take(line.len().saturating_sub(2))
+                    .all(|hex| !battle_field.terrain(hex).blocks_sight())
+        })
+        .collect()
+}
+
+fn opponent(group: Group) -> Group {
+    match group {
+        Group::Player => Group::Enemy,
+        Group::Enemy => Group::Player,
+    }
+}
+
+pub fn setup_fog_of_war(game_state: Res<GameState>, mut fog: ResMut<FogOfWar>) {
+    *fog = FogOfWar::new(
+        game_state.battle_field_layout.fog_of_war || game_state.difficulty.fog_of_war(),
+    );
+}
+
+pub fn update_vision(
+    mut commands: Commands,
+    battle_field: Option<Res<BattleField>>,
+    mut fog: ResMut<FogOfWar>,
+    pending_turn: Option<ResMut<PendingTurn>>,
+    unit_query: Query<
+        (
+            Entity,
+            &Group,
+            &Parent,
+            &Attributes,
+            &LifeState,
+            Option<&Concealed>,
+        ),
+        With<CharacterName>,
+    >,
+    mut tile_query: Query<(Entity, &mut Sight), With<Tile>>,
+) {
+    let Some(battle_field) = battle_field else {
+        return;
+    };
+    if !fog.enabled {
+        return;
+    }
+
+    let units = unit_query
+        .iter()
+        .filter(|(.., life_state, _)| **life_state == LifeState::Alive)
+        .filter_map(|(unit, group, parent, attributes, _, concealed)| {
+            let hex = battle_field.hex(parent.get())?;
+            Some((unit, *group, hex, attributes, concealed.is_some()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut seen = HashMap::<Group, HashSet<Hex>>::new();
+    for (_, group, hex, attributes, _) in &units {
+        let radius = attributes
+            .0
+            .get(&AttributeType::Vision)
+            .map_or(0, |vision| vision.get_value());
+        seen.entry(*group)
+            .or_default()
+            .extend(field_of_view(&battle_field, *hex, radius));
+    }
+    fog.seen = seen;
+
+    // remember where the other side was seen, and forget it once the spot
+    // is seen empty or the unit died
+    let positions = units
+        .iter()
+        .map(|(unit, _, hex, ..)| (*unit, *hex))
+        .collect::<HashMap<_, _>>();
+    for group in [Group::Player, Group::Enemy] {
+        let mut last_known = fog.last_known.remove(&group).unwrap_or_default();
+        last_known.retain(|unit, hex| {
+            positions.get(unit) == Some(hex)
+                || positions.contains_key(unit) && !fog.sees(group, *hex)
+        });
+        for (unit, unit_group, hex, ..) in &units {
+            if *unit_group == opponent(group) && fog.sees(group, *hex) {
+                last_known.insert(*unit, *hex);
+            }
+        }
+        fog.last_known.insert(group, last_known);
+    }
+
+    let mut revealed = false;
+    for (unit, group, hex, _, concealed) in &units {
+        if *group == Group::Player {
+            continue;
+        }
+        match (concealed, fog.sees(Group::Player, *hex)) {
+            (true, true) => {
+                commands.entity(*unit).remove::<Concealed>();
+                revealed = true;
+            }
+            (false, false) => {
+                commands.entity(*unit).insert(Concealed);
+            }
+            _ => {}
+        }
+    }
+    // an action which found an enemy can't be taken back to try another way
+    if let Some(mut pending_turn) = pending_turn {
+        if revealed && !pending_turn.is_empty() {
+            pending_turn.lock();
+        }
+    }
+
+    for (tile, mut sight) in &mut tile_query {
+        let hex = battle_field
+            .hex(tile)
+            .expect("Tile not on the battle field");
+        sight.set_if_neq(if fog.sees(Group::Player, hex) {
+            Sight::Seen
+        } else {
+            Sight::Unseen
+        });
+    }
+}
+
+/// Keeps concealed units hidden, whatever else shows them, and shows them
+/// again as they come into sight
+pub fn hide_concealed_units(
+    images: Res<Assets<Image>>,
+    mut revealed: RemovedComponents<Concealed>,
+    mut concealed_query: Query<&mut Visibility, With<Concealed>>,
+    mut unit_query: Query<(&mut Visibility, &Handle<Image>, &LifeState), Without<Concealed>>,
+) {
+    for mut visibility in &mut concealed_query {
+        visibility.set_if_neq(Visibility::Hidden);
+    }
+
+    for unit in revealed.iter() {
+        if let Ok((mut visibility, image, life_state)) = unit_query.get_mut(unit) {
+            *visibility = if *life_state == LifeState::Alive && images.get(image).is_some() {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+}
+
+pub fn cleanup_fog_of_war(mut fog: ResMut<FogOfWar>) {
+    *fog = FogOfWar::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{battle::battle_field::Terrain, utils::hex::HexOrientation};
+
+    use super::*;
+
+    fn battle_field(
+        hexes: impl Iterator<Item = Hex>,
+        terrain: HashMap<Hex, Terrain>,
+    ) -> BattleField {
+        let tiles = hexes
+            .enumerate()
+            .map(|(i, hex)| (hex, Entity::from_raw(i as u32)))
+            .collect();
+        BattleField::new(
+            tiles,
+            1.0,
+            HexOrientation::PointyTop,
+            Rect::default(),
+            terrain,
+        )
+    }
+
+    #[test]
+    fn vision_reaches_its_radius() {
+        let battle_field = battle_field(Hex::ZERO.spiral(6).into_iter(), HashMap::new());
+        let seen = field_of_view(&battle_field, Hex::ZERO, 3);
+
+        assert_eq!(seen.len(), 37);
+        assert!(seen.iter().all(|hex| hex.dist(Hex::ZERO) <= 3));
+    }
+
+    #[test]
+    fn gaps_block_the_view() {
+        let gap = Hex::ZERO.neighbour(0);
+        let battle_field = battle_field(
+            Hex::ZERO.spiral(4).into_iter().filter(|hex| *hex != gap),
+            HashMap::new(),
+        );
+        let seen = field_of_view(&battle_field, Hex::ZERO, 4);
+
+        let behind = gap.neighbour(0);
+        assert!(!seen.contains(&gap));
+        assert!(!seen.contains(&behind));
+        assert!(seen.contains(&Hex::ZERO.neighbour(1)));
+    }
+
+    #[test]
+    fn tall_grass_is_seen_into_but_not_past() {
+        let grass = Hex::ZERO.neighbour(0);
+        let battle_field = battle_field(
+            Hex::ZERO.spiral(4).into_iter(),
+            HashMap::from_iter([(grass, Terrain::TallGrass)]),
+        );
+        let seen = field_of_view(&battle_field, Hex::ZERO, 4);
+
+        assert!(seen.contains(&grass));
+        assert!(!seen.contains(&grass.neighbour(0)));
+    }
+
+    #[test]
+    fn without_fog_everything_is_seen() {
+        let fog = FogOfWar::new(false);
+        assert!(fog.sees(Group::Player, Hex::from_oddr((20, 20).into())));
+
+        let fog = FogOfWar::new(true);
+        assert!(!fog.sees(Group::Player, Hex::ZERO));
+        assert_eq!(fog.last_known(Group::Enemy).count(), 0);
+    }
+}
@@ -1,13 +1,25 @@
 use bevy::{prelude::*, utils::HashMap};
+use fluent::fluent_args;
+use serde::Serialize;
 
-use crate::abilities::Ability;
+use crate::abilities::{damage::DamageType, Ability};
+use crate::locale::Locale;
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Group {
     Player,
     Enemy,
 }
 
+impl Group {
+    pub fn name(&self, locale: &Locale) -> String {
+        locale.get(match self {
+            Self::Player => "group-player",
+            Self::Enemy => "group-enemy",
+        })
+    }
+}
+
 #[derive(Component, Debug, Clone)]
 pub struct CharacterName(pub String);
 
@@ -32,21 +44,7 @@ impl CharacterBundle {
             name: CharacterName(name.to_string()),
             category,
             abilities: Abilities::from_arr(abilities),
-            attributes: Attributes(
-                attributes
-                    .iter()
-                    .copied()
-                    .map(|(typ, value)| {
-                        (
-                            typ,
-                            match typ.get_corresponding_value_type() {
-                                AttributeValueType::Value => Attribute::value(value),
-                                AttributeValueType::Gauge => Attribute::gauge(value),
-                            },
-                        )
-                    })
-                    .collect(),
-            ),
+            attributes: Attributes::with_overrides(attributes),
             group,
         }
     }
@@ -74,6 +72,27 @@ pub enum CharacterCategory {
     Fungus,
 }
 
+impl CharacterCategory {
+    /// Resistance to a damage type in percent, negative values are weaknesses
+    pub fn resistance(&self, damage_type: DamageType) -> i32 {
+        use DamageType::*;
+
+        match (self, damage_type) {
+            (Self::Human, Physical) => 0,
+            (Self::Human, Spore) => -25,
+            (Self::Fungus, Physical) => 0,
+            (Self::Fungus, Spore) => 50,
+        }
+    }
+
+    pub fn name(&self, locale: &Locale) -> String {
+        locale.get(match self {
+            Self::Human => "category-human",
+            Self::Fungus => "category-fungus",
+        })
+    }
+}
+
 #[derive(Component, Debug, Clone, Default)]
 pub struct Abilities(pub HashMap<String, Ability>);
 
@@ -94,66 +113,722 @@ pub struct Attributes(pub HashMap<AttributeType, Attribute>);
 impl Default for Attributes {
     fn default() -> Self {
         Attributes(
-            [
-                (AttributeType::HitPoints, Attribute::gauge(150)),
-                (AttributeType::Attack, Attribute::value(10)),
-                (AttributeType::Defense, Attribute::value(10)),
-            ]
-            .into_iter()
-            .collect(),
+            AttributeType::ALL
+                .iter()
+                .map(|&typ| (typ, Attribute::new(typ, typ.definition().default)))
+                .collect(),
         )
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum AttributeType {
-    HitPoints,
-    Attack,
-    Defense,
-}
-
-impl AttributeType {
-    pub fn get_corresponding_value_type(&self) -> AttributeValueType {
-        use AttributeValueType::*;
+impl Attributes {
+    /// Current value of the attribute, the default if the unit lacks it
+    pub fn value(&self, typ: AttributeType) -> i32 {
+        self.0
+            .get(&typ)
+            .map(|attribute| attribute.get_value())
+            .unwrap_or_else(|| typ.definition().default)
+    }
 
-        match self {
-            Self::HitPoints => Gauge,
-            Self::Attack => Value,
-            Self::Defense => Value,
+    /// Default attributes with the given base values replaced
+    pub fn with_overrides(overrides: &[(AttributeType, i32)]) -> Self {
+        let mut attributes = Self::default();
+        for &(typ, value) in overrides {
+            attributes.0.insert(typ, Attribute::new(typ, value));
         }
+        attributes
     }
 }
 
-// I was hoping to find a crate with a macro
-// to autogenerate that...
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AttributeValueType {
     Value,
     Gauge,
 }
 
+/// Limits enforced on the effective value of an attribute. For gauges
+/// `max` caps the maximum of the gauge rather than its current value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clamp {
+    pub min: i32,
+    pub max: Option<i32>,
+}
+
+impl Clamp {
+    pub const NON_NEGATIVE: Clamp = Clamp { min: 0, max: None };
+
+    pub fn apply(&self, value: i32) -> i32 {
+        let value = value.max(self.min);
+        self.max.map_or(value, |max| value.min(max))
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
+pub struct AttributeDefinition {
+    pub value_type: AttributeValueType,
+    pub default: i32,
+    /// Id of the attribute's name in the locale files
+    pub message_id: &'static str,
+    pub clamp: Clamp,
+    /// Whether a bar is drawn above units for this attribute, only gauges
+    /// can have one
+    pub bar: bool,
+}
+
+/// Declares every attribute together with its metadata, generating the
+/// `AttributeType` enum and its `definition` lookup.
+macro_rules! attributes {
+    ($($typ:ident {
+        value_type: $value_type:ident,
+        default: $default:expr,
+        message_id: $message_id:expr,
+        clamp: ($min:expr, $max:expr),
+        bar: $bar:expr $(,)?
+    }),* $(,)?) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+        pub enum AttributeType {
+            $($typ),*
+        }
+
+        impl AttributeType {
+            pub const ALL: &'static [AttributeType] = &[$(Self::$typ),*];
+
+            pub fn definition(&self) -> AttributeDefinition {
+                match self {
+                    $(Self::$typ => AttributeDefinition {
+                        value_type: AttributeValueType::$value_type,
+                        default: $default,
+                        message_id: $message_id,
+                        clamp: Clamp { min: $min, max: $max },
+                        bar: $bar,
+                    }),*
+                }
+            }
+        }
+    };
+}
+
+attributes! {
+    HitPoints {
+        value_type: Gauge,
+        default: 150,
+        message_id: "attribute-hit-points",
+        clamp: (0, None),
+        bar: true,
+    },
+    Attack {
+        value_type: Value,
+        default: 10,
+        message_id: "attribute-attack",
+        clamp: (0, None),
+        bar: false,
+    },
+    Defense {
+        value_type: Value,
+        default: 10,
+        message_id: "attribute-defense",
+        clamp: (0, None),
+        bar: false,
+    },
+    Speed {
+        value_type: Value,
+        default: 10,
+        message_id: "attribute-speed",
+        clamp: (1, None),
+        bar: false,
+    },
+    Accuracy {
+        value_type: Value,
+        default: 95,
+        message_id: "attribute-accuracy",
+        clamp: (0, Some(100)),
+        bar: false,
+    },
+    Evasion {
+        value_type: Value,
+        default: 5,
+        message_id: "attribute-evasion",
+        clamp: (0, Some(100)),
+        bar: false,
+    },
+    Crit {
+        value_type: Value,
+        default: 5,
+        message_id: "attribute-crit-chance",
+        clamp: (0, Some(100)),
+        bar: false,
+    },
+    Energy {
+        value_type: Gauge,
+        default: 10,
+        message_id: "attribute-energy",
+        clamp: (0, None),
+        bar: true,
+    },
+    Shield {
+        value_type: Gauge,
+        default: 0,
+        message_id: "attribute-shield",
+        clamp: (0, None),
+        bar: true,
+    },
+    Vision {
+        value_type: Value,
+        default: 5,
+        message_id: "attribute-vision",
+        clamp: (0, None),
+        bar: false,
+    },
+}
+
+impl AttributeType {
+    pub fn name(&self, locale: &Locale) -> String {
+        locale.get(self.definition().message_id)
+    }
+
+    /// Whether stronger enemies get more of the attribute. Chances in
+    /// percent and the vision radius stay the same for everyone.
+    pub fn scales_with_power(&self) -> bool {
+        !matches!(
+            self,
+            Self::Accuracy | Self::Evasion | Self::Crit | Self::Vision
+        )
+    }
+}
+
+/// Where an attribute modifier came from, so that it can be shown
+/// in the UI and removed again later.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ModifierSource {
+    PowerUp(String),
+    Ability { name: String, caster: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ModifierKind {
+    Flat(i32),
+    Percent(i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeModifier {
+    pub kind: ModifierKind,
+    pub source: ModifierSource,
+    /// Number of turns left, `None` means the modifier is permanent
+    pub duration: Option<u32>,
+}
+
+impl AttributeModifier {
+    pub fn flat(value: i32, source: ModifierSource) -> Self {
+        Self {
+            kind: ModifierKind::Flat(value),
+            source,
+            duration: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn percent(value: i32, source: ModifierSource) -> Self {
+        Self {
+            kind: ModifierKind::Percent(value),
+            source,
+            duration: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_duration(mut self, turns: u32) -> Self {
+        self.duration = Some(turns);
+        self
+    }
+}
+
+impl std::fmt::Display for ModifierKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Flat(v) => write!(f, "{v:+}"),
+            Self::Percent(v) => write!(f, "{v:+}%"),
+        }
+    }
+}
+
+/// How a temporary modifier behaves when applied again by the same effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stacking {
+    /// Every application adds a new modifier, up to `max` of them.
+    /// Once at the limit, reapplying refreshes the existing durations.
+    Stack { max: usize },
+    /// Reapplying only refreshes the duration of the existing modifier
+    Refresh,
+}
+
+impl ModifierSource {
+    pub fn describe(&self, locale: &Locale) -> String {
+        match self {
+            Self::PowerUp(name) => locale.format(
+                "source-power-up",
+                &fluent_args!["power-up" => locale.power_up_name(name)],
+            ),
+            Self::Ability { name, caster } => locale.format(
+                "source-ability",
+                &fluent_args![
+                    "caster" => locale.character_name(caster),
+                    "ability" => locale.ability_name(name)
+                ],
+            ),
+        }
+    }
+
+    /// Whether two sources come from the same effect, regardless of who
+    /// applied it
+    pub fn is_same_effect(&self, other: &ModifierSource) -> bool {
+        match (self, other) {
+            (Self::Ability { name, .. }, Self::Ability { name: other, .. }) => name == other,
+            _ => self == other,
+        }
+    }
+}
+
+impl AttributeModifier {
+    pub fn describe(&self, locale: &Locale) -> String {
+        let mut description = locale.format(
+            "modifier",
+            &fluent_args![
+                "change" => self.kind.to_string(),
+                "source" => self.source.describe(locale)
+            ],
+        );
+        if let Some(turns) = self.duration {
+            description += " ";
+            description += &locale.format("modifier-turns", &fluent_args!["turns" => turns]);
+        }
+        description
+    }
+}
+
+fn apply_modifiers(base: i32, modifiers: &[AttributeModifier]) -> i32 {
+    let (flat, percent) = modifiers
+        .iter()
+        .fold((0, 0), |(flat, percent), modifier| match modifier.kind {
+            ModifierKind::Flat(v) => (flat + v, percent),
+            ModifierKind::Percent(v) => (flat, percent + v),
+        });
+
+    ((base + flat) as f32 * (100 + percent) as f32 / 100.0).round() as i32
+}
+
+/// Attributes are stored as a base value and a list of modifiers, the
+/// effective value is always computed on read and kept within `clamp`.
+///
+/// For gauges the modifiers apply to the maximum, while `value` is the
+/// current fill which is kept within `get_min()..=get_max()`.
+#[derive(Debug, Clone)]
 pub enum Attribute {
-    Value(i32),
-    Gauge { value: i32, min: i32, max: i32 },
+    Value {
+        base: i32,
+        clamp: Clamp,
+        modifiers: Vec<AttributeModifier>,
+    },
+    Gauge {
+        value: i32,
+        max: i32,
+        clamp: Clamp,
+        modifiers: Vec<AttributeModifier>,
+    },
 }
 
 impl Attribute {
-    pub fn get_value(&self) -> i32 {
-        match self {
-            Self::Value(v) => *v,
-            Self::Gauge { value, .. } => *value,
+    pub fn new(typ: AttributeType, base: i32) -> Self {
+        let definition = typ.definition();
+        match definition.value_type {
+            AttributeValueType::Value => Self::Value {
+                base,
+                clamp: definition.clamp,
+                modifiers: vec![],
+            },
+            AttributeValueType::Gauge => Self::Gauge {
+                value: base,
+                max: base,
+                clamp: definition.clamp,
+                modifiers: vec![],
+            },
         }
     }
+
+    #[cfg(test)]
     pub fn value(value: i32) -> Self {
-        Self::Value(value)
+        Self::Value {
+            base: value,
+            clamp: Clamp::NON_NEGATIVE,
+            modifiers: vec![],
+        }
     }
 
+    #[cfg(test)]
     pub fn gauge(max: i32) -> Self {
         Self::Gauge {
             value: max,
-            min: 0,
             max,
+            clamp: Clamp::NON_NEGATIVE,
+            modifiers: vec![],
+        }
+    }
+
+    pub fn get_value(&self) -> i32 {
+        match self {
+            Self::Value {
+                base,
+                clamp,
+                modifiers,
+            } => clamp.apply(apply_modifiers(*base, modifiers)),
+            Self::Gauge { value, .. } => (*value).clamp(self.get_min(), self.get_max()),
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_min(&self) -> i32 {
+        match self {
+            Self::Value { clamp, .. } => clamp.min,
+            Self::Gauge { clamp, .. } => clamp.min,
+        }
+    }
+
+    pub fn get_max(&self) -> i32 {
+        match self {
+            Self::Value { .. } => self.get_value(),
+            Self::Gauge {
+                max,
+                clamp,
+                modifiers,
+                ..
+            } => clamp.apply(apply_modifiers(*max, modifiers)),
+        }
+    }
+
+    pub fn get_base(&self) -> i32 {
+        match self {
+            Self::Value { base, .. } => *base,
+            Self::Gauge { max, .. } => *max,
+        }
+    }
+
+    pub fn set_base(&mut self, new_base: i32) {
+        match self {
+            Self::Value { base, .. } => *base = new_base,
+            Self::Gauge { value, max, .. } => {
+                *value += new_base - *max;
+                *max = new_base;
+            }
+        }
+        self.clamp_value();
+    }
+
+    pub fn modifiers(&self) -> &[AttributeModifier] {
+        match self {
+            Self::Value { modifiers, .. } => modifiers,
+            Self::Gauge { modifiers, .. } => modifiers,
+        }
+    }
+
+    /// "Name: value (base b, modifiers...)", gauges which aren't full show
+    /// their current value as well
+    pub fn describe(&self, typ: AttributeType, locale: &Locale) -> String {
+        let mut description = if self.get_value() != self.get_max() {
+            locale.format(
+                "attribute-gauge",
+                &fluent_args![
+                    "attribute" => typ.name(locale),
+                    "value" => self.get_value(),
+                    "max" => self.get_max()
+                ],
+            )
+        } else {
+            locale.format(
+                "attribute-value",
+                &fluent_args!["attribute" => typ.name(locale), "value" => self.get_max()],
+            )
+        };
+
+        if !self.modifiers().is_empty() {
+            description += " (";
+            description +=
+                &locale.format("attribute-base", &fluent_args!["base" => self.get_base()]);
+            for modifier in self.modifiers() {
+                description += ", ";
+                description += &modifier.describe(locale);
+            }
+            description += ")";
+        }
+
+        description
+    }
+
+    fn modifiers_mut(&mut self) -> &mut Vec<AttributeModifier> {
+        match self {
+            Self::Value { modifiers, .. } => modifiers,
+            Self::Gauge { modifiers, .. } => modifiers,
+        }
+    }
+
+    /// Changes the current value of a gauge, returns the change that was
+    /// actually applied after clamping
+    pub fn change_value(&mut self, delta: i32) -> i32 {
+        let (min, max) = (self.get_min(), self.get_max());
+        match self {
+            Self::Value { .. } => panic!("Can't change current value of a \"value\" attribute!"),
+            Self::Gauge { value, .. } => {
+                let old_value = *value;
+                *value = (*value + delta).clamp(min, max);
+                *value - old_value
+            }
+        }
+    }
+
+    pub fn add_modifier(&mut self, modifier: AttributeModifier) {
+        self.update_modifiers(|modifiers| modifiers.push(modifier));
+    }
+
+    #[cfg(test)]
+    pub fn remove_modifiers_from(&mut self, source: &ModifierSource) {
+        self.update_modifiers(|modifiers| modifiers.retain(|m| &m.source != source));
+    }
+
+    /// Adds a modifier following the stacking rules, only modifiers coming
+    /// from the same effect are taken into account
+    pub fn apply_modifier(&mut self, modifier: AttributeModifier, stacking: Stacking) {
+        self.update_modifiers(|modifiers| {
+            let limit = match stacking {
+                Stacking::Stack { max } => max,
+                Stacking::Refresh => 1,
+            };
+
+            let same_effect = modifiers
+                .iter()
+                .filter(|m| m.source.is_same_effect(&modifier.source))
+                .count();
+
+            if same_effect < limit {
+                modifiers.push(modifier);
+            } else {
+                for existing in modifiers
+                    .iter_mut()
+                    .filter(|m| m.source.is_same_effect(&modifier.source))
+                {
+                    existing.duration = existing.duration.max(modifier.duration);
+                }
+            }
+        });
+    }
+
+    /// Whether applying the effect again would change anything beyond
+    /// extending a duration that isn't about to run out
+    pub fn can_apply(&self, source: &ModifierSource, stacking: Stacking) -> bool {
+        let same_effect = self
+            .modifiers()
+            .iter()
+            .filter(|m| m.source.is_same_effect(source))
+            .collect::<Vec<_>>();
+
+        match stacking {
+            Stacking::Stack { max } if same_effect.len() < max => true,
+            _ => same_effect
+                .iter()
+                .all(|m| m.duration.is_some_and(|turns| turns <= 1)),
+        }
+    }
+
+    /// Counts down durations of temporary modifiers, removing and returning
+    /// the expired ones
+    pub fn tick_modifiers(&mut self) -> Vec<AttributeModifier> {
+        let mut expired = vec![];
+        self.update_modifiers(|modifiers| {
+            for modifier in modifiers.iter_mut() {
+                if let Some(turns) = modifier.duration.as_mut() {
+                    *turns = turns.saturating_sub(1);
+                }
+            }
+            let (gone, kept) = modifiers.drain(..).partition(|m| m.duration == Some(0));
+            expired = gone;
+            *modifiers = kept;
+        });
+        expired
+    }
+
+    // Gauges keep the same missing amount when their maximum changes,
+    // so that raising max hit points also heals and lowering them back
+    // doesn't leave the unit over its maximum
+    fn update_modifiers(&mut self, f: impl FnOnce(&mut Vec<AttributeModifier>)) {
+        let old_max = self.get_max();
+        f(self.modifiers_mut());
+        let new_max = self.get_max();
+
+        if let Self::Gauge { value, .. } = self {
+            *value += new_max - old_max;
+        }
+        self.clamp_value();
+    }
+
+    fn clamp_value(&mut self) {
+        let (min, max) = (self.get_min(), self.get_max());
+        if let Self::Gauge { value, .. } = self {
+            *value = (*value).clamp(min, max);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn power_up() -> ModifierSource {
+        ModifierSource::PowerUp("test".to_string())
+    }
+
+    #[test]
+    fn value_modifiers_are_applied_on_read() {
+        let mut attack = Attribute::value(10);
+        attack.add_modifier(AttributeModifier::flat(2, power_up()));
+        attack.add_modifier(AttributeModifier::percent(50, power_up()));
+
+        assert_eq!(attack.get_base(), 10);
+        assert_eq!(attack.get_value(), 18);
+
+        attack.remove_modifiers_from(&power_up());
+        assert_eq!(attack.get_value(), 10);
+    }
+
+    #[test]
+    fn value_cant_go_below_zero() {
+        let mut defense = Attribute::value(3);
+        defense.add_modifier(AttributeModifier::flat(-5, power_up()));
+
+        assert_eq!(defense.get_value(), 0);
+    }
+
+    #[test]
+    fn gauge_max_modifier_keeps_missing_amount() {
+        let mut hp = Attribute::gauge(100);
+        hp.change_value(-30);
+        hp.add_modifier(AttributeModifier::flat(20, power_up()));
+
+        assert_eq!((hp.get_value(), hp.get_max()), (90, 120));
+
+        hp.remove_modifiers_from(&power_up());
+        assert_eq!((hp.get_value(), hp.get_max()), (70, 100));
+    }
+
+    #[test]
+    fn gauge_max_never_below_min() {
+        let mut hp = Attribute::gauge(10);
+        hp.add_modifier(AttributeModifier::flat(-20, power_up()));
+
+        assert_eq!((hp.get_value(), hp.get_max()), (0, 0));
+        assert_eq!(hp.change_value(5), 0);
+    }
+
+    #[test]
+    fn temporary_modifiers_expire() {
+        let mut attack = Attribute::value(10);
+        attack.add_modifier(AttributeModifier::flat(-4, power_up()).with_duration(2));
+
+        attack.tick_modifiers();
+        assert_eq!(attack.get_value(), 6);
+        attack.tick_modifiers();
+        assert_eq!(attack.get_value(), 10);
+        assert!(attack.modifiers().is_empty());
+    }
+
+    #[test]
+    fn attribute_defaults_respect_clamp() {
+        for typ in AttributeType::ALL {
+            let definition = typ.definition();
+            assert_eq!(
+                definition.clamp.apply(definition.default),
+                definition.default,
+                "default of {typ:?} is outside of its clamp"
+            );
+        }
+    }
+
+    #[test]
+    fn bars_only_for_gauges() {
+        for typ in AttributeType::ALL {
+            let definition = typ.definition();
+            assert!(
+                !definition.bar || definition.value_type == AttributeValueType::Gauge,
+                "{typ:?} can't have a bar"
+            );
+        }
+    }
+
+    #[test]
+    fn overrides_keep_other_defaults() {
+        let attributes = Attributes::with_overrides(&[(AttributeType::Attack, 3)]);
+
+        assert_eq!(attributes.0.len(), AttributeType::ALL.len());
+        assert_eq!(attributes.0[&AttributeType::Attack].get_value(), 3);
+        assert_eq!(
+            attributes.0[&AttributeType::Speed].get_value(),
+            AttributeType::Speed.definition().default
+        );
+    }
+
+    #[test]
+    fn value_respects_max_clamp() {
+        let mut crit = Attribute::new(AttributeType::Crit, 90);
+        crit.add_modifier(AttributeModifier::flat(30, power_up()));
+
+        assert_eq!(crit.get_value(), 100);
+    }
+
+    fn ability(caster: &str) -> ModifierSource {
+        ModifierSource::Ability {
+            name: "weaken".to_string(),
+            caster: caster.to_string(),
+        }
+    }
+
+    #[test]
+    fn refresh_doesnt_stack() {
+        let mut attack = Attribute::value(10);
+        let debuff = |turns| AttributeModifier::flat(-3, ability("a")).with_duration(turns);
+
+        attack.apply_modifier(debuff(1), Stacking::Refresh);
+        assert!(attack.can_apply(&ability("b"), Stacking::Refresh));
+
+        attack.apply_modifier(debuff(3), Stacking::Refresh);
+        assert_eq!(attack.get_value(), 7);
+        assert_eq!(attack.modifiers()[0].duration, Some(3));
+        assert!(!attack.can_apply(&ability("b"), Stacking::Refresh));
+    }
+
+    #[test]
+    fn stacking_is_limited() {
+        let mut defense = Attribute::value(10);
+        let stacking = Stacking::Stack { max: 2 };
+
+        for _ in 0..3 {
+            assert_eq!(
+                defense.can_apply(&ability("a"), stacking),
+                defense.modifiers().len() < 2
+            );
+            defense.apply_modifier(
+                AttributeModifier::flat(-2, ability("a")).with_duration(3),
+                stacking,
+            );
+        }
+
+        assert_eq!(defense.get_value(), 6);
+    }
+
+    #[test]
+    fn tick_returns_expired_modifiers() {
+        let mut attack = Attribute::value(10);
+        attack.add_modifier(AttributeModifier::flat(-3, ability("a")).with_duration(1));
+        attack.add_modifier(AttributeModifier::flat(2, power_up()));
+
+        let expired = attack.tick_modifiers();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].source, ability("a"));
+        assert_eq!(attack.get_value(), 12);
+    }
+}
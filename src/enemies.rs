@@ -3,8 +3,7 @@ use bevy::{prelude::*, utils::HashMap};
 use crate::{
     abilities::Ability,
     available_abilities::AvailableAbilities,
-    character::{Abilities, AttributeType, Character, CharacterBundle, CharacterCategory, Group},
-    GameState,
+    character::{AttributeType, Character, CharacterBundle, CharacterCategory, Group},
 };
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -36,15 +35,7 @@ pub fn get_abilities(
         .collect()
 }
 
-pub fn init_available_enemies(
-    mut commands: Commands,
-    abs: Res<AvailableAbilities>,
-    mut game_state: ResMut<GameState>,
-) {
-    let player = game_state.characters.get_mut(0).unwrap();
-
-    player.bundle.abilities = Abilities::from_arr(get_abilities(&["move", "hit"], &abs).as_ref());
-
+pub fn init_available_enemies(mut commands: Commands, abs: Res<AvailableAbilities>) {
     use AttributeType::*;
     use CharacterCategory::*;
 
@@ -57,7 +48,7 @@ pub fn init_available_enemies(
                 CharacterBundle::new(
                     "mushroom",
                     Fungus,
-                    get_abilities(&["move", "hit"], &abs).as_ref(),
+                    get_abilities(&["move", "hit", "sunder"], &abs).as_ref(),
                     &[(HitPoints, 50), (Attack, 5), (Defense, 5)],
                     Group::Enemy,
                 ),
@@ -67,7 +58,7 @@ pub fn init_available_enemies(
                 CharacterBundle::new(
                     "wideshroom",
                     Fungus,
-                    get_abilities(&["move", "slam"], &abs).as_ref(),
+                    get_abilities(&["move", "slam", "harden"], &abs).as_ref(),
                     &[(HitPoints, 60), (Attack, 3), (Defense, 7)],
                     Group::Enemy,
                 ),
@@ -77,7 +68,7 @@ pub fn init_available_enemies(
                 CharacterBundle::new(
                     "purpleshroom",
                     Fungus,
-                    get_abilities(&["move", "shoot"], &abs).as_ref(),
+                    get_abilities(&["move", "shoot", "weaken"], &abs).as_ref(),
                     &[(HitPoints, 30), (Attack, 7), (Defense, 3)],
                     Group::Enemy,
                 ),
@@ -87,7 +78,7 @@ pub fn init_available_enemies(
                 CharacterBundle::new(
                     "manyshroom",
                     Fungus,
-                    get_abilities(&["move", "hit"], &abs).as_ref(),
+                    get_abilities(&["move", "hit", "spores"], &abs).as_ref(),
                     &[(HitPoints, 60), (Attack, 5), (Defense, 4)],
                     Group::Enemy,
                 ),
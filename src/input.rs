@@ -0,0 +1,637 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    ui::UiSystem,
+    utils::{HashMap, HashSet},
+    window::CursorMoved,
+};
+use fluent::fluent_args;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    battle::BattleState,
+    locale::Locale,
+    settings::{SettingsButton, SettingsState},
+};
+
+/// How far the stick has to be pushed to count as a direction
+const STICK_THRESHOLD: f32 = 0.5;
+
+pub struct InputMapPlugin;
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputMap>()
+            .init_resource::<StickDirections>()
+            .init_resource::<ButtonFocus>()
+            .add_system(
+                update_stick_directions
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(UiSystem::Focus),
+            )
+            .add_system(
+                navigate_buttons
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(update_stick_directions),
+            );
+    }
+}
+
+/// Everything the player can do with a key or gamepad button press
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    /// Presses the n-th button of the current button row
+    Hotkey(usize),
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    NextTarget,
+    PreviousTarget,
+    Confirm,
+    Cancel,
+    Undo,
+    ToggleDamageBreakdown,
+    CycleAnimationSpeed,
+    /// Moves the battle camera for as long as it's held
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+}
+
+impl InputAction {
+    /// Actions whose keys can be changed in the settings, hotkeys stay on
+    /// the number keys
+    pub const REBINDABLE: &'static [InputAction] = &[
+        Self::CursorUp,
+        Self::CursorDown,
+        Self::CursorLeft,
+        Self::CursorRight,
+        Self::NextTarget,
+        Self::PreviousTarget,
+        Self::Confirm,
+        Self::Cancel,
+        Self::Undo,
+        Self::ToggleDamageBreakdown,
+        Self::CycleAnimationSpeed,
+        Self::PanUp,
+        Self::PanDown,
+        Self::PanLeft,
+        Self::PanRight,
+    ];
+}
+
+impl InputAction {
+    pub fn name(&self, locale: &Locale) -> String {
+        let id = match self {
+            Self::Hotkey(i) => {
+                return locale.format("action-hotkey", &fluent_args!["number" => i + 1]);
+            }
+            Self::CursorUp => "action-cursor-up",
+            Self::CursorDown => "action-cursor-down",
+            Self::CursorLeft => "action-cursor-left",
+            Self::CursorRight => "action-cursor-right",
+            Self::NextTarget => "action-next-target",
+            Self::PreviousTarget => "action-previous-target",
+            Self::Confirm => "action-confirm",
+            Self::Cancel => "action-cancel",
+            Self::Undo => "action-undo",
+            Self::ToggleDamageBreakdown => "action-damage-breakdown",
+            Self::CycleAnimationSpeed => "action-animation-speed",
+            Self::PanUp => "action-pan-up",
+            Self::PanDown => "action-pan-down",
+            Self::PanLeft => "action-pan-left",
+            Self::PanRight => "action-pan-right",
+        };
+        locale.get(id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+}
+
+impl KeyBinding {
+    pub const fn key(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+        }
+    }
+
+    pub const fn ctrl(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: true,
+            shift: false,
+        }
+    }
+
+    pub const fn shift(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: true,
+        }
+    }
+
+    /// Modifiers have to match exactly, so that Tab and Shift+Tab can be
+    /// bound to different actions
+    fn just_pressed(&self, keys: &Input<KeyCode>) -> bool {
+        keys.just_pressed(self.key) && self.modifiers_match(keys)
+    }
+
+    fn pressed(&self, keys: &Input<KeyCode>) -> bool {
+        keys.pressed(self.key) && self.modifiers_match(keys)
+    }
+
+    fn modifiers_match(&self, keys: &Input<KeyCode>) -> bool {
+        self.ctrl == keys.any_pressed([KeyCode::LControl, KeyCode::RControl])
+            && self.shift == keys.any_pressed([KeyCode::LShift, KeyCode::RShift])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StickDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl StickDirection {
+    fn from_stick(x: f32, y: f32) -> Option<Self> {
+        if x.abs().max(y.abs()) < STICK_THRESHOLD {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0.0 { Self::Right } else { Self::Left })
+        } else {
+            Some(if y > 0.0 { Self::Up } else { Self::Down })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadBinding {
+    Button(GamepadButtonType),
+    /// The left stick pushed in a direction, triggers once per push
+    LeftStick(StickDirection),
+}
+
+pub const HOTKEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// Key and gamepad bindings of all input actions
+#[derive(Resource, Debug, Clone)]
+pub struct InputMap {
+    pub keys: HashMap<InputAction, Vec<KeyBinding>>,
+    pub gamepad: HashMap<InputAction, Vec<GamepadBinding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let mut keys = HashMap::from_iter([
+            (InputAction::CursorUp, vec![KeyBinding::key(KeyCode::Up)]),
+            (
+                InputAction::CursorDown,
+                vec![KeyBinding::key(KeyCode::Down)],
+            ),
+            (
+                InputAction::CursorLeft,
+                vec![KeyBinding::key(KeyCode::Left)],
+            ),
+            (
+                InputAction::CursorRight,
+                vec![KeyBinding::key(KeyCode::Right)],
+            ),
+            (InputAction::NextTarget, vec![KeyBinding::key(KeyCode::Tab)]),
+            (
+                InputAction::PreviousTarget,
+                vec![KeyBinding::shift(KeyCode::Tab)],
+            ),
+            (
+                InputAction::Confirm,
+                vec![
+                    KeyBinding::key(KeyCode::Return),
+                    KeyBinding::key(KeyCode::NumpadEnter),
+                ],
+            ),
+            (InputAction::Cancel, vec![KeyBinding::key(KeyCode::Escape)]),
+            (InputAction::Undo, vec![KeyBinding::ctrl(KeyCode::Z)]),
+            (
+                InputAction::ToggleDamageBreakdown,
+                vec![KeyBinding::key(KeyCode::L)],
+            ),
+            (
+                InputAction::CycleAnimationSpeed,
+                vec![KeyBinding::key(KeyCode::F)],
+            ),
+            (InputAction::PanUp, vec![KeyBinding::key(KeyCode::W)]),
+            (InputAction::PanDown, vec![KeyBinding::key(KeyCode::S)]),
+            (InputAction::PanLeft, vec![KeyBinding::key(KeyCode::A)]),
+            (InputAction::PanRight, vec![KeyBinding::key(KeyCode::D)]),
+        ]);
+        keys.extend(
+            HOTKEYS
+                .iter()
+                .enumerate()
+                .map(|(i, key)| (InputAction::Hotkey(i), vec![KeyBinding::key(*key)])),
+        );
+
+        let direction = |button, direction| {
+            vec![
+                GamepadBinding::Button(button),
+                GamepadBinding::LeftStick(direction),
+            ]
+        };
+        let gamepad = HashMap::from_iter([
+            (
+                InputAction::CursorUp,
+                direction(GamepadButtonType::DPadUp, StickDirection::Up),
+            ),
+            (
+                InputAction::CursorDown,
+                direction(GamepadButtonType::DPadDown, StickDirection::Down),
+            ),
+            (
+                InputAction::CursorLeft,
+                direction(GamepadButtonType::DPadLeft, StickDirection::Left),
+            ),
+            (
+                InputAction::CursorRight,
+                direction(GamepadButtonType::DPadRight, StickDirection::Right),
+            ),
+            (
+                InputAction::NextTarget,
+                vec![GamepadBinding::Button(GamepadButtonType::RightTrigger)],
+            ),
+            (
+                InputAction::PreviousTarget,
+                vec![GamepadBinding::Button(GamepadButtonType::LeftTrigger)],
+            ),
+            (
+                InputAction::Confirm,
+                vec![GamepadBinding::Button(GamepadButtonType::South)],
+            ),
+            (
+                InputAction::Cancel,
+                vec![GamepadBinding::Button(GamepadButtonType::East)],
+            ),
+            (
+                InputAction::Undo,
+                vec![GamepadBinding::Button(GamepadButtonType::West)],
+            ),
+            (
+                InputAction::ToggleDamageBreakdown,
+                vec![GamepadBinding::Button(GamepadButtonType::Select)],
+            ),
+            (
+                InputAction::CycleAnimationSpeed,
+                vec![GamepadBinding::Button(GamepadButtonType::North)],
+            ),
+        ]);
+
+        Self { keys, gamepad }
+    }
+}
+
+impl InputMap {
+    /// First key of the action, for the hints in the UI
+    pub fn hint(&self, action: InputAction) -> String {
+        let bindings = self.keys.get(&action).map_or(&[][..], Vec::as_slice);
+        describe_bindings(&bindings[..bindings.len().min(1)])
+    }
+}
+
+pub fn describe_bindings(bindings: &[KeyBinding]) -> String {
+    if bindings.is_empty() {
+        return "-".to_string();
+    }
+    bindings
+        .iter()
+        .map(|binding| {
+            let mut text = String::new();
+            if binding.ctrl {
+                text.push_str("Ctrl+");
+            }
+            if binding.shift {
+                text.push_str("Shift+");
+            }
+            text.push_str(&format!("{:?}", binding.key));
+            text
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Left stick directions of all gamepads, tracked so that holding the stick
+/// only triggers an action once
+#[derive(Resource, Default)]
+pub struct StickDirections {
+    held: HashMap<Gamepad, StickDirection>,
+    just_pushed: HashSet<StickDirection>,
+}
+
+fn update_stick_directions(
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut sticks: ResMut<StickDirections>,
+) {
+    sticks.just_pushed.clear();
+
+    for gamepad in gamepads.iter() {
+        let axis = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        let direction = StickDirection::from_stick(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+
+        match direction {
+            Some(direction) => {
+                if sticks.held.insert(gamepad, direction) != Some(direction) {
+                    sticks.just_pushed.insert(direction);
+                }
+            }
+            None => {
+                sticks.held.remove(&gamepad);
+            }
+        }
+    }
+}
+
+/// Input actions triggered this frame by the keyboard or any gamepad
+#[derive(SystemParam)]
+pub struct Actions<'w> {
+    input_map: Res<'w, InputMap>,
+    keys: Res<'w, Input<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    sticks: Res<'w, StickDirections>,
+    settings_state: Res<'w, State<SettingsState>>,
+}
+
+impl Actions<'_> {
+    /// Whether an overlay like the settings screen takes all input
+    pub fn is_blocked(&self) -> bool {
+        self.settings_state.0 == SettingsState::Open
+    }
+
+    /// Whether the action was triggered this frame on the current screen,
+    /// nothing reaches it while an overlay is open
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        !self.is_blocked() && self.triggered(action)
+    }
+
+    /// Whether the action was triggered this frame, even behind an overlay
+    pub fn triggered(&self, action: InputAction) -> bool {
+        let key_pressed = self.input_map.keys.get(&action).is_some_and(|bindings| {
+            bindings
+                .iter()
+                .any(|binding| binding.just_pressed(&self.keys))
+        });
+
+        key_pressed
+            || self.input_map.gamepad.get(&action).is_some_and(|bindings| {
+                bindings.iter().any(|binding| match binding {
+                    GamepadBinding::Button(button_type) => self.gamepads.iter().any(|gamepad| {
+                        self.gamepad_buttons
+                            .just_pressed(GamepadButton::new(gamepad, *button_type))
+                    }),
+                    GamepadBinding::LeftStick(direction) => {
+                        self.sticks.just_pushed.contains(direction)
+                    }
+                })
+            })
+    }
+
+    /// Whether the action is held down on the current screen, for actions
+    /// that last as long as their key
+    pub fn pressed(&self, action: InputAction) -> bool {
+        if self.is_blocked() {
+            return false;
+        }
+
+        let key_held = self
+            .input_map
+            .keys
+            .get(&action)
+            .is_some_and(|bindings| bindings.iter().any(|binding| binding.pressed(&self.keys)));
+
+        key_held
+            || self.input_map.gamepad.get(&action).is_some_and(|bindings| {
+                bindings.iter().any(|binding| match binding {
+                    GamepadBinding::Button(button_type) => self.gamepads.iter().any(|gamepad| {
+                        self.gamepad_buttons
+                            .pressed(GamepadButton::new(gamepad, *button_type))
+                    }),
+                    GamepadBinding::LeftStick(direction) => {
+                        self.sticks.held.values().any(|held| held == direction)
+                    }
+                })
+            })
+    }
+
+    /// Index of the hotkey pressed this frame
+    pub fn hotkey(&self) -> Option<usize> {
+        (0..HOTKEYS.len()).find(|i| self.just_pressed(InputAction::Hotkey(*i)))
+    }
+}
+
+/// Button selected without the mouse, shown and pressed through its
+/// `Interaction` so that every screen's button systems handle it as if it
+/// was hovered or clicked with the mouse
+#[derive(Resource, Default)]
+pub struct ButtonFocus {
+    focused: Option<Entity>,
+    /// Button clicked last frame, released again this frame
+    clicked: Option<Entity>,
+}
+
+/// Picks the closest button in the direction of `step`, in UI coordinates
+fn next_button(from: Vec2, step: Vec2, buttons: &[(Entity, Vec2)]) -> Option<Entity> {
+    buttons
+        .iter()
+        .filter_map(|(entity, pos)| {
+            let offset = *pos - from;
+            let along = offset.dot(step);
+            let across = offset.perp_dot(step).abs();
+            // ignore buttons behind or too far to the side
+            (along > 0.0 && across <= along * 2.0).then_some((*entity, along + across * 2.0))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+fn navigate_buttons(
+    actions: Actions,
+    battle_state: Res<State<BattleState>>,
+    mut focus: ResMut<ButtonFocus>,
+    mut ev_cursor: EventReader<CursorMoved>,
+    mut button_query: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &ComputedVisibility,
+            &mut Interaction,
+            Option<&SettingsButton>,
+        ),
+        With<Button>,
+    >,
+) {
+    // an open overlay keeps the focus on its own buttons
+    let blocked = actions.is_blocked();
+    let navigates = blocked || battle_state.0 != BattleState::AbilityTargeting;
+
+    if let Some(clicked) = focus.clicked.take() {
+        if let Ok((_, _, _, mut interaction, _)) = button_query.get_mut(clicked) {
+            *interaction = Interaction::Hovered;
+        }
+    }
+
+    // the mouse takes over again as soon as it moves
+    if ev_cursor.iter().count() > 0 {
+        focus.focused = None;
+        return;
+    }
+
+    let buttons = button_query
+        .iter()
+        .filter(|(.., visibility, _, overlay)| {
+            visibility.is_visible() && (!blocked || overlay.is_some())
+        })
+        .map(|(entity, transform, ..)| (entity, transform.translation().truncate()))
+        .collect::<Vec<_>>();
+    let current = focus
+        .focused
+        .and_then(|focused| buttons.iter().find(|(entity, _)| *entity == focused));
+
+    // while targeting, the directions move the hex cursor instead
+    if navigates {
+        let step = [
+            (InputAction::CursorUp, Vec2::NEG_Y),
+            (InputAction::CursorDown, Vec2::Y),
+            (InputAction::CursorLeft, Vec2::NEG_X),
+            (InputAction::CursorRight, Vec2::X),
+        ]
+        .into_iter()
+        .find(|(action, _)| actions.triggered(*action))
+        .map(|(_, step)| step);
+
+        if let Some(step) = step {
+            let next = match current {
+                Some((_, pos)) => next_button(*pos, step, &buttons),
+                // start at the top left button
+                None => buttons
+                    .iter()
+                    .min_by(|a, b| (a.1.y, a.1.x).partial_cmp(&(b.1.y, b.1.x)).unwrap())
+                    .map(|(entity, _)| *entity),
+            };
+
+            if let Some(next) = next {
+                if let Some(previous) = focus.focused.replace(next) {
+                    if let Ok((_, _, _, mut interaction, _)) = button_query.get_mut(previous) {
+                        interaction.set_if_neq(Interaction::None);
+                    }
+                }
+            }
+        }
+    }
+
+    let Some(focused) = focus.focused else {
+        return;
+    };
+    let Ok((_, _, _, mut interaction, _)) = button_query.get_mut(focused) else {
+        focus.focused = None;
+        return;
+    };
+
+    if navigates && actions.triggered(InputAction::Confirm) {
+        *interaction = Interaction::Clicked;
+        focus.clicked = Some(focused);
+    } else {
+        interaction.set_if_neq(Interaction::Hovered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_show_the_first_key() {
+        let mut input_map = InputMap::default();
+        assert_eq!(input_map.hint(InputAction::Undo), "Ctrl+Z");
+        assert_eq!(input_map.hint(InputAction::Confirm), "Return");
+
+        input_map.keys.remove(&InputAction::Undo);
+        assert_eq!(input_map.hint(InputAction::Undo), "-");
+    }
+
+    #[test]
+    fn modifiers_have_to_match() {
+        let mut keys = Input::<KeyCode>::default();
+        keys.press(KeyCode::LShift);
+        keys.press(KeyCode::Tab);
+
+        assert!(KeyBinding::shift(KeyCode::Tab).just_pressed(&keys));
+        assert!(!KeyBinding::key(KeyCode::Tab).just_pressed(&keys));
+        assert!(!KeyBinding::ctrl(KeyCode::Tab).just_pressed(&keys));
+    }
+
+    #[test]
+    fn every_action_is_bound() {
+        let input_map = InputMap::default();
+        assert_eq!(input_map.keys.len(), 15 + HOTKEYS.len());
+        assert!(input_map.keys.values().all(|bindings| !bindings.is_empty()));
+        assert!(input_map
+            .gamepad
+            .values()
+            .all(|bindings| !bindings.is_empty()));
+    }
+
+    #[test]
+    fn stick_directions_need_a_push() {
+        assert_eq!(StickDirection::from_stick(0.2, -0.3), None);
+        assert_eq!(
+            StickDirection::from_stick(0.9, -0.3),
+            Some(StickDirection::Right)
+        );
+        assert_eq!(
+            StickDirection::from_stick(0.4, -0.7),
+            Some(StickDirection::Down)
+        );
+    }
+
+    #[test]
+    fn navigates_to_the_closest_button_in_a_direction() {
+        let a = Entity::from_raw(0);
+        let b = Entity::from_raw(1);
+        let c = Entity::from_raw(2);
+        let buttons = [
+            (a, Vec2::new(100.0, 100.0)),
+            (b, Vec2::new(100.0, 200.0)),
+            (c, Vec2::new(300.0, 110.0)),
+        ];
+
+        assert_eq!(next_button(buttons[0].1, Vec2::Y, &buttons), Some(b));
+        assert_eq!(next_button(buttons[0].1, Vec2::X, &buttons), Some(c));
+        assert_eq!(next_button(buttons[0].1, Vec2::NEG_X, &buttons), None);
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+use fluent::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use serde::{Deserialize, Serialize};
+use unic_langid::LanguageIdentifier;
+
+use crate::settings::Settings;
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_locale);
+    }
+}
+
+/// Languages the game is translated to, every one has a Fluent file under
+/// `assets/locale/`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Self::English, Self::German];
+
+    fn id(self) -> LanguageIdentifier {
+        match self {
+            Self::English => "en-US",
+            Self::German => "de-DE",
+        }
+        .parse()
+        .expect("Invalid language identifier")
+    }
+
+    /// Messages of the language, embedded so that they are there before
+    /// the first frame on every platform
+    fn source(self) -> &'static str {
+        match self {
+            Self::English => include_str!("../assets/locale/en-US/main.ftl"),
+            Self::German => include_str!("../assets/locale/de-DE/main.ftl"),
+        }
+    }
+}
+
+/// Languages are always named in themselves, so that they can be found
+/// whatever the current language
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::English => write!(f, "English"),
+            Self::German => write!(f, "Deutsch"),
+        }
+    }
+}
+
+fn bundle(language: Language) -> FluentBundle<FluentResource> {
+    let resource = FluentResource::try_new(language.source().to_string())
+        .unwrap_or_else(|(_, errors)| panic!("Invalid messages for {language:?}: {errors:?}"));
+
+    let mut bundle = FluentBundle::new_concurrent(vec![language.id()]);
+    // the font has no glyphs for the bidi isolation marks
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .unwrap_or_else(|errors| panic!("Duplicate messages for {language:?}: {errors:?}"));
+    bundle
+}
+
+/// Messages of the current language, missing ones fall back to English
+#[derive(Resource)]
+pub struct Locale {
+    language: Language,
+    bundle: FluentBundle<FluentResource>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+impl Locale {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            bundle: bundle(language),
+            fallback: bundle(Language::English),
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn has(&self, id: &str) -> bool {
+        self.bundle.has_message(id) || self.fallback.has_message(id)
+    }
+
+    pub fn get(&self, id: &str) -> String {
+        self.format(id, &FluentArgs::new())
+    }
+
+    /// Message with the arguments filled in, the id itself if there is no
+    /// such message
+    pub fn format(&self, id: &str, args: &FluentArgs) -> String {
+        let Some((bundle, message)) = [&self.bundle, &self.fallback]
+            .into_iter()
+            .find_map(|bundle| Some((bundle, bundle.get_message(id)?)))
+        else {
+            warn!("Missing message {id}");
+            return id.to_string();
+        };
+        let Some(pattern) = message.value() else {
+            return id.to_string();
+        };
+
+        let mut errors = vec![];
+        let text = bundle.format_pattern(pattern, Some(args), &mut errors);
+        if !errors.is_empty() {
+            warn!("Couldn't format message {id}: {errors:?}");
+        }
+        text.into_owned()
+    }
+
+    /// Translated name of a data entry such as an ability, looked up as
+    /// `{kind}-{id}`. Entries without a translation keep their id.
+    fn name(&self, kind: &str, id: &str) -> String {
+        let key = format!("{kind}-{id}");
+        if self.has(&key) {
+            self.get(&key)
+        } else {
+            id.to_string()
+        }
+    }
+
+    pub fn ability_name(&self, id: &str) -> String {
+        self.name("ability", id)
+    }
+
+    pub fn character_name(&self, id: &str) -> String {
+        self.name("character", id)
+    }
+
+    pub fn power_up_name(&self, id: &str) -> String {
+        self.name("power-up", id)
+    }
+}
+
+fn update_locale(settings: Res<Settings>, mut locale: ResMut<Locale>) {
+    if settings.language != locale.language {
+        *locale = Locale::new(settings.language);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fluent::fluent_args;
+
+    use super::*;
+
+    fn message_ids(language: Language) -> Vec<String> {
+        language
+            .source()
+            .lines()
+            .filter_map(|line| {
+                let (id, _) = line.split_once(" =")?;
+                (!id.is_empty() && !id.starts_with([' ', '#', '-'])).then(|| id.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_language_has_every_message() {
+        let english = message_ids(Language::English);
+        for language in Language::ALL {
+            let locale = Locale::new(language);
+            for id in &english {
+                assert!(locale.bundle.has_message(id), "{language:?} lacks {id}");
+            }
+            assert_eq!(message_ids(language).len(), english.len());
+        }
+    }
+
+    #[test]
+    fn plurals_follow_the_count() {
+        let locale = Locale::new(Language::English);
+
+        assert_eq!(
+            locale.format("modifier-turns", &fluent_args!["turns" => 1]),
+            "(1 turn)"
+        );
+        assert_eq!(
+            locale.format("modifier-turns", &fluent_args!["turns" => 3]),
+            "(3 turns)"
+        );
+    }
+
+    #[test]
+    fn missing_names_keep_their_id() {
+        let locale = Locale::new(Language::German);
+
+        assert_eq!(locale.ability_name("hit"), "Schlag");
+        assert_eq!(locale.ability_name("no-such-ability"), "no-such-ability");
+    }
+}
@@ -5,41 +5,47 @@
 #![allow(clippy::too_many_arguments, clippy::type_complexity)]
 
 mod abilities;
+mod audio;
 mod available_abilities;
 mod available_power_ups;
 mod battle;
 mod character;
 mod enemies;
+mod input;
+mod locale;
 mod main_menu;
+mod run;
+mod settings;
+mod theme;
 mod utils;
 
 use abilities::AbilityPlugin;
+use audio::GameAudioPlugin;
 use available_abilities::init_available_abilities;
 use available_power_ups::init_available_power_ups;
-use battle::{battle_field::BattleFieldLayout, BattlePlugin};
+use battle::{battle_field::BattleFieldLayout, stats::RunStats, BattlePlugin};
 use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
 use bevy_prototype_lyon::prelude::*;
-use character::{
-    Abilities, Attributes, Character, CharacterBundle, CharacterCategory, CharacterName, Group,
-};
+use character::Character;
 use enemies::init_available_enemies;
+use input::InputMapPlugin;
+use locale::{Locale, LocalePlugin};
 use main_menu::MainMenuPlugin;
-use utils::hex::Hex;
-
-pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-pub const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
-
-pub const WINDOW_WIDTH: f32 = 1280.0;
-pub const WINDOW_HEIGHT: f32 = 720.0;
+use run::{Difficulty, RunPlugin, StartingCharacter};
+use settings::{Settings, SettingsPlugin};
+use theme::{Theme, ThemePlugin};
 
 fn main() {
+    let settings = Settings::load();
+
     App::new()
-        .insert_resource(Msaa::Sample4)
+        .insert_resource(settings.msaa())
         .init_resource::<GameState>()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Counterbalance".into(),
-                resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
+                resolution: (settings.resolution.0 as f32, settings.resolution.1 as f32).into(),
+                mode: settings.window_mode(),
                 // Tells wasm to resize the window according to the available canvas
                 fit_canvas_to_parent: true,
                 // Tells wasm not to override default event handling, like F5, Ctrl+R etc.
@@ -56,9 +62,18 @@ fn main() {
                 .in_schedule(OnEnter(InitState::AfterAbilities)),
         )
         .add_plugin(ShapePlugin)
+        .add_plugin(InputMapPlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(LocalePlugin)
+        .add_plugin(ThemePlugin)
+        .add_plugin(RunPlugin)
         .add_plugin(MainMenuPlugin)
         .add_plugin(BattlePlugin)
         .add_plugin(AbilityPlugin)
+        .add_plugin(GameAudioPlugin)
+        .insert_resource(Locale::new(settings.language))
+        .insert_resource(Theme::new(settings.palette))
+        .insert_resource(settings)
         .run();
 }
 
@@ -85,33 +100,25 @@ pub struct GameState {
     characters: Vec<Character>,
     battle_field_layout: BattleFieldLayout,
     round: i32,
+    seed: u64,
+    difficulty: Difficulty,
+    starting_character: StartingCharacter,
+    /// Names of the power ups picked so far
+    power_ups: Vec<String>,
+    run_stats: RunStats,
 }
 
 impl Default for GameState {
     fn default() -> Self {
         GameState {
-            characters: vec![Character {
-                bundle: CharacterBundle {
-                    name: CharacterName("player".to_string()),
-                    category: CharacterCategory::Human,
-                    abilities: Abilities::default(),
-                    attributes: Attributes::default(),
-                    group: Group::Player,
-                },
-                image_path: "images/kitty.png".to_string(),
-            }],
-            battle_field_layout: BattleFieldLayout {
-                size: (12, 7).into(),
-                player_start: [(0, 3), (0, 0), (0, 6)]
-                    .into_iter()
-                    .map(|p| Hex::from_oddr(p.into()))
-                    .collect(),
-                enemy_start: [(10, 2), (10, 4), (11, 3), (11, 1), (11, 5)]
-                    .into_iter()
-                    .map(|p| Hex::from_oddr(p.into()))
-                    .collect(),
-            },
+            characters: vec![],
+            battle_field_layout: BattleFieldLayout::default(),
             round: 1,
+            seed: rand::random(),
+            difficulty: Difficulty::default(),
+            starting_character: StartingCharacter::default(),
+            power_ups: vec![],
+            run_stats: RunStats::default(),
         }
     }
 }
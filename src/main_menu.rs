@@ -1,29 +1,216 @@
-use crate::{AppState, HOVERED_BUTTON, NORMAL_BUTTON};
+use crate::{
+    available_abilities::AvailableAbilities,
+    available_power_ups::AvailablePowerUps,
+    character::{AttributeType, Character},
+    enemies::AvailableEnemies,
+    input::{Actions, InputAction},
+    locale::Locale,
+    run::{Difficulty, RunOptions, SavedRun, StartingCharacter, MAX_SEED_LENGTH},
+    settings::SettingsState,
+    theme::Theme,
+    AppState, GameState,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::app::AppExit;
 use bevy::prelude::*;
+use fluent::fluent_args;
 
 pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(setup_menu.in_schedule(OnEnter(AppState::MainMenu)))
+        app.init_resource::<MenuScreen>()
+            .add_system(setup_menu.in_schedule(OnEnter(AppState::MainMenu)))
             .add_system(cleanup_menu.in_schedule(OnExit(AppState::MainMenu)))
-            .add_system(main_menu_button_interaction.in_set(OnUpdate(AppState::MainMenu)));
+            .add_systems(
+                (
+                    spawn_menu_screen,
+                    main_menu_button_interaction,
+                    type_seed,
+                    leave_menu_screen,
+                    update_new_run_text
+                        .after(main_menu_button_interaction)
+                        .after(type_seed),
+                )
+                    .in_set(OnUpdate(AppState::MainMenu)),
+            );
     }
 }
 
+/// Page of the main menu currently shown
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MenuScreen {
+    #[default]
+    Title,
+    NewRun,
+    Compendium,
+}
+
 #[derive(Component)]
 pub struct MainMenu;
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy)]
 pub enum MainMenuButton {
-    StartGame,
+    Continue,
+    NewRun,
+    Settings,
+    Compendium,
+    #[cfg(not(target_arch = "wasm32"))]
+    Quit,
+    ChangeDifficulty,
+    ChangeCharacter,
+    RandomSeed,
+    StartRun,
+    Back,
+}
+
+/// Texts of the new run screen showing the chosen options
+#[derive(Component)]
+pub enum NewRunText {
+    Seed,
+    Difficulty,
+    Character,
+}
+
+/// Option after `current`, going back to the first after the last
+fn next_option<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+    let index = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0);
+    options[(index + 1) % options.len()]
+}
+
+fn describe_new_run_text(text: &NewRunText, options: &RunOptions, locale: &Locale) -> String {
+    match text {
+        NewRunText::Seed if options.seed.is_empty() => locale.get("menu-seed-random"),
+        NewRunText::Seed => {
+            locale.format("menu-seed", &fluent_args!["seed" => options.seed.as_str()])
+        }
+        NewRunText::Difficulty => locale.format(
+            "menu-difficulty",
+            &fluent_args!["difficulty" => options.difficulty.name(locale)],
+        ),
+        NewRunText::Character => locale.format(
+            "menu-character",
+            &fluent_args!["character" => options.character.name(locale)],
+        ),
+    }
+}
+
+fn describe_enemy(enemy: &Character, locale: &Locale) -> String {
+    let attributes = [
+        AttributeType::HitPoints,
+        AttributeType::Attack,
+        AttributeType::Defense,
+        AttributeType::Speed,
+    ]
+    .into_iter()
+    .filter_map(|typ| {
+        let attribute = enemy.bundle.attributes.0.get(&typ)?;
+        Some(format!("{} {}", typ.name(locale), attribute.get_base()))
+    })
+    .collect::<Vec<_>>();
+
+    let mut abilities = enemy
+        .bundle
+        .abilities
+        .0
+        .keys()
+        .map(|ability| locale.ability_name(ability))
+        .collect::<Vec<_>>();
+    abilities.sort();
+
+    format!(
+        "{}\n{}\n{}",
+        locale.character_name(&enemy.bundle.name.0),
+        attributes.join(", "),
+        locale.format(
+            "compendium-abilities",
+            &fluent_args!["abilities" => abilities.join(", ")]
+        )
+    )
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    theme: &Theme,
+    button: MainMenuButton,
+    label: &str,
+    text: Option<NewRunText>,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(400.0), Val::Px(65.0)),
+                margin: UiRect::all(Val::Px(10.0)),
+                // horizontally center child text
+                justify_content: JustifyContent::Center,
+                // vertically center child text
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: theme.normal_button.into(),
+            ..default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            let mut label = parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 40.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ));
+            if let Some(text) = text {
+                label.insert(text);
+            }
+        });
+}
+
+pub fn setup_menu(mut screen: ResMut<MenuScreen>) {
+    *screen = MenuScreen::Title;
 }
 
-pub fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// Builds the current page of the menu again whenever it changes
+pub fn spawn_menu_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    screen: Res<MenuScreen>,
+    saved_run: Res<SavedRun>,
+    options: Res<RunOptions>,
+    locale: Res<Locale>,
+    theme: Res<Theme>,
+    enemies: Option<Res<AvailableEnemies>>,
+    power_ups: Option<Res<AvailablePowerUps>>,
+    menu_query: Query<Entity, With<MainMenu>>,
+) {
+    if !screen.is_changed()
+        && !saved_run.is_changed()
+        && !locale.is_changed()
+        && !theme.is_changed()
+        && !menu_query.is_empty()
+    {
+        return;
+    }
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Medium.ttf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 20.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
     commands
         .spawn(NodeBundle {
             style: Style {
                 size: Size::width(Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..default()
@@ -31,32 +218,213 @@ pub fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         })
         .insert(MainMenu)
-        .with_children(|parent| {
-            // main window
-            parent
-                .spawn(ButtonBundle {
-                    style: Style {
-                        size: Size::new(Val::Px(250.0), Val::Px(65.0)),
-                        // horizontally center child text
-                        justify_content: JustifyContent::Center,
-                        // vertically center child text
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    background_color: NORMAL_BUTTON.into(),
-                    ..default()
-                })
-                .insert(MainMenuButton::StartGame)
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Start Game",
+        .with_children(|parent| match *screen {
+            MenuScreen::Title => {
+                if saved_run.0.is_some() {
+                    spawn_button(
+                        parent,
+                        &font,
+                        &theme,
+                        MainMenuButton::Continue,
+                        &locale.get("menu-continue"),
+                        None,
+                    );
+                }
+                spawn_button(
+                    parent,
+                    &font,
+                    &theme,
+                    MainMenuButton::NewRun,
+                    &locale.get("menu-new-run"),
+                    None,
+                );
+                spawn_button(
+                    parent,
+                    &font,
+                    &theme,
+                    MainMenuButton::Settings,
+                    &locale.get("menu-settings"),
+                    None,
+                );
+                spawn_button(
+                    parent,
+                    &font,
+                    &theme,
+                    MainMenuButton::Compendium,
+                    &locale.get("menu-compendium"),
+                    None,
+                );
+                #[cfg(not(target_arch = "wasm32"))]
+                spawn_button(
+                    parent,
+                    &font,
+                    &theme,
+                    MainMenuButton::Quit,
+                    &locale.get("menu-quit"),
+                    None,
+                );
+            }
+            MenuScreen::NewRun => {
+                parent.spawn((
+                    TextBundle::from_section(
+                        describe_new_run_text(&NewRunText::Seed, &options, &locale),
                         TextStyle {
-                            font: asset_server.load("fonts/FiraSans-Medium.ttf"),
                             font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..text_style.clone()
+                        },
+                    ),
+                    NewRunText::Seed,
+                ));
+                parent.spawn(TextBundle::from_section(
+                    locale.get("menu-seed-hint"),
+                    text_style.clone(),
+                ));
+                spawn_button(
+                    parent,
+                    &font,
+                    &theme,
+                    MainMenuButton::RandomSeed,
+                    &locale.get("menu-random-seed"),
+                    None,
+                );
+                for text in [NewRunText::Difficulty, NewRunText::Character] {
+                    let button = match text {
+                        NewRunText::Difficulty => MainMenuButton::ChangeDifficulty,
+                        _ => MainMenuButton::ChangeCharacter,
+                    };
+                    let label = describe_new_run_text(&text, &options, &locale);
+                    spawn_button(parent, &font, &theme, button, &label, Some(text));
+                }
+                spawn_button(
+                    parent,
+                    &font,
+                    &theme,
+                    MainMenuButton::StartRun,
+                    &locale.get("menu-start"),
+                    None,
+                );
+                spawn_button(
+                    parent,
+                    &font,
+                    &theme,
+                    MainMenuButton::Back,
+                    &locale.get("menu-back"),
+                    None,
+                );
+            }
+            MenuScreen::Compendium => {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            justify_content: JustifyContent::SpaceEvenly,
+                            size: Size::width(Val::Percent(80.0)),
+                            ..default()
                         },
-                    ));
-                });
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    locale.get("compendium-enemies"),
+                                    TextStyle {
+                                        font_size: 40.0,
+                                        ..text_style.clone()
+                                    },
+                                ));
+                                let mut enemies = enemies
+                                    .iter()
+                                    .flat_map(|enemies| enemies.0.values().flatten())
+                                    .collect::<Vec<_>>();
+                                enemies.sort_by_key(|enemy| &enemy.bundle.name.0);
+                                for enemy in enemies {
+                                    parent
+                                        .spawn(NodeBundle {
+                                            style: Style {
+                                                align_items: AlignItems::Center,
+                                                margin: UiRect::vertical(Val::Px(5.0)),
+                                                ..default()
+                                            },
+                                            ..default()
+                                        })
+                                        .with_children(|parent| {
+                                            parent.spawn(ImageBundle {
+                                                style: Style {
+                                                    size: Size::all(Val::Px(48.0)),
+                                                    margin: UiRect::right(Val::Px(10.0)),
+                                                    ..default()
+                                                },
+                                                image: UiImage::new(
+                                                    asset_server.load(enemy.image_path.as_str()),
+                                                ),
+                                                ..default()
+                                            });
+                                            parent.spawn(TextBundle::from_section(
+                                                describe_enemy(enemy, &locale),
+                                                text_style.clone(),
+                                            ));
+                                        });
+                                }
+                            });
+
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    locale.get("compendium-power-ups"),
+                                    TextStyle {
+                                        font_size: 40.0,
+                                        ..text_style.clone()
+                                    },
+                                ));
+                                let mut power_ups = power_ups
+                                    .iter()
+                                    .flat_map(|power_ups| power_ups.0.values())
+                                    .collect::<Vec<_>>();
+                                power_ups.sort_by_key(|power_up| &power_up.name);
+                                for power_up in power_ups {
+                                    parent.spawn(
+                                        TextBundle::from_section(
+                                            format!(
+                                                "{}\n{}, {}",
+                                                locale.power_up_name(&power_up.name),
+                                                power_up.main_effect.describe(&locale),
+                                                power_up.side_effect.describe(&locale)
+                                            ),
+                                            text_style.clone(),
+                                        )
+                                        .with_style(
+                                            Style {
+                                                margin: UiRect::vertical(Val::Px(5.0)),
+                                                ..default()
+                                            },
+                                        ),
+                                    );
+                                }
+                            });
+                    });
+                spawn_button(
+                    parent,
+                    &font,
+                    &theme,
+                    MainMenuButton::Back,
+                    &locale.get("menu-back"),
+                    None,
+                );
+            }
         });
 }
 
@@ -65,25 +433,123 @@ pub fn main_menu_button_interaction(
         (&Interaction, &MainMenuButton, &mut BackgroundColor),
         (Changed<Interaction>, With<Button>),
     >,
+    theme: Res<Theme>,
+    mut screen: ResMut<MenuScreen>,
+    mut options: ResMut<RunOptions>,
+    saved_run: Res<SavedRun>,
+    mut game_state: ResMut<GameState>,
+    abilities: Res<AvailableAbilities>,
+    power_ups: Res<AvailablePowerUps>,
     mut next_state: ResMut<NextState<AppState>>,
+    mut next_settings_state: ResMut<NextState<SettingsState>>,
+    #[cfg(not(target_arch = "wasm32"))] mut ev_exit: EventWriter<AppExit>,
 ) {
     for (interaction, button, mut color) in &mut interaction_query {
         match *interaction {
             Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
+                *color = theme.hovered_button.into();
             }
             Interaction::None => {
-                *color = NORMAL_BUTTON.into();
+                *color = theme.normal_button.into();
             }
             Interaction::Clicked => match button {
-                MainMenuButton::StartGame => next_state.set(AppState::Battle),
+                MainMenuButton::Continue => {
+                    if let Some(save) = &saved_run.0 {
+                        *game_state = GameState::from_save(save, &abilities, &power_ups);
+                        next_state.set(save.next_state());
+                    }
+                }
+                MainMenuButton::NewRun => *screen = MenuScreen::NewRun,
+                MainMenuButton::Settings => next_settings_state.set(SettingsState::Open),
+                MainMenuButton::Compendium => *screen = MenuScreen::Compendium,
+                #[cfg(not(target_arch = "wasm32"))]
+                MainMenuButton::Quit => ev_exit.send(AppExit),
+                MainMenuButton::ChangeDifficulty => {
+                    options.difficulty = next_option(&Difficulty::ALL, options.difficulty)
+                }
+                MainMenuButton::ChangeCharacter => {
+                    options.character = next_option(&StartingCharacter::ALL, options.character)
+                }
+                MainMenuButton::RandomSeed => options.seed.clear(),
+                MainMenuButton::StartRun => {
+                    *game_state = GameState::new_run(&options, &abilities);
+                    next_state.set(AppState::Battle);
+                }
+                MainMenuButton::Back => *screen = MenuScreen::Title,
             },
         }
     }
 }
 
+/// Typing letters and numbers on the new run screen enters a seed
+pub fn type_seed(
+    screen: Res<MenuScreen>,
+    actions: Actions,
+    keys: Res<Input<KeyCode>>,
+    mut ev_character: EventReader<ReceivedCharacter>,
+    mut options: ResMut<RunOptions>,
+) {
+    let typed = ev_character
+        .iter()
+        .map(|ev| ev.char)
+        .filter(|char| char.is_ascii_alphanumeric())
+        .collect::<Vec<_>>();
+    if *screen != MenuScreen::NewRun || actions.is_blocked() {
+        return;
+    }
+
+    for char in typed {
+        if options.seed.len() < MAX_SEED_LENGTH {
+            options.seed.push(char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        options.seed.pop();
+    }
+}
+
+/// Cancel goes back from the other pages to the title
+pub fn leave_menu_screen(actions: Actions, mut screen: ResMut<MenuScreen>) {
+    if *screen != MenuScreen::Title && actions.just_pressed(InputAction::Cancel) {
+        *screen = MenuScreen::Title;
+    }
+}
+
+pub fn update_new_run_text(
+    options: Res<RunOptions>,
+    locale: Res<Locale>,
+    mut text_query: Query<(&mut Text, &NewRunText)>,
+) {
+    if !options.is_changed() {
+        return;
+    }
+
+    for (mut text, new_run_text) in &mut text_query {
+        if let Some(section) = text.sections.first_mut() {
+            section.value = describe_new_run_text(new_run_text, &options, &locale);
+        }
+    }
+}
+
 pub fn cleanup_menu(mut commands: Commands, query: Query<Entity, With<MainMenu>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_cycle_back_to_the_first() {
+        assert_eq!(
+            next_option(&Difficulty::ALL, Difficulty::Normal),
+            Difficulty::Hard
+        );
+        assert_eq!(
+            next_option(&Difficulty::ALL, Difficulty::Hard),
+            Difficulty::Easy
+        );
+    }
+}
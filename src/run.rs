@@ -0,0 +1,318 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    available_abilities::AvailableAbilities,
+    available_power_ups::AvailablePowerUps,
+    battle::stats::RunStats,
+    character::{AttributeType, Character, CharacterBundle, CharacterCategory, Group},
+    enemies::get_abilities,
+    locale::Locale,
+    AppState, GameState,
+};
+
+/// Longest seed that can be typed in
+pub const MAX_SEED_LENGTH: usize = 20;
+
+pub struct RunPlugin;
+
+impl Plugin for RunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunOptions>()
+            .init_resource::<SavedRun>()
+            .add_system(load_saved_run.in_schedule(OnEnter(AppState::MainMenu)))
+            .add_system(save_run.in_schedule(OnEnter(AppState::Battle)))
+            .add_system(save_run.in_schedule(OnEnter(AppState::AbilityChoose)));
+    }
+}
+
+/// Makes the enemies stronger or weaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Self::Easy, Self::Normal, Self::Hard];
+
+    /// Multiplier of the enemies' base attributes
+    pub fn enemy_power(self) -> f32 {
+        match self {
+            Self::Easy => 0.85,
+            Self::Normal => 1.0,
+            Self::Hard => 1.2,
+        }
+    }
+
+    /// Enemies added to every battle
+    pub fn extra_enemies(self) -> i32 {
+        match self {
+            Self::Easy | Self::Normal => 0,
+            Self::Hard => 1,
+        }
+    }
+
+    /// Whether every battle hides what the units can't see, not just the
+    /// maps which ask for it
+    pub fn fog_of_war(self) -> bool {
+        self == Self::Hard
+    }
+
+    pub fn name(self, locale: &Locale) -> String {
+        locale.get(match self {
+            Self::Easy => "difficulty-easy",
+            Self::Normal => "difficulty-normal",
+            Self::Hard => "difficulty-hard",
+        })
+    }
+}
+
+/// Character the player starts a new run with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StartingCharacter {
+    #[default]
+    Brawler,
+    Archer,
+    Guardian,
+}
+
+impl StartingCharacter {
+    pub const ALL: [StartingCharacter; 3] = [Self::Brawler, Self::Archer, Self::Guardian];
+
+    fn abilities(self) -> &'static [&'static str] {
+        match self {
+            Self::Brawler => &["move", "hit"],
+            Self::Archer => &["move", "shoot"],
+            Self::Guardian => &["move", "slam"],
+        }
+    }
+
+    fn attributes(self) -> &'static [(AttributeType, i32)] {
+        use AttributeType::*;
+
+        match self {
+            Self::Brawler => &[],
+            Self::Archer => &[(HitPoints, 110), (Speed, 12)],
+            Self::Guardian => &[(HitPoints, 180), (Attack, 8), (Defense, 14)],
+        }
+    }
+
+    pub fn character(self, abilities: &Res<AvailableAbilities>) -> Character {
+        Character::new(
+            CharacterBundle::new(
+                "player",
+                CharacterCategory::Human,
+                &get_abilities(self.abilities(), abilities),
+                self.attributes(),
+                Group::Player,
+            ),
+            "images/kitty.png",
+        )
+    }
+
+    pub fn name(self, locale: &Locale) -> String {
+        locale.get(match self {
+            Self::Brawler => "starting-character-brawler",
+            Self::Archer => "starting-character-archer",
+            Self::Guardian => "starting-character-guardian",
+        })
+    }
+}
+
+/// Seed typed in by the player. Numbers are used as they are, any other
+/// text is hashed, and no text at all means a random seed.
+fn parse_seed(text: &str) -> Option<u64> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    // FNV-1a, which unlike the std hasher stays the same between releases
+    Some(text.parse().unwrap_or_else(|_| {
+        text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }))
+}
+
+/// Options of the next new run, picked in the main menu
+#[derive(Resource, Debug, Clone, Default)]
+pub struct RunOptions {
+    pub seed: String,
+    pub difficulty: Difficulty,
+    pub character: StartingCharacter,
+}
+
+impl GameState {
+    pub fn new_run(options: &RunOptions, abilities: &Res<AvailableAbilities>) -> Self {
+        Self {
+            characters: vec![options.character.character(abilities)],
+            seed: parse_seed(&options.seed).unwrap_or_else(rand::random),
+            difficulty: options.difficulty,
+            starting_character: options.character,
+            ..default()
+        }
+    }
+
+    /// Rebuilds the run by applying the chosen power ups to the starting
+    /// character again
+    pub fn from_save(
+        save: &RunSave,
+        abilities: &Res<AvailableAbilities>,
+        power_ups: &Res<AvailablePowerUps>,
+    ) -> Self {
+        let mut player = save.character.character(abilities);
+        for name in &save.power_ups {
+            match power_ups.0.get(name) {
+                Some(power_up) => power_up.apply(&mut player),
+                None => warn!("Skipping the unknown saved power up {name}"),
+            }
+        }
+
+        Self {
+            characters: vec![player],
+            round: save.round,
+            seed: save.seed,
+            difficulty: save.difficulty,
+            starting_character: save.character,
+            power_ups: save.power_ups.clone(),
+            run_stats: save.run_stats.clone(),
+            ..default()
+        }
+    }
+}
+
+/// Run in progress, saved at the start of every battle and power up choice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunSave {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub character: StartingCharacter,
+    pub round: i32,
+    /// Names of the power ups in the order they were picked
+    pub power_ups: Vec<String>,
+    pub run_stats: RunStats,
+    /// Whether the run continues with picking a power up rather than
+    /// with a battle
+    pub choosing_power_up: bool,
+}
+
+impl RunSave {
+    pub fn new(game_state: &GameState, choosing_power_up: bool) -> Self {
+        Self {
+            seed: game_state.seed,
+            difficulty: game_state.difficulty,
+            character: game_state.starting_character,
+            round: game_state.round,
+            power_ups: game_state.power_ups.clone(),
+            run_stats: game_state.run_stats.clone(),
+            choosing_power_up,
+        }
+    }
+
+    pub fn next_state(&self) -> AppState {
+        if self.choosing_power_up {
+            AppState::AbilityChoose
+        } else {
+            AppState::Battle
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Option<Self> {
+        let path = crate::settings::config_path("run.json")?;
+        let json = std::fs::read_to_string(&path).ok()?;
+        serde_json::from_str(&json)
+            .map_err(|err| warn!("Ignoring invalid saved run in {}: {err}", path.display()))
+            .ok()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Option<Self> {
+        None
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self) {
+        let Some(path) = crate::settings::config_path("run.json") else {
+            warn!("Couldn't find a config directory to save the run in");
+            return;
+        };
+
+        let result = serde_json::to_string(self)
+            .map_err(std::io::Error::from)
+            .and_then(|json| {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(&path, json)
+            });
+        if let Err(err) = result {
+            warn!("Couldn't save the run to {}: {err}", path.display());
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self) {}
+
+    /// Removes the saved run once it's lost
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn delete() {
+        if let Some(path) = crate::settings::config_path("run.json") {
+            if let Err(err) = std::fs::remove_file(&path) {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Couldn't delete the saved run {}: {err}", path.display());
+                }
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn delete() {}
+}
+
+/// Run the main menu can continue
+#[derive(Resource, Default)]
+pub struct SavedRun(pub Option<RunSave>);
+
+fn load_saved_run(mut saved_run: ResMut<SavedRun>) {
+    saved_run.0 = RunSave::load();
+}
+
+fn save_run(game_state: Res<GameState>, state: Res<State<AppState>>) {
+    RunSave::new(&game_state, state.0 == AppState::AbilityChoose).save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_are_numbers_or_hashed_text() {
+        assert_eq!(parse_seed(""), None);
+        assert_eq!(parse_seed(" 42 "), Some(42));
+        assert_eq!(parse_seed("kitty"), parse_seed("kitty"));
+        assert_ne!(parse_seed("kitty"), parse_seed("kitty2"));
+    }
+
+    #[test]
+    fn saved_run_round_trips() {
+        let save = RunSave {
+            seed: 7,
+            difficulty: Difficulty::Hard,
+            character: StartingCharacter::Archer,
+            round: 3,
+            power_ups: vec!["slam".to_string()],
+            run_stats: RunStats::default(),
+            choosing_power_up: true,
+        };
+
+        let json = serde_json::to_string(&save).unwrap();
+
+        assert_eq!(serde_json::from_str::<RunSave>(&json).unwrap(), save);
+        assert_eq!(save.next_state(), AppState::AbilityChoose);
+    }
+}
@@ -0,0 +1,797 @@
+use bevy::{
+    prelude::*,
+    ui::FocusPolicy,
+    window::{PrimaryWindow, WindowMode},
+};
+use fluent::fluent_args;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::AudioVolume,
+    battle::animation::AnimationSpeed,
+    input::{describe_bindings, InputAction, InputMap, KeyBinding},
+    locale::{Language, Locale},
+    theme::Theme,
+};
+
+const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+const MSAA_SAMPLES: [u32; 4] = [1, 2, 4, 8];
+const VOLUME_STEP: f32 = 0.1;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<SettingsState>()
+            .init_resource::<KeyCapture>()
+            .add_systems((
+                apply_settings,
+                save_settings,
+                open_settings_button_interaction,
+            ))
+            .add_system(setup_settings_screen.in_schedule(OnEnter(SettingsState::Open)))
+            .add_system(cleanup_settings_screen.in_schedule(OnExit(SettingsState::Open)))
+            .add_systems(
+                (
+                    settings_button_interaction,
+                    capture_key_binding,
+                    update_settings_text
+                        .after(settings_button_interaction)
+                        .after(capture_key_binding),
+                )
+                    .in_set(OnUpdate(SettingsState::Open)),
+            )
+            // builds the screen again in a newly picked language or palette
+            .add_systems(
+                (cleanup_settings_screen, setup_settings_screen)
+                    .chain()
+                    .distributive_run_if(screen_outdated)
+                    .in_set(OnUpdate(SettingsState::Open)),
+            );
+    }
+}
+
+/// The settings screen is an overlay, so that it can be opened from any
+/// screen without leaving it
+#[derive(States, PartialEq, Eq, Debug, Clone, Hash, Default)]
+pub enum SettingsState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// Colours used for the groups and highlights
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorPalette {
+    #[default]
+    Default,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast,
+}
+
+impl ColorPalette {
+    pub const ALL: [ColorPalette; 5] = [
+        Self::Default,
+        Self::Deuteranopia,
+        Self::Protanopia,
+        Self::Tritanopia,
+        Self::HighContrast,
+    ];
+
+    fn name(&self, locale: &Locale) -> String {
+        locale.get(match self {
+            Self::Default => "palette-default",
+            Self::Deuteranopia => "palette-deuteranopia",
+            Self::Protanopia => "palette-protanopia",
+            Self::Tritanopia => "palette-tritanopia",
+            Self::HighContrast => "palette-high-contrast",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingKind {
+    Resolution,
+    Fullscreen,
+    Msaa,
+    MusicVolume,
+    SfxVolume,
+    AnimationSpeed,
+    Palette,
+    Language,
+}
+
+impl SettingKind {
+    const ALL: [SettingKind; 8] = [
+        Self::Resolution,
+        Self::Fullscreen,
+        Self::Msaa,
+        Self::MusicVolume,
+        Self::SfxVolume,
+        Self::AnimationSpeed,
+        Self::Palette,
+        Self::Language,
+    ];
+
+    fn message_id(&self) -> &'static str {
+        match self {
+            Self::Resolution => "settings-resolution",
+            Self::Fullscreen => "settings-fullscreen",
+            Self::Msaa => "settings-msaa",
+            Self::MusicVolume => "settings-music-volume",
+            Self::SfxVolume => "settings-sfx-volume",
+            Self::AnimationSpeed => "settings-animation-speed",
+            Self::Palette => "settings-palette",
+            Self::Language => "settings-language",
+        }
+    }
+}
+
+/// Element of `options` which is `step` positions away from `current`,
+/// staying at the ends
+fn step_option<T: Copy + PartialEq>(options: &[T], current: T, step: i32) -> T {
+    let index = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0) as i32;
+    options[(index + step).clamp(0, options.len() as i32 - 1) as usize]
+}
+
+/// Player preferences, saved in the user's config directory
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub resolution: (u32, u32),
+    pub fullscreen: bool,
+    pub msaa_samples: u32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub animation_speed: AnimationSpeed,
+    pub palette: ColorPalette,
+    pub language: Language,
+    /// Key bindings which differ from the defaults
+    pub key_bindings: Vec<(InputAction, Vec<KeyBinding>)>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let volume = AudioVolume::default();
+        Self {
+            resolution: RESOLUTIONS[0],
+            fullscreen: false,
+            msaa_samples: 4,
+            music_volume: volume.music,
+            sfx_volume: volume.sfx,
+            animation_speed: AnimationSpeed::default(),
+            palette: ColorPalette::default(),
+            language: Language::default(),
+            key_bindings: vec![],
+        }
+    }
+}
+
+impl Settings {
+    pub fn msaa(&self) -> Msaa {
+        match self.msaa_samples {
+            1 => Msaa::Off,
+            2 => Msaa::Sample2,
+            8 => Msaa::Sample8,
+            _ => Msaa::Sample4,
+        }
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    pub fn change(&mut self, kind: SettingKind, step: i32) {
+        let step_volume = |volume: f32| {
+            ((volume + step as f32 * VOLUME_STEP).clamp(0.0, 1.0) * 10.0).round() / 10.0
+        };
+
+        match kind {
+            SettingKind::Resolution => {
+                self.resolution = step_option(&RESOLUTIONS, self.resolution, step)
+            }
+            SettingKind::Fullscreen => self.fullscreen = !self.fullscreen,
+            SettingKind::Msaa => {
+                self.msaa_samples = step_option(&MSAA_SAMPLES, self.msaa_samples, step)
+            }
+            SettingKind::MusicVolume => self.music_volume = step_volume(self.music_volume),
+            SettingKind::SfxVolume => self.sfx_volume = step_volume(self.sfx_volume),
+            SettingKind::AnimationSpeed => {
+                self.animation_speed = step_option(&AnimationSpeed::ALL, self.animation_speed, step)
+            }
+            SettingKind::Palette => {
+                self.palette = step_option(&ColorPalette::ALL, self.palette, step)
+            }
+            SettingKind::Language => {
+                self.language = step_option(&Language::ALL, self.language, step)
+            }
+        }
+    }
+
+    pub fn describe(&self, kind: SettingKind, locale: &Locale) -> String {
+        let on_off = |on| locale.get(if on { "settings-on" } else { "settings-off" });
+
+        match kind {
+            SettingKind::Resolution => format!("{}x{}", self.resolution.0, self.resolution.1),
+            SettingKind::Fullscreen => on_off(self.fullscreen),
+            SettingKind::Msaa => match self.msaa_samples {
+                1 => on_off(false),
+                samples => format!("{samples}x"),
+            },
+            SettingKind::MusicVolume => format!("{:.0}%", self.music_volume * 100.0),
+            SettingKind::SfxVolume => format!("{:.0}%", self.sfx_volume * 100.0),
+            SettingKind::AnimationSpeed => self.animation_speed.name(locale),
+            SettingKind::Palette => self.palette.name(locale),
+            SettingKind::Language => self.language.to_string(),
+        }
+    }
+
+    pub fn bindings(&self, action: InputAction) -> Vec<KeyBinding> {
+        self.key_bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map(|(_, bindings)| bindings.clone())
+            .unwrap_or_else(|| {
+                InputMap::default()
+                    .keys
+                    .get(&action)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+    }
+
+    /// Another action which the binding already triggers
+    pub fn bound_elsewhere(&self, action: InputAction, binding: KeyBinding) -> Option<InputAction> {
+        InputMap::default()
+            .keys
+            .into_keys()
+            .filter(|other| *other != action)
+            .find(|other| self.bindings(*other).contains(&binding))
+    }
+
+    /// Fails with the action which already has the binding
+    pub fn rebind(&mut self, action: InputAction, binding: KeyBinding) -> Result<(), InputAction> {
+        if let Some(other) = self.bound_elsewhere(action, binding) {
+            return Err(other);
+        }
+        self.key_bindings.retain(|(bound, _)| *bound != action);
+        self.key_bindings.push((action, vec![binding]));
+        Ok(())
+    }
+
+    /// Gives the actions whose saved keys clash with another action their
+    /// default keys back, such as keys saved before the defaults changed
+    fn reset_conflicting_bindings(&mut self) {
+        while let Some(index) = self.key_bindings.iter().position(|(action, bindings)| {
+            bindings
+                .iter()
+                .any(|binding| self.bound_elsewhere(*action, *binding).is_some())
+        }) {
+            let (action, _) = self.key_bindings.remove(index);
+            warn!("Resetting the keys of {action:?}, another action has them");
+        }
+    }
+
+    /// Saved settings, or the defaults if there are none yet
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Self {
+        let Some(path) = config_path("settings.json") else {
+            return Self::default();
+        };
+
+        let mut settings = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                warn!("Ignoring invalid settings in {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        settings.reset_conflicting_bindings();
+        settings
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self) {
+        let Some(path) = config_path("settings.json") else {
+            warn!("Couldn't find a config directory to save the settings in");
+            return;
+        };
+
+        let result = serde_json::to_string_pretty(self)
+            .map_err(std::io::Error::from)
+            .and_then(|json| {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(&path, json)
+            });
+        if let Err(err) = result {
+            warn!("Couldn't save settings to {}: {err}", path.display());
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self) {}
+}
+
+/// Path of a file in the game's folder inside the user's config directory
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_path(file_name: &str) -> Option<std::path::PathBuf> {
+    use std::{env, path::PathBuf};
+
+    let config_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+
+    Some(config_dir?.join("counterbalance").join(file_name))
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut msaa: ResMut<Msaa>,
+    mut volume: ResMut<AudioVolume>,
+    mut speed: ResMut<AnimationSpeed>,
+    mut input_map: ResMut<InputMap>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for mut window in &mut window_query {
+        let (width, height) = settings.resolution;
+        if window.resolution.width() != width as f32 || window.resolution.height() != height as f32
+        {
+            window.resolution.set(width as f32, height as f32);
+        }
+        if window.mode != settings.window_mode() {
+            window.mode = settings.window_mode();
+        }
+    }
+
+    if *msaa != settings.msaa() {
+        *msaa = settings.msaa();
+    }
+    volume.music = settings.music_volume;
+    volume.sfx = settings.sfx_volume;
+    *speed = settings.animation_speed;
+
+    *input_map = InputMap::default();
+    for (action, bindings) in &settings.key_bindings {
+        input_map.keys.insert(*action, bindings.clone());
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    if settings.is_changed() && !settings.is_added() {
+        settings.save();
+    }
+}
+
+#[derive(Component)]
+pub struct SettingsScreen;
+
+#[derive(Component, Clone, Copy)]
+pub enum SettingsButton {
+    Change(SettingKind, i32),
+    Rebind(InputAction),
+    ResetBindings,
+    Close,
+}
+
+#[derive(Component)]
+pub enum SettingsText {
+    Value(SettingKind),
+    Binding(InputAction),
+}
+
+/// Opens the settings from screens which don't have a menu of their own
+#[derive(Component)]
+pub struct OpenSettingsButton;
+
+/// Action waiting for the next key press to be bound to it
+#[derive(Resource, Default)]
+pub struct KeyCapture {
+    action: Option<InputAction>,
+    /// Action which already has the key pressed last
+    taken_by: Option<InputAction>,
+}
+
+fn setup_settings_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    theme: Res<Theme>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Medium.ttf");
+    let text_style = |font_size| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
+    let button = |parent: &mut ChildBuilder, width, label: String, button: SettingsButton| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(width), Val::Px(30.0)),
+                        margin: UiRect::all(Val::Px(3.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: theme.normal_button.into(),
+                    ..default()
+                },
+                button,
+            ))
+            .with_children(|parent| {
+                let mut text = parent.spawn(TextBundle::from_section(label, text_style(20.0)));
+                if let SettingsButton::Rebind(action) = button {
+                    text.insert(SettingsText::Binding(action));
+                }
+            });
+    };
+
+    let column = || NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            margin: UiRect::all(Val::Px(20.0)),
+            ..default()
+        },
+        ..default()
+    };
+    let row = || NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ..default()
+    };
+    let label = |parent: &mut ChildBuilder, text: String, width| {
+        parent.spawn(
+            TextBundle::from_section(text, text_style(20.0)).with_style(Style {
+                size: Size::width(Val::Px(width)),
+                ..default()
+            }),
+        );
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::all(Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                focus_policy: FocusPolicy::Block,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            SettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locale.get("settings-title"),
+                text_style(40.0),
+            ));
+
+            parent.spawn(row()).with_children(|parent| {
+                parent.spawn(column()).with_children(|parent| {
+                    for kind in SettingKind::ALL {
+                        parent.spawn(row()).with_children(|parent| {
+                            label(parent, locale.get(kind.message_id()), 170.0);
+                            button(
+                                parent,
+                                30.0,
+                                "<".to_string(),
+                                SettingsButton::Change(kind, -1),
+                            );
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    settings.describe(kind, &locale),
+                                    text_style(20.0),
+                                )
+                                .with_style(Style {
+                                    size: Size::width(Val::Px(130.0)),
+                                    justify_content: JustifyContent::Center,
+                                    ..default()
+                                }),
+                                SettingsText::Value(kind),
+                            ));
+                            button(
+                                parent,
+                                30.0,
+                                ">".to_string(),
+                                SettingsButton::Change(kind, 1),
+                            );
+                        });
+                    }
+                });
+
+                parent.spawn(column()).with_children(|parent| {
+                    for action in InputAction::REBINDABLE {
+                        parent.spawn(row()).with_children(|parent| {
+                            label(parent, action.name(&locale), 190.0);
+                            button(
+                                parent,
+                                200.0,
+                                describe_bindings(&settings.bindings(*action)),
+                                SettingsButton::Rebind(*action),
+                            );
+                        });
+                    }
+                });
+            });
+
+            parent.spawn(row()).with_children(|parent| {
+                button(
+                    parent,
+                    220.0,
+                    locale.get("settings-reset-bindings"),
+                    SettingsButton::ResetBindings,
+                );
+                button(
+                    parent,
+                    220.0,
+                    locale.get("settings-close"),
+                    SettingsButton::Close,
+                );
+            });
+        });
+}
+
+fn settings_button_interaction(
+    keys: Res<Input<KeyCode>>,
+    theme: Res<Theme>,
+    mut settings: ResMut<Settings>,
+    mut capture: ResMut<KeyCapture>,
+    mut interaction_query: Query<
+        (&Interaction, &SettingsButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_state: ResMut<NextState<SettingsState>>,
+) {
+    if capture.action.is_none() && keys.just_pressed(KeyCode::Escape) {
+        next_state.set(SettingsState::Closed);
+        return;
+    }
+
+    for (interaction, button, mut color) in &mut interaction_query {
+        match *interaction {
+            Interaction::Hovered => *color = theme.hovered_button.into(),
+            Interaction::None => *color = theme.normal_button.into(),
+            Interaction::Clicked => match button {
+                SettingsButton::Change(kind, step) => settings.change(*kind, *step),
+                SettingsButton::Rebind(action) => {
+                    *capture = KeyCapture {
+                        action: Some(*action),
+                        taken_by: None,
+                    }
+                }
+                SettingsButton::ResetBindings => {
+                    *capture = KeyCapture::default();
+                    settings.key_bindings.clear();
+                }
+                SettingsButton::Close => next_state.set(SettingsState::Closed),
+            },
+        }
+    }
+}
+
+/// Binds the next key pressed to the action being rebound, Escape cancels
+fn capture_key_binding(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut capture: ResMut<KeyCapture>,
+) {
+    let Some(action) = capture.action else {
+        return;
+    };
+
+    const MODIFIERS: [KeyCode; 4] = [
+        KeyCode::LControl,
+        KeyCode::RControl,
+        KeyCode::LShift,
+        KeyCode::RShift,
+    ];
+    let Some(key) = keys
+        .get_just_pressed()
+        .find(|key| !MODIFIERS.contains(key))
+        .copied()
+    else {
+        return;
+    };
+
+    if key == KeyCode::Escape {
+        *capture = KeyCapture::default();
+        // so the same press doesn't also close the screen
+        keys.clear_just_pressed(KeyCode::Escape);
+        return;
+    }
+    let binding = KeyBinding {
+        key,
+        ctrl: keys.any_pressed([KeyCode::LControl, KeyCode::RControl]),
+        shift: keys.any_pressed([KeyCode::LShift, KeyCode::RShift]),
+    };
+    // keys of other actions are skipped and the capture waits for another one
+    match settings.rebind(action, binding) {
+        Ok(()) => *capture = KeyCapture::default(),
+        Err(other) => capture.taken_by = Some(other),
+    }
+}
+
+fn update_settings_text(
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    capture: Res<KeyCapture>,
+    mut text_query: Query<(&mut Text, &SettingsText)>,
+) {
+    if !settings.is_changed() && !capture.is_changed() {
+        return;
+    }
+
+    for (mut text, setting) in &mut text_query {
+        let value = match setting {
+            SettingsText::Value(kind) => settings.describe(*kind, &locale),
+            SettingsText::Binding(action) if capture.action == Some(*action) => {
+                match capture.taken_by {
+                    Some(other) => locale.format(
+                        "settings-key-taken",
+                        &fluent_args!["action" => other.name(&locale)],
+                    ),
+                    None => locale.get("settings-press-key"),
+                }
+            }
+            SettingsText::Binding(action) => describe_bindings(&settings.bindings(*action)),
+        };
+        if let Some(section) = text.sections.first_mut() {
+            if section.value != value {
+                section.value = value;
+            }
+        }
+    }
+}
+
+fn screen_outdated(locale: Res<Locale>, theme: Res<Theme>) -> bool {
+    locale.is_changed() || theme.is_changed()
+}
+
+fn cleanup_settings_screen(
+    mut commands: Commands,
+    mut capture: ResMut<KeyCapture>,
+    query: Query<Entity, With<SettingsScreen>>,
+) {
+    *capture = KeyCapture::default();
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn open_settings_button_interaction(
+    theme: Res<Theme>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<OpenSettingsButton>),
+    >,
+    mut next_state: ResMut<NextState<SettingsState>>,
+) {
+    for (interaction, mut color) in &mut interaction_query {
+        match *interaction {
+            Interaction::Hovered => *color = theme.hovered_button.into(),
+            Interaction::None => *color = theme.normal_button.into(),
+            Interaction::Clicked => next_state.set(SettingsState::Open),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_stay_within_the_options() {
+        let mut settings = Settings::default();
+
+        settings.change(SettingKind::Resolution, -1);
+        assert_eq!(settings.resolution, RESOLUTIONS[0]);
+        settings.change(SettingKind::Resolution, 1);
+        assert_eq!(settings.resolution, RESOLUTIONS[1]);
+
+        for _ in 0..20 {
+            settings.change(SettingKind::MusicVolume, 1);
+        }
+        assert_eq!(settings.music_volume, 1.0);
+        assert_eq!(
+            settings.describe(SettingKind::MusicVolume, &Locale::new(Language::English)),
+            "100%"
+        );
+
+        settings.change(SettingKind::Msaa, 1);
+        assert!(settings.msaa() == Msaa::Sample8);
+    }
+
+    #[test]
+    fn saved_settings_round_trip() {
+        let mut settings = Settings::default();
+        settings
+            .rebind(InputAction::Confirm, KeyBinding::key(KeyCode::Space))
+            .unwrap();
+        settings.palette = ColorPalette::Tritanopia;
+
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+        assert_eq!(
+            settings.bindings(InputAction::Confirm),
+            [KeyBinding::key(KeyCode::Space)]
+        );
+    }
+
+    #[test]
+    fn keys_are_bound_to_one_action_only() {
+        let mut settings = Settings::default();
+        for action in InputMap::default().keys.keys() {
+            for binding in settings.bindings(*action) {
+                assert_eq!(settings.bound_elsewhere(*action, binding), None);
+            }
+        }
+
+        assert_eq!(
+            settings.rebind(InputAction::Confirm, KeyBinding::key(KeyCode::W)),
+            Err(InputAction::PanUp)
+        );
+        assert!(settings
+            .rebind(InputAction::Confirm, KeyBinding::key(KeyCode::Return))
+            .is_ok());
+
+        // saved before panning took WASD
+        settings.key_bindings = vec![
+            (InputAction::CursorUp, vec![KeyBinding::key(KeyCode::W)]),
+            (InputAction::Cancel, vec![KeyBinding::key(KeyCode::Q)]),
+        ];
+        settings.reset_conflicting_bindings();
+        assert_eq!(
+            settings.bindings(InputAction::CursorUp),
+            [KeyBinding::key(KeyCode::Up)]
+        );
+        assert_eq!(
+            settings.bindings(InputAction::Cancel),
+            [KeyBinding::key(KeyCode::Q)]
+        );
+    }
+
+    #[test]
+    fn missing_settings_use_defaults() {
+        let settings: Settings = serde_json::from_str(r#"{ "fullscreen": true }"#).unwrap();
+        assert!(settings.fullscreen);
+        assert_eq!(settings.resolution, Settings::default().resolution);
+    }
+}
@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use crate::settings::{ColorPalette, Settings};
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_theme);
+    }
+}
+
+/// Lines drawn over a highlighted tile, so that highlights can be told
+/// apart without seeing their colours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileCue {
+    None,
+    Outline,
+    ThickOutline,
+    /// Parallel diagonal lines
+    Hatch,
+    Dot,
+    /// Short upright strokes, like blades of grass
+    Blades,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileStyle {
+    pub color: Color,
+    pub cue: TileCue,
+}
+
+impl TileStyle {
+    fn new(color: Color, cue: TileCue) -> Self {
+        Self { color, cue }
+    }
+}
+
+/// Colours of everything that carries a meaning in the game, picked by the
+/// palette in the settings
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub palette: ColorPalette,
+    pub normal_button: Color,
+    pub hovered_button: Color,
+    /// Toggle buttons which are switched on
+    pub active_button: Color,
+    pub tile: TileStyle,
+    /// Tiles covered in tall grass, when nothing highlights them
+    pub tall_grass: TileStyle,
+    /// Tiles the chosen ability can reach
+    pub range: TileStyle,
+    /// Tiles a unit walks over to the hovered tile
+    pub path: TileStyle,
+    pub valid_target: TileStyle,
+    pub invalid_target: TileStyle,
+    /// Colour the tile cues are drawn in
+    pub cue: Color,
+    pub ally: Color,
+    pub enemy: Color,
+    /// Unit whose turn it is
+    pub active_unit: Color,
+    /// Units hovered in the UI, such as in the battle log
+    pub highlighted_unit: Color,
+    /// Attributes above their base value
+    pub increase: Color,
+    /// Attributes below their base value
+    pub decrease: Color,
+    /// Damage numbers floating over the hit units
+    pub damage: Color,
+    pub crit: Color,
+    pub miss: Color,
+}
+
+impl Theme {
+    pub fn new(palette: ColorPalette) -> Self {
+        use TileCue::*;
+
+        let theme = Self {
+            palette,
+            normal_button: Color::rgb(0.15, 0.15, 0.15),
+            hovered_button: Color::rgb(0.25, 0.25, 0.25),
+            active_button: Color::rgb(0.35, 0.35, 0.55),
+            tile: TileStyle::new(Color::GRAY, None),
+            tall_grass: TileStyle::new(Color::rgb(0.33, 0.45, 0.29), Blades),
+            range: TileStyle::new(Color::rgb(0.62, 1.0, 0.53), Outline),
+            path: TileStyle::new(Color::rgb(0.45, 0.8, 0.4), Dot),
+            valid_target: TileStyle::new(Color::LIME_GREEN, ThickOutline),
+            invalid_target: TileStyle::new(Color::RED, Hatch),
+            cue: Color::rgba(0.0, 0.0, 0.0, 0.7),
+            ally: Color::rgb(0.3, 0.5, 0.9),
+            enemy: Color::rgb(0.8, 0.3, 0.3),
+            active_unit: Color::GOLD,
+            highlighted_unit: Color::rgb(1.0, 1.0, 0.4),
+            increase: Color::GREEN,
+            decrease: Color::RED,
+            damage: Color::RED,
+            crit: Color::ORANGE_RED,
+            miss: Color::GRAY,
+        };
+
+        // the colour-blind presets avoid the pairs their players confuse,
+        // based on the Okabe-Ito palette
+        match palette {
+            ColorPalette::Default => theme,
+            ColorPalette::Deuteranopia | ColorPalette::Protanopia => Self {
+                tall_grass: TileStyle::new(Color::rgb(0.45, 0.42, 0.25), Blades),
+                range: TileStyle::new(Color::rgb(0.6, 0.8, 0.95), Outline),
+                path: TileStyle::new(Color::rgb(0.34, 0.71, 0.91), Dot),
+                valid_target: TileStyle::new(Color::rgb(0.0, 0.45, 0.7), ThickOutline),
+                invalid_target: TileStyle::new(Color::rgb(0.9, 0.6, 0.0), Hatch),
+                ally: Color::rgb(0.0, 0.45, 0.7),
+                enemy: Color::rgb(0.9, 0.6, 0.0),
+                increase: Color::rgb(0.34, 0.71, 0.91),
+                decrease: Color::rgb(0.9, 0.6, 0.0),
+                damage: Color::rgb(0.9, 0.6, 0.0),
+                crit: Color::rgb(0.94, 0.89, 0.26),
+                ..theme
+            },
+            ColorPalette::Tritanopia => Self {
+                tall_grass: TileStyle::new(Color::rgb(0.45, 0.3, 0.35), Blades),
+                range: TileStyle::new(Color::rgb(0.6, 0.9, 0.9), Outline),
+                path: TileStyle::new(Color::rgb(0.3, 0.75, 0.75), Dot),
+                valid_target: TileStyle::new(Color::rgb(0.0, 0.6, 0.6), ThickOutline),
+                invalid_target: TileStyle::new(Color::rgb(0.84, 0.2, 0.2), Hatch),
+                ally: Color::rgb(0.0, 0.6, 0.6),
+                enemy: Color::rgb(0.84, 0.2, 0.2),
+                increase: Color::rgb(0.0, 0.75, 0.75),
+                decrease: Color::rgb(0.95, 0.35, 0.35),
+                damage: Color::rgb(0.84, 0.2, 0.2),
+                crit: Color::rgb(0.8, 0.47, 0.65),
+                ..theme
+            },
+            ColorPalette::HighContrast => Self {
+                normal_button: Color::BLACK,
+                hovered_button: Color::rgb(0.4, 0.4, 0.4),
+                active_button: Color::rgb(0.0, 0.0, 0.8),
+                tile: TileStyle::new(Color::rgb(0.25, 0.25, 0.25), None),
+                tall_grass: TileStyle::new(Color::rgb(0.1, 0.4, 0.1), Blades),
+                range: TileStyle::new(Color::WHITE, Outline),
+                path: TileStyle::new(Color::CYAN, Dot),
+                valid_target: TileStyle::new(Color::YELLOW, ThickOutline),
+                invalid_target: TileStyle::new(Color::FUCHSIA, Hatch),
+                cue: Color::BLACK,
+                ally: Color::CYAN,
+                enemy: Color::FUCHSIA,
+                active_unit: Color::YELLOW,
+                highlighted_unit: Color::YELLOW,
+                increase: Color::CYAN,
+                decrease: Color::FUCHSIA,
+                damage: Color::FUCHSIA,
+                crit: Color::YELLOW,
+                miss: Color::WHITE,
+                ..theme
+            },
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(ColorPalette::default())
+    }
+}
+
+fn update_theme(settings: Res<Settings>, mut theme: ResMut<Theme>) {
+    if settings.palette != theme.palette {
+        *theme = Theme::new(settings.palette);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_differ_without_colours() {
+        for palette in ColorPalette::ALL {
+            let theme = Theme::new(palette);
+            let cues = [
+                theme.tile.cue,
+                theme.tall_grass.cue,
+                theme.range.cue,
+                theme.path.cue,
+                theme.valid_target.cue,
+                theme.invalid_target.cue,
+            ];
+
+            for (i, cue) in cues.iter().enumerate() {
+                assert!(!cues[i + 1..].contains(cue), "{palette:?} repeats {cue:?}");
+            }
+            assert_ne!(theme.valid_target.color, theme.invalid_target.color);
+            assert_ne!(theme.ally, theme.enemy);
+            assert_ne!(theme.damage, theme.crit);
+            assert_ne!(theme.damage, theme.miss);
+        }
+    }
+}
@@ -3,178 +3,560 @@ use bevy_prototype_lyon::prelude::*;
 
 use crate::{
     battle::{battle_field::BattleField, init::get_scaling},
-    character::{Attribute, AttributeType, Attributes},
+    character::{Attribute, AttributeType, AttributeValueType, Attributes, ModifierKind},
 };
 
+const BORDER_SIZE: f32 = 2.0;
+const ROW_SPACING: f32 = 3.0;
+/// Ticks closer than this many pixels aren't drawn
+const MIN_TICK_SPACING: f32 = 4.0;
+const STATUS_PIP_WIDTH: f32 = 5.0;
+/// How long the recent damage stays before shrinking, in seconds
+const GHOST_DELAY: f32 = 0.4;
+/// How fast the recent damage shrinks, in bar widths per second
+const GHOST_SPEED: f32 = 0.8;
+
+const BACKGROUND_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const GHOST_COLOR: Color = Color::rgb(0.95, 0.35, 0.3);
+const TICK_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const BUFF_COLOR: Color = Color::rgb(0.3, 0.9, 0.4);
+const DEBUFF_COLOR: Color = Color::rgb(0.9, 0.3, 0.9);
+
 pub struct BarPlugin;
 
 impl Plugin for BarPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(setup_bar);
-        app.add_system(update_bar);
+        let bars = BarStack::from_definitions().unwrap_or_else(|err| panic!("Invalid bars: {err}"));
+        app.insert_resource(UnitBars(bars));
+        app.add_system(setup_bar_stack);
+        app.add_system(update_bar_stack);
+        app.add_system(update_status_pips);
         app.add_system(correct_bar_position);
     }
 }
 
+/// Look of a single bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BarStyle {
+    pub color: Color,
+    pub size: Vec2,
+    /// Value between two ticks, `None` draws no ticks
+    pub tick: Option<i32>,
+}
+
+impl BarStyle {
+    pub fn new(color: Color, size: Vec2) -> Self {
+        Self {
+            color,
+            size,
+            tick: None,
+        }
+    }
+
+    pub fn with_ticks(mut self, every: i32) -> Self {
+        self.tick = Some(every);
+        self
+    }
+}
+
+/// Look of the bar of an attribute, for the attributes that have one
+fn bar_style(typ: AttributeType) -> BarStyle {
+    match typ {
+        AttributeType::HitPoints => {
+            BarStyle::new(Color::rgb(0.2, 0.8, 0.2), Vec2::new(80.0, 12.0)).with_ticks(50)
+        }
+        AttributeType::Energy => {
+            BarStyle::new(Color::rgb(0.9, 0.8, 0.2), Vec2::new(80.0, 6.0)).with_ticks(5)
+        }
+        AttributeType::Shield => {
+            BarStyle::new(Color::rgb(0.4, 0.7, 1.0), Vec2::new(80.0, 6.0)).with_ticks(25)
+        }
+        _ => BarStyle::new(Color::WHITE, Vec2::new(80.0, 6.0)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarSource {
+    Gauge(AttributeType),
+    /// One pip per remaining turn of every timed modifier
+    StatusDurations,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BarRow {
+    source: BarSource,
+    style: BarStyle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarError {
+    NotAGauge(AttributeType),
+}
+
+impl std::fmt::Display for BarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotAGauge(typ) => write!(f, "{typ:?} is not a gauge and can't have a bar"),
+        }
+    }
+}
+
+/// Bar stack given to every unit, checked when the plugin is built
+#[derive(Resource)]
+pub struct UnitBars(pub BarStack);
+
+/// Bars drawn above a unit, the first one is the closest to the unit
+#[derive(Component, Debug, Clone, Default)]
+pub struct BarStack {
+    rows: Vec<BarRow>,
+}
+
+impl BarStack {
+    pub fn with_gauge(
+        mut self,
+        attribute: AttributeType,
+        style: BarStyle,
+    ) -> Result<Self, BarError> {
+        if attribute.definition().value_type != AttributeValueType::Gauge {
+            return Err(BarError::NotAGauge(attribute));
+        }
+        self.rows.push(BarRow {
+            source: BarSource::Gauge(attribute),
+            style,
+        });
+        Ok(self)
+    }
+
+    pub fn with_status_durations(mut self) -> Self {
+        self.rows.push(BarRow {
+            source: BarSource::StatusDurations,
+            style: BarStyle::new(Color::NONE, Vec2::new(0.0, STATUS_PIP_WIDTH)),
+        });
+        self
+    }
+
+    /// Bars of all attributes which are defined to have one, topped by the
+    /// status durations
+    pub fn from_definitions() -> Result<Self, BarError> {
+        AttributeType::ALL
+            .iter()
+            .filter(|typ| typ.definition().bar)
+            .try_fold(Self::default(), |stack, typ| {
+                stack.with_gauge(*typ, bar_style(*typ))
+            })
+            .map(BarStack::with_status_durations)
+    }
+}
+
+/// Node holding all bars of a unit, kept upright and above its image
+#[derive(Component)]
+pub struct BarStackNode;
+
+#[derive(Component)]
+pub struct BarRowNode {
+    row: BarRow,
+    fill_entity: Entity,
+    ghost_entity: Entity,
+    ticks_entity: Entity,
+    fill: f32,
+    ghost: f32,
+    ghost_delay: f32,
+    range: (i32, i32),
+}
+
+/// Node holding the status pips of a `BarSource::StatusDurations` row
 #[derive(Component)]
-pub struct Bar {
-    attribute: AttributeType,
+pub struct StatusPips;
+
+/// Filled part of a gauge, `None` when the gauge has no range to show
+fn fill_fraction(attribute: &Attribute) -> Option<f32> {
+    let (value, min, max) = (
+        attribute.get_value(),
+        attribute.get_min(),
+        attribute.get_max(),
+    );
+    (max > min).then(|| (value - min) as f32 / (max - min) as f32)
 }
 
-impl Bar {
-    pub fn new(attribute: AttributeType) -> Bar {
-        Self { attribute }
+/// Horizontal positions of the ticks inside a bar of the given width
+fn tick_positions(min: i32, max: i32, every: i32, width: f32) -> Vec<f32> {
+    if every <= 0 || max <= min {
+        return vec![];
+    }
+    let spacing = width * every as f32 / (max - min) as f32;
+    if spacing < MIN_TICK_SPACING {
+        return vec![];
     }
+
+    (1..)
+        .map(|i| i * every)
+        .take_while(|offset| *offset < max - min)
+        .map(|offset| width * offset as f32 / (max - min) as f32)
+        .collect()
+}
+
+/// Moves the recent damage segment towards the current fill, pausing again
+/// whenever the fill drops
+fn shrink_ghost(ghost: f32, previous_fill: f32, fill: f32, delay: &mut f32, delta: f32) -> f32 {
+    if ghost <= fill {
+        *delay = GHOST_DELAY;
+        return fill;
+    }
+    if fill < previous_fill {
+        *delay = GHOST_DELAY;
+        return ghost;
+    }
+    if *delay > 0.0 {
+        *delay -= delta;
+        return ghost;
+    }
+    (ghost - GHOST_SPEED * delta).max(fill)
+}
+
+fn rectangle(size: Vec2) -> Path {
+    GeometryBuilder::build_as(&shapes::Rectangle {
+        extents: size,
+        origin: RectangleOrigin::BottomLeft,
+    })
+}
+
+fn ticks_path(positions: &[f32], height: f32) -> Path {
+    let mut builder = PathBuilder::new();
+    for x in positions {
+        builder.move_to(Vec2::new(*x, 0.0));
+        builder.line_to(Vec2::new(*x, height));
+    }
+    builder.build()
 }
 
 fn position_above_image(image: &Image) -> Transform {
     Transform::from_xyz(0.0, 0.5 * image.size().y + 20.0, 0.0)
 }
 
-fn get_inner_shape(bar: &Bar, attributes: &Attributes, border_size: f32) -> shapes::Rectangle {
-    let attribute = attributes
-        .0
-        .get(&bar.attribute)
-        .expect("Missing attribute to which bar corresponds");
+fn invert_scale(scale: &Vec3) -> Vec3 {
+    Vec3::new(1.0 / scale.x, 1.0 / scale.y, 1.0 / scale.z)
+}
 
-    match *attribute {
-        Attribute::Value(_) => panic!("Bar not supported for \"value\" attribute!"),
-        Attribute::Gauge { value, min, max } => shapes::Rectangle {
-            extents: Vec2::new(
-                (80.0 - border_size) * (value - min).clamp(min, max) as f32 / (max - min) as f32,
-                -(20.0 - border_size),
-            ),
-            origin: RectangleOrigin::TopLeft,
-        },
+fn stack_transform(image: &Image, battle_field: Option<&BattleField>) -> Transform {
+    let transform = position_above_image(image);
+    match battle_field {
+        Some(battle_field) => transform.with_scale(invert_scale(&get_scaling(
+            Some(image),
+            battle_field.tile_size(),
+        ))),
+        None => transform,
     }
 }
 
-fn setup_bar(
+fn setup_bar_stack(
     mut commands: Commands,
     images: Res<Assets<Image>>,
-    query: Query<
-        (
-            Entity,
-            &Bar,
-            &Attributes,
-            &Handle<Image>,
-            Option<&RenderLayers>,
-        ),
-        Added<Bar>,
-    >,
+    battle_field: Option<Res<BattleField>>,
+    query: Query<(Entity, &BarStack, &Handle<Image>, Option<&RenderLayers>), Added<BarStack>>,
 ) {
-    const BORDER_SIZE: f32 = 2.0;
-
-    let shape = shapes::Rectangle {
-        extents: Vec2::new(80.0, 20.0),
-        ..default()
-    };
+    for (entity, stack, image_handle, render_layers) in query.iter() {
+        let layers = render_layers.copied().unwrap_or_default();
+        let (visibility, transform) = images
+            .get(image_handle)
+            .map(|image| {
+                (
+                    Visibility::Inherited,
+                    stack_transform(image, battle_field.as_deref()),
+                )
+            })
+            .unwrap_or_else(|| (Visibility::Hidden, Transform::default()));
 
-    for (entity, bar, attributes, image_handle, render_layers) in query.iter() {
         commands.entity(entity).with_children(|parent| {
-            let (visibility, transform) = images
-                .get(image_handle)
-                .map(|image| (Visibility::default(), position_above_image(image)))
-                .unwrap_or_else(|| (Visibility::Hidden, Transform::default()));
-
-            let mut bar_entity = parent.spawn((
-                ShapeBundle {
-                    path: GeometryBuilder::build_as(&shape),
-                    transform,
-                    visibility,
-                    ..default()
-                },
-                Fill::color(Color::WHITE),
-                Stroke::new(Color::BLACK, 2.0),
-            ));
-
-            bar_entity.with_children(|parent| {
-                let inner_shape = get_inner_shape(bar, attributes, BORDER_SIZE);
-
-                let mut bar_inside = parent.spawn((
-                    ShapeBundle {
-                        path: GeometryBuilder::build_as(&inner_shape),
-                        transform: Transform::from_xyz(
-                            -0.5 * (shape.extents.x - BORDER_SIZE),
-                            -0.5 * (shape.extents.y - BORDER_SIZE),
-                            1.0,
-                        ),
+            parent
+                .spawn((
+                    SpatialBundle {
+                        transform,
+                        visibility,
                         ..default()
                     },
-                    Fill::color(Color::GREEN),
-                ));
+                    BarStackNode,
+                ))
+                .with_children(|parent| {
+                    for row in &stack.rows {
+                        let mut row_node = parent.spawn(SpatialBundle::default());
 
-                if let Some(layers) = render_layers {
-                    bar_inside.insert(*layers);
-                }
-            });
+                        if row.source == BarSource::StatusDurations {
+                            row_node.insert(StatusPips);
+                            continue;
+                        }
 
-            if let Some(layers) = render_layers {
-                bar_entity.insert(*layers);
-            }
+                        let layer = |parent: &mut ChildBuilder, path, z, fill: Option<Color>| {
+                            let mut node = parent.spawn((
+                                ShapeBundle {
+                                    path,
+                                    transform: Transform::from_xyz(0.0, 0.0, z),
+                                    ..default()
+                                },
+                                layers,
+                            ));
+                            match fill {
+                                Some(color) => node.insert(Fill::color(color)),
+                                None => node.insert(Stroke::new(TICK_COLOR, 1.0)),
+                            };
+                            node.id()
+                        };
+
+                        let mut ids = [Entity::PLACEHOLDER; 3];
+                        row_node.with_children(|parent| {
+                            parent.spawn((
+                                ShapeBundle {
+                                    path: rectangle(row.style.size),
+                                    ..default()
+                                },
+                                Fill::color(BACKGROUND_COLOR),
+                                Stroke::new(Color::BLACK, BORDER_SIZE),
+                                layers,
+                            ));
+                            ids = [
+                                layer(parent, rectangle(Vec2::ZERO), 1.0, Some(row.style.color)),
+                                layer(parent, rectangle(Vec2::ZERO), 0.5, Some(GHOST_COLOR)),
+                                layer(parent, ticks_path(&[], 0.0), 2.0, None),
+                            ];
+                        });
+
+                        row_node.insert(BarRowNode {
+                            row: *row,
+                            fill_entity: ids[0],
+                            ghost_entity: ids[1],
+                            ticks_entity: ids[2],
+                            fill: -1.0,
+                            ghost: 0.0,
+                            ghost_delay: 0.0,
+                            range: (0, 0),
+                        });
+                    }
+                });
         });
     }
 }
 
-fn invert_scale(scale: &Vec3) -> Vec3 {
-    Vec3::new(1.0 / scale.x, 1.0 / scale.y, 1.0 / scale.z)
+/// Refreshes the gauges and stacks the visible rows on top of each other
+fn update_bar_stack(
+    time: Res<Time>,
+    unit_query: Query<(&Attributes, &Children), With<BarStack>>,
+    stack_query: Query<&Children, With<BarStackNode>>,
+    mut row_query: Query<(
+        Option<&mut BarRowNode>,
+        Option<&Children>,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    mut path_query: Query<&mut Path>,
+) {
+    for (attributes, children) in unit_query.iter() {
+        for rows in children
+            .iter()
+            .filter_map(|child| stack_query.get(*child).ok())
+        {
+            let mut height = 0.0;
+            for row_entity in rows.iter() {
+                let Ok((row_node, pips, mut transform, mut visibility)) =
+                    row_query.get_mut(*row_entity)
+                else {
+                    continue;
+                };
+
+                let size = match row_node {
+                    Some(mut row_node) => {
+                        let row_node = &mut *row_node;
+                        let BarSource::Gauge(typ) = row_node.row.source else {
+                            continue;
+                        };
+                        let Some((attribute, fill)) = attributes
+                            .0
+                            .get(&typ)
+                            .and_then(|attribute| Some((attribute, fill_fraction(attribute)?)))
+                        else {
+                            *visibility = Visibility::Hidden;
+                            continue;
+                        };
+                        let size = row_node.row.style.size;
+
+                        let previous_fill = row_node.fill;
+                        if row_node.fill != fill {
+                            row_node.fill = fill;
+                            if let Ok(mut path) = path_query.get_mut(row_node.fill_entity) {
+                                *path = rectangle(Vec2::new(size.x * fill, size.y));
+                            }
+                        }
+
+                        let ghost = shrink_ghost(
+                            row_node.ghost,
+                            previous_fill,
+                            fill,
+                            &mut row_node.ghost_delay,
+                            time.delta_seconds(),
+                        );
+                        if ghost != row_node.ghost {
+                            row_node.ghost = ghost;
+                            if let Ok(mut path) = path_query.get_mut(row_node.ghost_entity) {
+                                *path = rectangle(Vec2::new(size.x * ghost, size.y));
+                            }
+                        }
+
+                        let range = (attribute.get_min(), attribute.get_max());
+                        if row_node.range != range {
+                            row_node.range = range;
+                            if let Ok(mut path) = path_query.get_mut(row_node.ticks_entity) {
+                                let ticks = row_node.row.style.tick.map_or(vec![], |every| {
+                                    tick_positions(range.0, range.1, every, size.x)
+                                });
+                                *path = ticks_path(&ticks, size.y);
+                            }
+                        }
+
+                        size
+                    }
+                    None if pips.is_some_and(|pips| !pips.is_empty()) => {
+                        Vec2::new(0.0, STATUS_PIP_WIDTH)
+                    }
+                    None => {
+                        *visibility = Visibility::Hidden;
+                        continue;
+                    }
+                };
+
+                *visibility = Visibility::Inherited;
+                transform.translation.x = -0.5 * size.x;
+                transform.translation.y = height;
+                height += size.y + ROW_SPACING;
+            }
+        }
+    }
+}
+
+fn update_status_pips(
+    mut commands: Commands,
+    unit_query: Query<
+        (&Attributes, &Children, Option<&RenderLayers>),
+        (With<BarStack>, Changed<Attributes>),
+    >,
+    stack_query: Query<&Children, With<BarStackNode>>,
+    pips_query: Query<Entity, With<StatusPips>>,
+) {
+    for (attributes, children, render_layers) in unit_query.iter() {
+        let layers = render_layers.copied().unwrap_or_default();
+        let statuses = AttributeType::ALL
+            .iter()
+            .filter_map(|typ| attributes.0.get(typ))
+            .flat_map(|attribute| attribute.modifiers())
+            .filter_map(|modifier| {
+                let turns = modifier.duration?;
+                let buff = match modifier.kind {
+                    ModifierKind::Flat(change) | ModifierKind::Percent(change) => change > 0,
+                };
+                Some((turns, if buff { BUFF_COLOR } else { DEBUFF_COLOR }))
+            })
+            .collect::<Vec<_>>();
+
+        for rows in children
+            .iter()
+            .filter_map(|child| stack_query.get(*child).ok())
+        {
+            for pips in rows.iter().filter_map(|row| pips_query.get(*row).ok()) {
+                commands.entity(pips).despawn_descendants();
+                commands.entity(pips).with_children(|parent| {
+                    let mut x = 0.0;
+                    for (turns, color) in &statuses {
+                        let width = *turns as f32 * STATUS_PIP_WIDTH;
+                        parent.spawn((
+                            ShapeBundle {
+                                path: rectangle(Vec2::new(width - 1.0, STATUS_PIP_WIDTH - 1.0)),
+                                transform: Transform::from_xyz(x, 0.0, 0.0),
+                                ..default()
+                            },
+                            Fill::color(*color),
+                            layers,
+                        ));
+                        x += width + ROW_SPACING;
+                    }
+                });
+            }
+        }
+    }
 }
 
 fn correct_bar_position(
     images: Res<Assets<Image>>,
     battle_field: Option<Res<BattleField>>,
     mut ev_image_asset: EventReader<AssetEvent<Image>>,
-    entity_query: Query<(&Handle<Image>, &Children), With<Bar>>,
-    mut transform_query: Query<(&mut Transform, &mut Visibility), Without<Bar>>,
+    entity_query: Query<(&Handle<Image>, &Children), With<BarStack>>,
+    mut stack_query: Query<(&mut Transform, &mut Visibility), With<BarStackNode>>,
 ) {
     for ev in ev_image_asset.iter() {
         if let AssetEvent::Created { handle } = ev {
+            let image = images.get(handle).expect("Wrong image asset created event");
             for children in entity_query
                 .iter()
                 .filter_map(|(q_handle, children)| (q_handle == handle).then_some(children))
             {
-                let transform_entity = *children.first().expect("Expected bar to have a child");
-                let (mut transform, mut visibility) = transform_query
-                    .get_mut(transform_entity)
-                    .expect("Missing transform");
-
-                *transform = position_above_image(
-                    images.get(handle).expect("Wrong image asset created event"),
-                );
-
-                if let Some(battle_field) = battle_field.as_ref() {
-                    *transform = transform.with_scale(invert_scale(&get_scaling(
-                        images.get(handle),
-                        battle_field.tile_size(),
-                    )));
+                let mut stacks = stack_query.iter_many_mut(children);
+                while let Some((mut transform, mut visibility)) = stacks.fetch_next() {
+                    *transform = stack_transform(image, battle_field.as_deref());
+                    *visibility = Visibility::Inherited;
                 }
-                *visibility = Visibility::default();
             }
         }
     }
 }
 
-fn update_bar(
-    bar_query: Query<(&Bar, &Attributes, &Children), Changed<Attributes>>,
-    stroke_query: Query<(&Stroke, &Children)>,
-    mut inner_bar_query: Query<&mut Path>,
-) {
-    for (bar, attributes, children) in bar_query.iter() {
-        let stroke_entity = *children.first().expect("Expected bar to have a child");
-        if let Ok((stroke, stroke_children)) = stroke_query.get(stroke_entity) {
-            let inner_bar_entity = stroke_children
-                .first()
-                .expect("Expected stroke to have a child");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_attributes_are_rejected() {
+        let style = BarStyle::new(Color::WHITE, Vec2::new(80.0, 10.0));
+
+        assert_eq!(
+            BarStack::default()
+                .with_gauge(AttributeType::Attack, style)
+                .unwrap_err(),
+            BarError::NotAGauge(AttributeType::Attack)
+        );
+        assert!(BarStack::default()
+            .with_gauge(AttributeType::Shield, style)
+            .is_ok());
+        assert!(BarStack::from_definitions().is_ok());
+    }
 
-            let mut inner_bar = inner_bar_query
-                .get_mut(*inner_bar_entity)
-                .expect("Mismatched child");
+    #[test]
+    fn ticks_skip_crowded_and_empty_gauges() {
+        assert_eq!(tick_positions(0, 150, 50, 75.0), vec![25.0, 50.0]);
+        assert!(tick_positions(0, 1000, 5, 80.0).is_empty());
+        assert!(tick_positions(0, 0, 5, 80.0).is_empty());
+    }
 
-            let border_size = stroke.options.line_width;
+    #[test]
+    fn ghost_waits_then_shrinks_to_the_fill() {
+        let mut delay = 0.0;
+        assert_eq!(shrink_ghost(0.5, 0.5, 0.8, &mut delay, 0.1), 0.8);
+        assert_eq!(delay, GHOST_DELAY);
 
-            let inner_shape = get_inner_shape(bar, attributes, border_size);
+        assert_eq!(shrink_ghost(0.8, 0.8, 0.5, &mut delay, 0.1), 0.8);
+        assert_eq!(shrink_ghost(0.8, 0.5, 0.5, &mut delay, GHOST_DELAY), 0.8);
+        let ghost = shrink_ghost(0.8, 0.5, 0.5, &mut delay, 0.1);
+        assert!(ghost < 0.8 && ghost > 0.5);
 
-            *inner_bar = GeometryBuilder::build_as(&inner_shape);
-        }
+        // a second hit while shrinking waits again before going on
+        assert_eq!(shrink_ghost(ghost, 0.5, 0.3, &mut delay, 0.1), ghost);
+        assert_eq!(delay, GHOST_DELAY);
+        assert_eq!(shrink_ghost(ghost, 0.3, 0.3, &mut delay, 0.1), ghost);
+        assert_eq!(shrink_ghost(ghost, 0.3, 0.3, &mut delay, 10.0), ghost);
+        assert_eq!(shrink_ghost(ghost, 0.3, 0.3, &mut delay, 10.0), 0.3);
+    }
+
+    #[test]
+    fn empty_gauges_have_no_fill() {
+        let mut shield = Attribute::gauge(0);
+        assert_eq!(fill_fraction(&shield), None);
+
+        shield.set_base(20);
+        assert_eq!(fill_fraction(&shield), Some(1.0));
     }
 }
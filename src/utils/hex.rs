@@ -1,15 +1,69 @@
-use std::hash::{Hash, Hasher};
+use std::{
+    collections::VecDeque,
+    hash::{Hash, Hasher},
+    ops::{Add, Sub},
+};
 
-use bevy::prelude::{IVec2, IVec3, Vec3};
+use bevy::{
+    prelude::{IVec2, IVec3, Vec2, Vec3},
+    utils::HashMap,
+};
 
 fn manhattan_distance(a: &IVec3, b: &IVec3) -> i32 {
     (a.x - b.x).abs() + (a.y - b.y).abs() + (a.z - b.z).abs()
 }
 
-#[derive(Debug, Clone, Copy, Eq)]
-pub enum Hex {
-    Oddr(IVec2),
-    Cube(IVec3),
+/// Cube offsets to the neighbours, counterclockwise starting to the right
+const DIRECTIONS: [IVec3; 6] = [
+    IVec3::new(1, 0, -1),
+    IVec3::new(1, 1, 0),
+    IVec3::new(0, 1, 1),
+    IVec3::new(-1, 0, 1),
+    IVec3::new(-1, -1, 0),
+    IVec3::new(0, -1, -1),
+];
+
+/// Which way the hexagons are turned on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HexOrientation {
+    /// Corners at the top and bottom, laid out in rows
+    #[default]
+    PointyTop,
+    /// Flat edges at the top and bottom, laid out in columns
+    FlatTop,
+}
+
+impl HexOrientation {
+    /// Offset coordinates numbering the rows or columns of this orientation
+    pub fn offset_kind(self) -> OffsetKind {
+        match self {
+            Self::PointyTop => OffsetKind::OddR,
+            Self::FlatTop => OffsetKind::OddQ,
+        }
+    }
+}
+
+/// Offset coordinates, which number the hexes like a grid. The maps only
+/// use the odd layouts, the even ones are kept for the tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetKind {
+    /// Rows of pointy-top hexes, the odd rows pushed right
+    OddR,
+    /// Rows of pointy-top hexes, the even rows pushed right
+    #[cfg(test)]
+    EvenR,
+    /// Columns of flat-top hexes, the odd columns pushed up
+    OddQ,
+    /// Columns of flat-top hexes, the even columns pushed up
+    #[cfg(test)]
+    EvenQ,
+}
+
+/// A hex on an endless grid, kept in cube coordinates. The rows go up, so
+/// the coordinates always add up to `x - y + z = 0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hex {
+    cube: IVec3,
 }
 
 impl Hash for Hex {
@@ -18,9 +72,23 @@ impl Hash for Hex {
     }
 }
 
-impl PartialEq for Hex {
-    fn eq(&self, other: &Self) -> bool {
-        self.to_oddr().eq(&other.to_oddr())
+impl Add for Hex {
+    type Output = Hex;
+
+    fn add(self, rhs: Hex) -> Hex {
+        Hex {
+            cube: self.cube + rhs.cube,
+        }
+    }
+}
+
+impl Sub for Hex {
+    type Output = Hex;
+
+    fn sub(self, rhs: Hex) -> Hex {
+        Hex {
+            cube: self.cube - rhs.cube,
+        }
     }
 }
 
@@ -29,42 +97,216 @@ fn lerp(a: f32, b: f32, t: f32) -> f32 {
 }
 
 impl Hex {
+    pub const ZERO: Hex = Hex { cube: IVec3::ZERO };
+
     pub fn from_oddr(pos: IVec2) -> Self {
-        Self::Oddr(pos)
+        Self::from_offset(pos, OffsetKind::OddR)
     }
 
-    #[allow(dead_code)]
+    pub fn to_oddr(self) -> IVec2 {
+        self.to_offset(OffsetKind::OddR)
+    }
+
+    /// Takes the `x` and `y` of the cube coordinates, `z` follows from them
+    #[cfg(test)]
     pub fn from_cube(pos: IVec2) -> Self {
-        Self::Cube(IVec3::new(pos.x, pos.y, -pos.x + pos.y))
+        Self::from_axial(pos)
     }
 
-    #[allow(dead_code)]
-    pub fn to_oddr(self) -> IVec2 {
-        match self {
-            Self::Oddr(pos) => pos,
-            Self::Cube(pos) => {
-                let x = pos.x - (pos.y + (pos.y & 1)) / 2;
-                let y = pos.y;
-                IVec2::new(x, y)
+    pub fn to_cube(self) -> IVec3 {
+        self.cube
+    }
+
+    /// Axial coordinates are the cube coordinates without the redundant `z`
+    pub fn from_axial(pos: IVec2) -> Self {
+        Self {
+            cube: IVec3::new(pos.x, pos.y, pos.y - pos.x),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn to_axial(self) -> IVec2 {
+        self.cube.truncate()
+    }
+
+    pub fn from_offset(pos: IVec2, kind: OffsetKind) -> Self {
+        let (col, row) = (pos.x, pos.y);
+        match kind {
+            OffsetKind::OddR => Self::from_axial(IVec2::new(col + (row + (row & 1)) / 2, row)),
+            #[cfg(test)]
+            OffsetKind::EvenR => Self::from_axial(IVec2::new(col + (row - (row & 1)) / 2, row)),
+            OffsetKind::OddQ => {
+                let y = row - (col - (col & 1)) / 2;
+                Self::from_axial(IVec2::new(col + y, y))
+            }
+            #[cfg(test)]
+            OffsetKind::EvenQ => {
+                let y = row - (col + (col & 1)) / 2;
+                Self::from_axial(IVec2::new(col + y, y))
             }
         }
     }
 
-    pub fn to_cube(self) -> IVec3 {
-        match self {
-            Self::Cube(pos) => pos,
-            Self::Oddr(pos) => {
-                let q = pos.x + (pos.y + (pos.y & 1)) / 2;
-                let r = pos.y;
-                IVec3::new(q, r, -q + r)
+    pub fn to_offset(self, kind: OffsetKind) -> IVec2 {
+        let IVec3 { x, y, .. } = self.cube;
+        match kind {
+            OffsetKind::OddR => IVec2::new(x - (y + (y & 1)) / 2, y),
+            #[cfg(test)]
+            OffsetKind::EvenR => IVec2::new(x - (y - (y & 1)) / 2, y),
+            OffsetKind::OddQ => {
+                let col = x - y;
+                IVec2::new(col, y + (col - (col & 1)) / 2)
+            }
+            #[cfg(test)]
+            OffsetKind::EvenQ => {
+                let col = x - y;
+                IVec2::new(col, y + (col + (col & 1)) / 2)
             }
         }
     }
 
+    /// Doubled coordinates count in half hexes along the rows of pointy-top
+    /// hexes, or along the columns of flat-top ones
+    #[cfg(test)]
+    pub fn from_doubled(pos: IVec2, orientation: HexOrientation) -> Self {
+        match orientation {
+            HexOrientation::PointyTop => Self::from_axial(IVec2::new((pos.x + pos.y) / 2, pos.y)),
+            HexOrientation::FlatTop => {
+                Self::from_axial(IVec2::new((pos.x + pos.y) / 2, (pos.y - pos.x) / 2))
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub fn to_doubled(self, orientation: HexOrientation) -> IVec2 {
+        let IVec3 { x, y, .. } = self.cube;
+        match orientation {
+            HexOrientation::PointyTop => IVec2::new(2 * x - y, y),
+            HexOrientation::FlatTop => IVec2::new(x - y, x + y),
+        }
+    }
+
+    /// Centre of the hex, with the centre of the origin at zero and `size`
+    /// from the centre to the corners
+    pub fn to_pixel(self, orientation: HexOrientation, size: f32) -> Vec2 {
+        let x = self.cube.x as f32;
+        let y = self.cube.y as f32;
+        let pos = match orientation {
+            HexOrientation::PointyTop => Vec2::new(3.0f32.sqrt() * (x - 0.5 * y), 1.5 * y),
+            HexOrientation::FlatTop => Vec2::new(1.5 * (x - y), 0.5 * 3.0f32.sqrt() * (x + y)),
+        };
+        pos * size
+    }
+
+    /// The hex containing the point, the opposite of `to_pixel`
+    pub fn from_pixel(pos: Vec2, orientation: HexOrientation, size: f32) -> Self {
+        let pos = pos / size;
+        let (x, y) = match orientation {
+            HexOrientation::PointyTop => {
+                let y = pos.y / 1.5;
+                (pos.x / 3.0f32.sqrt() + 0.5 * y, y)
+            }
+            HexOrientation::FlatTop => {
+                let col = pos.x / 1.5;
+                let doubled_row = 2.0 * pos.y / 3.0f32.sqrt();
+                (0.5 * (doubled_row + col), 0.5 * (doubled_row - col))
+            }
+        };
+        Self::cube_round(Vec3::new(x, y, y - x))
+    }
+
+    /// The neighbour in one of the six directions, counterclockwise starting
+    /// to the right of pointy-top hexes
+    pub fn neighbour(self, direction: usize) -> Hex {
+        Hex {
+            cube: self.cube + DIRECTIONS[direction % 6],
+        }
+    }
+
+    pub fn neighbours(self) -> [Hex; 6] {
+        std::array::from_fn(|direction| self.neighbour(direction))
+    }
+
     pub fn dist(&self, hex: Hex) -> i32 {
         manhattan_distance(&self.to_cube(), &hex.to_cube()) / 2
     }
 
+    /// Hexes exactly `radius` away, going around counterclockwise
+    pub fn ring(self, radius: i32) -> Vec<Hex> {
+        if radius <= 0 {
+            return vec![self];
+        }
+
+        let mut hex = Hex {
+            cube: self.cube + DIRECTIONS[4] * radius,
+        };
+        let mut ring = Vec::with_capacity(6 * radius as usize);
+        for direction in 0..6 {
+            for _ in 0..radius {
+                ring.push(hex);
+                hex = hex.neighbour(direction);
+            }
+        }
+        ring
+    }
+
+    /// Hexes at most `radius` away, ring by ring going outwards
+    pub fn spiral(self, radius: i32) -> Vec<Hex> {
+        (0..=radius).flat_map(|r| self.ring(r)).collect()
+    }
+
+    /// Hexes at most `radius` away
+    pub fn range(self, radius: i32) -> Vec<Hex> {
+        Self::range_intersection(&[(self, radius)])
+    }
+
+    /// Hexes within reach of all the given centres, each with its radius
+    pub fn range_intersection(ranges: &[(Hex, i32)]) -> Vec<Hex> {
+        let Some(&(first, radius)) = ranges.first() else {
+            return vec![];
+        };
+
+        let mut min = first.cube - IVec3::splat(radius);
+        let mut max = first.cube + IVec3::splat(radius);
+        for &(center, radius) in &ranges[1..] {
+            min = min.max(center.cube - IVec3::splat(radius));
+            max = max.min(center.cube + IVec3::splat(radius));
+        }
+
+        let mut hexes = vec![];
+        for x in min.x..=max.x {
+            // x - y + z = 0, so z bounds y too
+            for y in min.y.max(x + min.z)..=max.y.min(x + max.z) {
+                hexes.push(Self::from_axial(IVec2::new(x, y)));
+            }
+        }
+        hexes
+    }
+
+    /// Turns the hex around `center` by sixths of a turn, counterclockwise
+    /// for positive `steps`
+    #[cfg(test)]
+    pub fn rotate_around(self, center: Hex, steps: i32) -> Hex {
+        let mut cube = (self - center).cube;
+        for _ in 0..steps.rem_euclid(6) {
+            cube = IVec3::new(-cube.z, cube.x, cube.y);
+        }
+        Hex { cube } + center
+    }
+
+    /// Mirrors the hex over the line through `center` going in `direction`,
+    /// as used by `neighbour`
+    #[cfg(test)]
+    pub fn reflect_around(self, center: Hex, direction: usize) -> Hex {
+        // turn the line to the right, mirror over it and turn it back
+        let steps = (direction % 6) as i32;
+        let cube = (self - center).rotate_around(Hex::ZERO, -steps).cube;
+        let mirrored = Hex {
+            cube: IVec3::new(-cube.z, -cube.y, -cube.x),
+        };
+        mirrored.rotate_around(Hex::ZERO, steps) + center
+    }
+
     fn cube_lerp(a: Hex, b: Hex, t: f32) -> Vec3 {
         let a = a.to_cube();
         let b = b.to_cube();
@@ -77,34 +319,121 @@ impl Hex {
     }
 
     fn cube_round(frac: Vec3) -> Hex {
-        let mut qrs = frac.round();
-        let qrs_diff = (qrs - frac).abs();
+        let mut xyz = frac.round();
+        let xyz_diff = (xyz - frac).abs();
 
-        if qrs_diff.x > -qrs_diff.y && qrs_diff.x > qrs_diff.z {
-            qrs.x = qrs.y - qrs.z;
-        } else if -qrs_diff.y > qrs_diff.z {
-            qrs.y = qrs.x + qrs.z;
+        // the coordinate rounded the most follows from the other two
+        if xyz_diff.x > xyz_diff.y && xyz_diff.x > xyz_diff.z {
+            xyz.x = xyz.y - xyz.z;
+        } else if xyz_diff.y > xyz_diff.z {
+            xyz.y = xyz.x + xyz.z;
         }
 
-        Hex::from_cube((qrs.x as i32, qrs.y as i32).into())
+        Hex::from_axial((xyz.x as i32, xyz.y as i32).into())
     }
 
     pub fn line(self, end: Hex) -> Vec<Hex> {
         let n = self.dist(end);
+        if n == 0 {
+            return vec![self];
+        }
+
         (0..=n)
             .map(|i| Self::cube_round(Self::cube_lerp(self, end, i as f32 / n as f32)))
             .collect()
     }
+
+    /// Steps it takes to walk from this hex to every hex it can reach,
+    /// going from neighbour to neighbour over passable hexes only
+    pub fn walk_distances(self, passable: impl Fn(Hex) -> bool) -> HashMap<Hex, i32> {
+        let mut dists = HashMap::from_iter([(self, 0)]);
+        let mut queue = VecDeque::from([self]);
+        while let Some(hex) = queue.pop_front() {
+            let dist = dists[&hex];
+            for next in hex.neighbours() {
+                if passable(next) && !dists.contains_key(&next) {
+                    dists.insert(next, dist + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        dists
+    }
+
+    /// Shortest walk from this hex to `end` over passable hexes, with both
+    /// ends. Takes the straight line where nothing is in the way.
+    pub fn walk_path(self, end: Hex, passable: impl Fn(Hex) -> bool) -> Option<Vec<Hex>> {
+        let line = self.line(end);
+        if line.iter().skip(1).all(|hex| passable(*hex)) {
+            return Some(line);
+        }
+
+        let mut came_from = HashMap::from_iter([(self, self)]);
+        let mut queue = VecDeque::from([self]);
+        while let Some(hex) = queue.pop_front() {
+            if hex == end {
+                let mut path = vec![end];
+                while *path.last().unwrap() != self {
+                    path.push(came_from[path.last().unwrap()]);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in hex.neighbours() {
+                if passable(next) && !came_from.contains_key(&next) {
+                    came_from.insert(next, hex);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn walks_go_around_gaps() {
+        // a wall between the ends, with a way around at the top
+        let wall = [(0, -1), (0, 0), (0, 1)]
+            .map(|pos| Hex::from_axial(pos.into()))
+            .to_vec();
+        let passable = |hex: Hex| hex.dist(Hex::ZERO) <= 3 && !wall.contains(&hex);
+        let from = Hex::from_axial((-1, 0).into());
+        let to = Hex::from_axial((1, 0).into());
+
+        let path = from.walk_path(to, passable).unwrap();
+        assert_eq!((path[0], *path.last().unwrap()), (from, to));
+        assert!(path.windows(2).all(|step| step[0].dist(step[1]) == 1));
+        assert!(path.iter().all(|hex| !wall.contains(hex)));
+        assert_eq!(from.walk_distances(passable)[&to], path.len() as i32 - 1);
+        assert!(path.len() > 3);
+
+        assert_eq!(
+            from.walk_path(from.neighbour(3), passable).unwrap().len(),
+            2
+        );
+        assert!(from.walk_path(Hex::ZERO, passable).is_none());
+    }
+
     const ODDR: [(i32, i32); 5] = [(0, 0), (0, 2), (2, 0), (1, 1), (2, 1)];
     const CUBE: [(i32, i32, i32); 5] = [(0, 0, 0), (1, 2, 1), (2, 0, -2), (2, 1, -1), (3, 1, -2)];
     // 1v1, 1v2, .. 1v5, 2v2, 2v3, .. 2v5, etc.
     const DISTS: [i32; 15] = [0, 2, 2, 2, 3, 0, 3, 2, 3, 0, 1, 1, 0, 1, 0];
+    const OFFSET_KINDS: [OffsetKind; 4] = [
+        OffsetKind::OddR,
+        OffsetKind::EvenR,
+        OffsetKind::OddQ,
+        OffsetKind::EvenQ,
+    ];
+    const ORIENTATIONS: [HexOrientation; 2] = [HexOrientation::PointyTop, HexOrientation::FlatTop];
+
+    /// Every hex within a few steps of the origin, to check properties on
+    fn some_hexes() -> Vec<Hex> {
+        Hex::ZERO.spiral(6)
+    }
 
     #[test]
     fn convert_from_oddr_to_cube() {
@@ -127,6 +456,77 @@ mod tests {
     }
 
     #[test]
+    fn coordinates_round_trip() {
+        for hex in some_hexes() {
+            assert_eq!(Hex::from_axial(hex.to_axial()), hex);
+            for kind in OFFSET_KINDS {
+                assert_eq!(Hex::from_offset(hex.to_offset(kind), kind), hex, "{kind:?}");
+            }
+            for orientation in ORIENTATIONS {
+                assert_eq!(
+                    Hex::from_doubled(hex.to_doubled(orientation), orientation),
+                    hex,
+                    "{orientation:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn offset_neighbours_are_adjacent() {
+        // pushed rows and columns touch the next one at the same index and
+        // the one after it
+        let pairs = [
+            (OffsetKind::OddR, (0, 0), (0, 1)),
+            (OffsetKind::OddR, (1, 1), (2, 2)),
+            (OffsetKind::EvenR, (0, 0), (1, 1)),
+            (OffsetKind::EvenR, (0, 1), (0, 2)),
+            (OffsetKind::OddQ, (1, 0), (0, 1)),
+            (OffsetKind::OddQ, (1, 1), (2, 2)),
+            (OffsetKind::EvenQ, (0, 0), (1, 1)),
+            (OffsetKind::EvenQ, (1, 1), (2, 0)),
+        ];
+        for (kind, a, b) in pairs {
+            let a = Hex::from_offset(a.into(), kind);
+            let b = Hex::from_offset(b.into(), kind);
+            assert_eq!(a.dist(b), 1, "{kind:?} {a:?} {b:?}");
+        }
+    }
+
+    #[test]
+    fn pixels_round_trip() {
+        for orientation in ORIENTATIONS {
+            for hex in some_hexes() {
+                let center = hex.to_pixel(orientation, 30.0);
+                assert_eq!(Hex::from_pixel(center, orientation, 30.0), hex);
+                // anywhere well inside the hex, not just its centre
+                for corner in 0..6 {
+                    let toward = Vec2::from_angle(corner as f32 * std::f32::consts::PI / 3.0);
+                    let pos = center + toward * 20.0;
+                    assert_eq!(Hex::from_pixel(pos, orientation, 30.0), hex);
+                }
+
+                for neighbour in hex.neighbours() {
+                    let spacing = neighbour.to_pixel(orientation, 30.0).distance(center);
+                    assert!((spacing - 30.0 * 3.0f32.sqrt()).abs() < 1e-3);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pointy_top_pixels_match_odd_rows() {
+        let hex = Hex::from_oddr((2, 1).into());
+        let expected = Vec2::new(2.5 * 3.0f32.sqrt(), 1.5);
+        assert!(
+            hex.to_pixel(HexOrientation::PointyTop, 1.0)
+                .distance(expected)
+                < 1e-5
+        );
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
     fn distance_oddr() {
         let mut z = 0;
         for i in 0..ODDR.len() {
@@ -145,6 +545,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::needless_range_loop)]
     fn distance_cube() {
         let mut z = 0;
         for i in 0..CUBE.len() {
@@ -164,6 +565,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn neighbours_go_around() {
+        let center = Hex::from_oddr((3, 2).into());
+        let neighbours = center.neighbours();
+        for (i, neighbour) in neighbours.iter().enumerate() {
+            assert_eq!(center.dist(*neighbour), 1);
+            assert_eq!(neighbour.dist(neighbours[(i + 1) % 6]), 1);
+        }
+        assert_eq!(center.neighbour(0), Hex::from_oddr((4, 2).into()));
+    }
+
+    #[test]
+    fn rings_and_spirals() {
+        let center = Hex::from_oddr((1, 1).into());
+        for radius in 0..5 {
+            let ring = center.ring(radius);
+            assert_eq!(ring.len(), (6 * radius).max(1) as usize);
+            assert!(ring.iter().all(|hex| center.dist(*hex) == radius));
+
+            let mut spiral = center.spiral(radius);
+            assert_eq!(spiral.len(), (3 * radius * (radius + 1) + 1) as usize);
+            let mut range = center.range(radius);
+            spiral.sort_by_key(|hex| hex.to_axial().to_array());
+            range.sort_by_key(|hex| hex.to_axial().to_array());
+            assert_eq!(spiral, range);
+        }
+    }
+
+    #[test]
+    fn range_intersection_is_within_every_range() {
+        let a = (Hex::from_oddr((0, 0).into()), 3);
+        let b = (Hex::from_oddr((4, 1).into()), 2);
+        let intersection = Hex::range_intersection(&[a, b]);
+
+        let expected = some_hexes()
+            .into_iter()
+            .filter(|hex| hex.dist(a.0) <= a.1 && hex.dist(b.0) <= b.1)
+            .count();
+        assert_eq!(intersection.len(), expected);
+        assert!(intersection
+            .iter()
+            .all(|hex| hex.dist(a.0) <= a.1 && hex.dist(b.0) <= b.1));
+
+        let far = (Hex::from_oddr((20, 0).into()), 1);
+        assert!(Hex::range_intersection(&[a, far]).is_empty());
+    }
+
+    #[test]
+    fn rotation_and_reflection_keep_distances() {
+        let center = Hex::from_oddr((1, 2).into());
+        for hex in some_hexes() {
+            assert_eq!(hex.rotate_around(center, 6), hex);
+            assert_eq!(hex.rotate_around(center, 2).rotate_around(center, -2), hex);
+            for direction in 0..6 {
+                let mirrored = hex.reflect_around(center, direction);
+                assert_eq!(mirrored.reflect_around(center, direction), hex);
+                assert_eq!(mirrored.dist(center), hex.dist(center));
+            }
+            for steps in 0..6 {
+                assert_eq!(
+                    hex.rotate_around(center, steps).dist(center),
+                    hex.dist(center)
+                );
+            }
+        }
+
+        // a sixth of a turn moves each neighbour to the next one
+        for direction in 0..6 {
+            let neighbour = center.neighbour(direction);
+            assert_eq!(
+                neighbour.rotate_around(center, 1),
+                center.neighbour(direction + 1)
+            );
+            assert_eq!(neighbour.reflect_around(center, direction), neighbour);
+        }
+        assert_eq!(
+            center.neighbour(1).reflect_around(center, 0),
+            center.neighbour(5)
+        );
+    }
+
     #[test]
     fn line_draw() {
         let start = Hex::from_oddr((0, 0).into());
@@ -176,5 +658,18 @@ mod tests {
             .collect::<Vec<_>>();
 
         assert_eq!(line, expected);
+        assert_eq!(start.line(start), vec![start]);
+    }
+
+    #[test]
+    fn lines_are_connected() {
+        let start = Hex::from_oddr((-2, 1).into());
+        for end in some_hexes() {
+            let line = start.line(end);
+            assert_eq!(line.len() as i32, start.dist(end) + 1);
+            assert_eq!(line.first(), Some(&start));
+            assert_eq!(line.last(), Some(&end));
+            assert!(line.windows(2).all(|pair| pair[0].dist(pair[1]) == 1));
+        }
     }
 }